@@ -1,5 +1,6 @@
 use std::sync::Arc;
 
+use rook_lw_image_repo::event::EventRepository;
 use rook_lw_image_repo::image_info::ImageInfoRepository;
 use rook_lw_image_repo::image_store::ImageStoreRepository;
 
@@ -9,6 +10,7 @@ use crate::services::DaemonService;
 pub struct AppState {
     pub admin_static_dir: String,
     pub image_info_repo: Arc<Box<dyn ImageInfoRepository>>,
+    pub event_repo: Arc<Box<dyn EventRepository>>,
     pub image_store_repo: Arc<Box<dyn ImageStoreRepository>>,
     pub daemon_service: Arc<DaemonService>,
 }
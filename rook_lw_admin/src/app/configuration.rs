@@ -3,6 +3,7 @@ use crate::app::AppState;
 use crate::services::DaemonService;
 
 use rook_lw_image_repo::sqlite::create_pool;
+use rook_lw_image_repo::event::{EventRepository, EventRepositorySqlite};
 use rook_lw_image_repo::image_info::{ImageInfoRepository, ImageInfoRepositorySqlite};
 use rook_lw_image_repo::image_store::{ImageStoreRepository, ImageStoreRepositoryFile};
 
@@ -10,15 +11,23 @@ use std::sync::Arc;
 use r2d2_sqlite::SqliteConnectionManager;
 use r2d2::Pool;
 
-pub fn create_app(var_dir: &str, admin_dir: &str, app_dir: &str) -> RookLWAdminResult<AppState> {
+pub fn create_app(
+    var_dir: &str,
+    admin_dir: &str,
+    app_dir: &str,
+    daemon_metrics_address: &str,
+    daemon_control_socket: &str,
+) -> RookLWAdminResult<AppState> {
     let sqlite_pool = create_sqlite_pool(var_dir)?;
-    let image_info_repo = create_image_info_repository(sqlite_pool)?;
+    let image_info_repo = create_image_info_repository(sqlite_pool.clone())?;
+    let event_repo = create_event_repository(sqlite_pool)?;
 
     let app = AppState {
         admin_static_dir: admin_dir.to_string(),
         image_info_repo: Arc::new(image_info_repo),
+        event_repo: Arc::new(event_repo),
         image_store_repo: Arc::new(create_image_store_repository(var_dir)?),
-        daemon_service: Arc::new(create_daemon_service(app_dir)?),
+        daemon_service: Arc::new(create_daemon_service(app_dir, daemon_metrics_address, daemon_control_socket)?),
     };
 
     Ok(app)
@@ -38,6 +47,14 @@ fn create_image_info_repository(pool: Pool<SqliteConnectionManager>) -> RookLWAd
     Ok(Box::new(repo))
 }
 
+fn create_event_repository(pool: Pool<SqliteConnectionManager>) -> RookLWAdminResult<Box<dyn EventRepository>> {
+    let repo = EventRepositorySqlite::new(
+        pool
+    )?;
+
+    Ok(Box::new(repo))
+}
+
 fn create_image_store_repository(var_dir: &str) -> RookLWAdminResult<Box<dyn ImageStoreRepository>> {
     let images_path = format!("{}/images", var_dir);
     let repo = ImageStoreRepositoryFile::new(
@@ -47,6 +64,6 @@ fn create_image_store_repository(var_dir: &str) -> RookLWAdminResult<Box<dyn Ima
     Ok(Box::new(repo))
 }
 
-fn create_daemon_service(app_dir: &str) -> RookLWAdminResult<DaemonService> {
-    DaemonService::new(app_dir)
+fn create_daemon_service(app_dir: &str, metrics_address: &str, control_socket_path: &str) -> RookLWAdminResult<DaemonService> {
+    DaemonService::new(app_dir, metrics_address, control_socket_path)
 }
\ No newline at end of file
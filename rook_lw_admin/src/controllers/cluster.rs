@@ -0,0 +1,59 @@
+use actix_web::{Responder, HttpResponse, web};
+use actix_web::web::ServiceConfig;
+use serde_qs::actix::QsQuery;
+use tokio::task::spawn_blocking;
+
+use rook_lw_image_repo::ImageRepoResult;
+use rook_lw_models::image::{EmbeddingClusterSummary, ImageInfoSearchOptions};
+use crate::RookLWAdminError;
+use crate::app::AppState;
+
+pub async fn list_clusters(
+    state: web::Data<AppState>,
+) -> Result<impl Responder, RookLWAdminError> {
+    let repo = state.image_info_repo.clone();
+    let clusters = spawn_blocking(move || -> ImageRepoResult<Vec<EmbeddingClusterSummary>> {
+        let mut summaries = Vec::new();
+        for cluster in repo.get_embedding_clusters()? {
+            let representative_image = match &cluster.representative_image_id {
+                Some(image_id) => repo.get_image_info(image_id)?,
+                None => None,
+            };
+            summaries.push(EmbeddingClusterSummary {
+                cluster_id: cluster.cluster_id,
+                member_count: cluster.member_count,
+                representative_image,
+                created_timestamp: cluster.created_timestamp,
+                updated_timestamp: cluster.updated_timestamp,
+            });
+        }
+        summaries.sort_by_key(|summary| std::cmp::Reverse(summary.member_count));
+        Ok(summaries)
+    }).await??;
+    Ok(HttpResponse::Ok()
+        .insert_header(("Cache-Control", "public, max-age=60"))
+        .json(clusters))
+}
+
+pub async fn get_cluster_images(
+    state: web::Data<AppState>,
+    cluster_id: web::Path<u32>,
+    query: QsQuery<ImageInfoSearchOptions>,
+) -> Result<impl Responder, RookLWAdminError> {
+    let repo = state.image_info_repo.clone();
+    let options = ImageInfoSearchOptions {
+        cluster_id: Some(cluster_id.into_inner()),
+        ..query.into_inner()
+    };
+    let image_info = spawn_blocking(move || {
+        repo.search_image_info(&options)
+    }).await??;
+    Ok(HttpResponse::Ok()
+        .insert_header(("Cache-Control", "public, max-age=60"))
+        .json(image_info))
+}
+
+pub fn register(sc: &mut ServiceConfig) {
+    sc.route("/api/cluster", web::get().to(list_clusters));
+    sc.route("/api/cluster/{cluster_id}/images", web::get().to(get_cluster_images));
+}
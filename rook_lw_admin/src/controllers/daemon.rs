@@ -35,8 +35,93 @@ pub async fn daemon_stop(state: web::Data<AppState>)
     Ok(HttpResponse::Ok().json(Status { message: status, ..Default::default() }))
 }
 
+pub async fn daemon_metrics(state: web::Data<AppState>)
+    -> Result<impl Responder, RookLWAdminError>
+{
+    let metrics = spawn_blocking(move || state.daemon_service.get_metrics()).await??;
+
+    match metrics {
+        Some(metrics) => Ok(HttpResponse::Ok()
+            .content_type("text/plain; version=0.0.4")
+            .body(metrics)),
+        None => Ok(HttpResponse::ServiceUnavailable().json(Status {
+            message: "Daemon metrics not available.".into(),
+            ..Default::default()
+        }))
+    }
+}
+
+/// Status reported by the daemon itself: stage health, configuration and last event.
+pub async fn daemon_state(state: web::Data<AppState>)
+    -> Result<impl Responder, RookLWAdminError>
+{
+    let status = spawn_blocking(move || state.daemon_service.get_daemon_status()).await??;
+
+    match status {
+        Some(status) => Ok(HttpResponse::Ok().json(status)),
+        None => Ok(control_unavailable()),
+    }
+}
+
+pub async fn daemon_pause(state: web::Data<AppState>)
+    -> Result<impl Responder, RookLWAdminError>
+{
+    let message = spawn_blocking(move || state.daemon_service.pause()).await??;
+    Ok(control_response(message))
+}
+
+pub async fn daemon_resume(state: web::Data<AppState>)
+    -> Result<impl Responder, RookLWAdminError>
+{
+    let message = spawn_blocking(move || state.daemon_service.resume()).await??;
+    Ok(control_response(message))
+}
+
+pub async fn daemon_trigger(state: web::Data<AppState>)
+    -> Result<impl Responder, RookLWAdminError>
+{
+    let message = spawn_blocking(move || state.daemon_service.trigger()).await??;
+    Ok(control_response(message))
+}
+
+/// The snapshot is stored with the images; the message is its path there.
+pub async fn daemon_snapshot(state: web::Data<AppState>)
+    -> Result<impl Responder, RookLWAdminError>
+{
+    let image_path = spawn_blocking(move || state.daemon_service.snapshot()).await??;
+    Ok(control_response(image_path))
+}
+
+pub async fn daemon_reload(state: web::Data<AppState>)
+    -> Result<impl Responder, RookLWAdminError>
+{
+    let message = spawn_blocking(move || state.daemon_service.reload_config()).await??;
+    Ok(control_response(message))
+}
+
+fn control_response(message: Option<String>) -> HttpResponse {
+    match message {
+        Some(message) => HttpResponse::Ok().json(Status { message, ..Default::default() }),
+        None => control_unavailable(),
+    }
+}
+
+fn control_unavailable() -> HttpResponse {
+    HttpResponse::ServiceUnavailable().json(Status {
+        message: "Daemon control socket not available.".into(),
+        ..Default::default()
+    })
+}
+
 pub fn register(sc: &mut ServiceConfig) {
     sc.route("/api/daemon/status", web::get().to(daemon_status));
     sc.route("/api/daemon/stop", web::post().to(daemon_stop));
     sc.route("/api/daemon/start", web::post().to(daemon_start));
+    sc.route("/api/daemon/metrics", web::get().to(daemon_metrics));
+    sc.route("/api/daemon/state", web::get().to(daemon_state));
+    sc.route("/api/daemon/pause", web::post().to(daemon_pause));
+    sc.route("/api/daemon/resume", web::post().to(daemon_resume));
+    sc.route("/api/daemon/trigger", web::post().to(daemon_trigger));
+    sc.route("/api/daemon/snapshot", web::post().to(daemon_snapshot));
+    sc.route("/api/daemon/reload", web::post().to(daemon_reload));
 }
\ No newline at end of file
@@ -0,0 +1,88 @@
+use actix_web::{Responder, HttpResponse, web};
+use actix_web::web::ServiceConfig;
+use serde_qs::actix::QsQuery;
+use tokio::task::spawn_blocking;
+
+use rook_lw_models::event::{EventSearchOptions, EventSummary};
+use crate::RookLWAdminError;
+use crate::app::AppState;
+
+pub async fn search_events(
+    state: web::Data<AppState>,
+    query: QsQuery<EventSearchOptions>,
+) -> Result<impl Responder, RookLWAdminError> {
+    let repo = state.event_repo.clone();
+    let events = spawn_blocking(move || {
+        repo.search_events(&query)
+    }).await??;
+    Ok(HttpResponse::Ok()
+        .insert_header(("Cache-Control", "public, max-age=60"))
+        .json(events))
+}
+
+pub async fn get_event(
+    state: web::Data<AppState>,
+    event_id: web::Path<String>,
+) -> Result<impl Responder, RookLWAdminError> {
+    let repo = state.event_repo.clone();
+    let event_id = event_id.into_inner();
+    let event = spawn_blocking(move || {
+        repo.get_event(&event_id)
+    }).await??;
+    match event {
+        Some(event) => Ok(HttpResponse::Ok()
+            .insert_header(("Cache-Control", "public, max-age=60"))
+            .json(event)),
+        None => Ok(HttpResponse::NotFound().json(serde_json::json!({"error": "Event not found"}))),
+    }
+}
+
+/// The image info of the best frame of the event.
+pub async fn get_event_hero(
+    state: web::Data<AppState>,
+    event_id: web::Path<String>,
+) -> Result<impl Responder, RookLWAdminError> {
+    let event_repo = state.event_repo.clone();
+    let image_info_repo = state.image_info_repo.clone();
+    let event_id = event_id.into_inner();
+    let hero = spawn_blocking(move || {
+        let Some(hero_image_id) = event_repo.get_event(&event_id)?.and_then(|event| event.hero_image_id) else {
+            return Ok(None);
+        };
+        image_info_repo.get_image_info(&hero_image_id)
+    }).await??;
+    match hero {
+        Some(image_info) => Ok(HttpResponse::Ok()
+            .insert_header(("Cache-Control", "public, max-age=60"))
+            .json(image_info)),
+        None => Ok(HttpResponse::NotFound().json(serde_json::json!({"error": "Hero image not found"}))),
+    }
+}
+
+pub async fn get_event_summary(
+    state: web::Data<AppState>,
+    event_id: web::Path<String>,
+) -> Result<impl Responder, RookLWAdminError> {
+    let repo = state.image_info_repo.clone();
+    let event_id = event_id.into_inner();
+    let (event_id, images) = spawn_blocking(move || {
+        let images = repo.get_event_image_info(&event_id);
+        (event_id, images)
+    }).await?;
+    let images = images?;
+
+    if images.is_empty() {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({"error": "Event not found"})));
+    }
+
+    Ok(HttpResponse::Ok()
+        .insert_header(("Cache-Control", "public, max-age=60"))
+        .json(EventSummary::from_image_infos(event_id, &images)))
+}
+
+pub fn register(sc: &mut ServiceConfig) {
+    sc.route("/api/event", web::get().to(search_events));
+    sc.route("/api/event/{event_id}", web::get().to(get_event));
+    sc.route("/api/event/{event_id}/hero", web::get().to(get_event_hero));
+    sc.route("/api/event/{event_id}/summary", web::get().to(get_event_summary));
+}
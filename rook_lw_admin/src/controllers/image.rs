@@ -11,7 +11,7 @@ use tracing::error;
 
 use std::io::Read;
 
-use rook_lw_models::image::ImageInfoSearchOptions;
+use rook_lw_models::image::{Detection, ImageInfoSearchOptions, SimilaritySearchOptions};
 use crate::RookLWAdminError;
 use crate::app::AppState;
 
@@ -58,6 +58,62 @@ pub async fn get_image_info_by_id(
     }
 }
 
+pub async fn find_similar_images(
+    state: web::Data<AppState>,
+    image_id: web::Path<String>,
+    query: QsQuery<SimilaritySearchOptions>,
+) -> Result<impl Responder, RookLWAdminError> {
+    let repo = state.image_info_repo.clone();
+    let image_id = image_id.into_inner();
+    let result = spawn_blocking(move || {
+        match repo.get_image_info(&image_id)? {
+            Some(_) => repo.find_similar_image_info(&image_id, &query).map(Some),
+            None => Ok(None),
+        }
+    }).await??;
+    match result {
+        Some(similar_images) => Ok(HttpResponse::Ok()
+            .insert_header(("Cache-Control", "public, max-age=60"))
+            .json(similar_images)),
+        None => Ok(HttpResponse::NotFound().json(serde_json::json!({"error": "Image not found"}))),
+    }
+}
+
+/// Store the detections of an image as checked by a person.
+pub async fn set_verified_detections(
+    state: web::Data<AppState>,
+    image_id: web::Path<String>,
+    detections: web::Json<Vec<Detection>>,
+) -> Result<impl Responder, RookLWAdminError> {
+    let repo = state.image_info_repo.clone();
+    let image_id = image_id.into_inner();
+    let detections = detections.into_inner();
+    let found = spawn_blocking(move || {
+        repo.set_verified_detections(&image_id, Some(&detections))
+    }).await??;
+    if found {
+        Ok(HttpResponse::NoContent().finish())
+    } else {
+        Ok(HttpResponse::NotFound().json(serde_json::json!({"error": "Image not found"})))
+    }
+}
+
+pub async fn clear_verified_detections(
+    state: web::Data<AppState>,
+    image_id: web::Path<String>,
+) -> Result<impl Responder, RookLWAdminError> {
+    let repo = state.image_info_repo.clone();
+    let image_id = image_id.into_inner();
+    let found = spawn_blocking(move || {
+        repo.set_verified_detections(&image_id, None)
+    }).await??;
+    if found {
+        Ok(HttpResponse::NoContent().finish())
+    } else {
+        Ok(HttpResponse::NotFound().json(serde_json::json!({"error": "Image not found"})))
+    }
+}
+
 pub async fn get_image(
     state: web::Data<AppState>,
     path: web::Path<String>,
@@ -108,5 +164,8 @@ pub async fn get_image(
 pub fn register(sc: &mut ServiceConfig) {
     sc.route("/api/image_info", web::get().to(search_image_info));
     sc.route("/api/image_info/{image_id}", web::get().to(get_image_info_by_id));
+    sc.route("/api/image_info/{image_id}/similar", web::get().to(find_similar_images));
+    sc.route("/api/image_info/{image_id}/verified_detections", web::put().to(set_verified_detections));
+    sc.route("/api/image_info/{image_id}/verified_detections", web::delete().to(clear_verified_detections));
     sc.route("/api/image/{image_path:.*}", web::get().to(get_image));
 }
\ No newline at end of file
@@ -1,6 +1,8 @@
 pub mod admin;
+pub mod cluster;
 pub mod daemon;
 pub mod directory;
+pub mod event;
 pub mod home;
 pub mod image;
 pub mod process;
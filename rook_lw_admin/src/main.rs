@@ -36,6 +36,14 @@ struct Cli {
     /// Directory the daemon home is
     #[arg(long, default_value = ".")]
     app_dir: String,
+
+    /// Address of the daemon metrics server
+    #[arg(long, default_value = "127.0.0.1:9464")]
+    daemon_metrics_address: String,
+
+    /// Control socket of the daemon
+    #[arg(long, default_value = "var/run/rook_lw_daemon.sock")]
+    daemon_control_socket: String,
 }
 
 async fn run() -> RookLWAdminResult<()> {
@@ -74,6 +82,8 @@ async fn run() -> RookLWAdminResult<()> {
         &var_dir, 
         format!("{}/admin", &www_dir).as_str(),
         &cli.app_dir,
+        &cli.daemon_metrics_address,
+        &cli.daemon_control_socket,
     )?;
 
     let server = HttpServer::new(move || {
@@ -89,7 +99,9 @@ async fn run() -> RookLWAdminResult<()> {
             )
             .service(web::scope("")
                 .configure(controllers::admin::register)
+                .configure(controllers::cluster::register)
                 .configure(controllers::daemon::register)
+                .configure(controllers::event::register)
                 .configure(controllers::home::register)
                 .configure(controllers::image::register)
                 .configure(controllers::process::register)
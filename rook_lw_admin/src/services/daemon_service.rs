@@ -1,29 +1,110 @@
 use std::time::Duration;
 use std::{path::PathBuf, thread::sleep};
 use std::process::Command;
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
 
-use sysinfo::{System, ProcessesToUpdate, Process};
+use sysinfo::{System, ProcessesToUpdate, Process, Signal};
 use chrono::DateTime;
 use tracing::info;
 
+use rook_lw_control::{ControlClient, ControlError, ControlResult, DaemonStatus};
 use rook_lw_models::process::ProcessInfo;
 
 use crate::{RookLWAdminError, RookLWAdminResult};
 
 const DAEMON_PROCESS_NAME: &str = "rook_lw_daemon";
+const METRICS_TIMEOUT: Duration = Duration::from_secs(5);
 
 pub struct DaemonService {
-    app_dir: String
+    app_dir: String,
+    metrics_address: String,
+    control: ControlClient,
 }
 
 impl DaemonService {
 
-    pub fn new(app_dir: impl Into<String>) -> RookLWAdminResult<Self> {
+    pub fn new(
+        app_dir: impl Into<String>,
+        metrics_address: impl Into<String>,
+        control_socket_path: impl Into<PathBuf>,
+    ) -> RookLWAdminResult<Self> {
         Ok(Self {
-            app_dir: app_dir.into()
+            app_dir: app_dir.into(),
+            metrics_address: metrics_address.into(),
+            control: ControlClient::new(control_socket_path),
         })
     }
 
+    /// Status reported by the running daemon over its control socket, or None
+    /// when it is not reachable.
+    pub fn get_daemon_status(&self) -> RookLWAdminResult<Option<DaemonStatus>> {
+        Self::reachable(self.control.status())
+    }
+
+    pub fn pause(&self) -> RookLWAdminResult<Option<String>> {
+        Self::reachable(self.control.pause())
+    }
+
+    pub fn resume(&self) -> RookLWAdminResult<Option<String>> {
+        Self::reachable(self.control.resume())
+    }
+
+    pub fn trigger(&self) -> RookLWAdminResult<Option<String>> {
+        Self::reachable(self.control.trigger())
+    }
+
+    /// Path of the stored snapshot, relative to the image directory.
+    pub fn snapshot(&self) -> RookLWAdminResult<Option<String>> {
+        Self::reachable(self.control.snapshot())
+    }
+
+    pub fn reload_config(&self) -> RookLWAdminResult<Option<String>> {
+        Self::reachable(self.control.reload_config())
+    }
+
+    fn reachable<T>(result: ControlResult<T>) -> RookLWAdminResult<Option<T>> {
+        match result {
+            Ok(value) => Ok(Some(value)),
+            Err(ControlError::Unavailable(e)) => {
+                info!("Daemon control socket not reachable: {}", e);
+                Ok(None)
+            },
+            Err(e) => Err(RookLWAdminError::Other(format!("{e}"))),
+        }
+    }
+
+    /// Metrics of the daemon in Prometheus text format, or None when its
+    /// metrics server is not reachable.
+    pub fn get_metrics(&self) -> RookLWAdminResult<Option<String>> {
+        let address = self.metrics_address.to_socket_addrs()?
+            .next()
+            .ok_or_else(|| RookLWAdminError::Other(format!("Invalid metrics address: {}", self.metrics_address)))?;
+
+        let mut stream = match TcpStream::connect_timeout(&address, METRICS_TIMEOUT) {
+            Ok(stream) => stream,
+            Err(e) => {
+                info!("Daemon metrics not reachable at {}: {}", address, e);
+                return Ok(None);
+            }
+        };
+        stream.set_read_timeout(Some(METRICS_TIMEOUT))?;
+        stream.set_write_timeout(Some(METRICS_TIMEOUT))?;
+        write!(stream, "GET /metrics HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n", address)?;
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response)?;
+
+        let (head, body) = response.split_once("\r\n\r\n")
+            .ok_or_else(|| RookLWAdminError::Other("Malformed metrics response.".into()))?;
+        let status_line = head.lines().next().unwrap_or_default();
+        if status_line.split_whitespace().nth(1) != Some("200") {
+            return Err(RookLWAdminError::Other(format!("Metrics request failed: {}", status_line)));
+        }
+
+        Ok(Some(body.to_string()))
+    }
+
     pub fn start(self: &Self) -> RookLWAdminResult<ProcessInfo> {
         info!("Start requested for daemon.");
         
@@ -108,18 +189,33 @@ impl DaemonService {
 
     pub fn stop(self: &Self) -> RookLWAdminResult<String> {
         info!("Stop requested for daemon.");
+
+        // The control socket lets the daemon drain its pipeline on any platform.
+        if let Some(message) = Self::reachable(self.control.stop())? {
+            return Ok(message);
+        }
+
         let mut sys = System::new_all();
         sys.refresh_processes(ProcessesToUpdate::All, true);
 
         match Self::find_daemon_process(&mut sys)? {
             None => Ok("Daemon process not running.".into()),
             Some(p) => {
-                info!("Sending kill signal to: {}", p.pid());
-                if !p.kill() {
-                    Err(RookLWAdminError::Other("Unable to kill process.".into()))
-                }
-                else {
-                    Ok("Daemon process kill signal sent.".into())
+                // SIGTERM lets the daemon drain its pipeline; fall back to a
+                // kill where it is not supported.
+                info!("Sending terminate signal to: {}", p.pid());
+                match p.kill_with(Signal::Term) {
+                    Some(true) => Ok("Daemon process terminate signal sent.".into()),
+                    Some(false) => Err(RookLWAdminError::Other("Unable to signal process.".into())),
+                    None => {
+                        info!("Terminate signal not supported, sending kill signal to: {}", p.pid());
+                        if !p.kill() {
+                            Err(RookLWAdminError::Other("Unable to kill process.".into()))
+                        }
+                        else {
+                            Ok("Daemon process kill signal sent.".into())
+                        }
+                    }
                 }
             }
         }
@@ -5,6 +5,7 @@ use leptos_router::path;
 
 use crate::components::Admin;
 use crate::components::DaemonControl;
+use crate::components::EventSearch;
 use crate::components::ImageDisplay;
 use crate::components::ImageSearch;
 use crate::components::NavBar;
@@ -12,6 +13,7 @@ use crate::components::ServerOperations;
 
 use crate::services::ImageInfoService;
 use crate::services::DaemonService;
+use crate::services::EventService;
 use crate::services::ServeropsService;
 
 #[component]
@@ -19,6 +21,7 @@ pub fn App() -> impl IntoView {
     let api_base_path = "";
     provide_context(ImageInfoService::new(api_base_path));
     provide_context(DaemonService::new(api_base_path));
+    provide_context(EventService::new(api_base_path));
     provide_context(ServeropsService::new(api_base_path));
     view! {
         <div class="app-shell">
@@ -27,6 +30,10 @@ pub fn App() -> impl IntoView {
                 <Router base="/admin">
                     <main class="app-content">
                         <Routes fallback=Admin>
+                            <Route
+                                path=path!("events")
+                                view=EventSearch
+                            />
                             <Route
                                 path=path!("images")
                                 view=ImageSearch
@@ -0,0 +1,119 @@
+use leptos::*;
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+
+use rook_lw_models::event::{describe_class_counts, EventInfo, EventSearchOptions};
+
+use crate::components::ErrorDisplay;
+use crate::services::EventService;
+
+#[component]
+pub fn EventInfoRow(event: EventInfo) -> impl IntoView {
+    let start = event.start_timestamp.to_string();
+    view! {
+        <tr>
+            <td>
+                { match &event.hero_image_id {
+                    Some(hero_image_id) => view! {
+                        <a href={ format!("image_display/{}", hero_image_id) }>{ start }</a>
+                    }.into_any(),
+                    None => view! { <span>{ start }</span> }.into_any(),
+                } }
+            </td>
+            <td>{ event.trigger.as_str() }</td>
+            <td>{ event.frame_count }</td>
+            <td>{ event.peak_motion_score }</td>
+            <td>
+                { if event.class_counts.is_empty() {
+                    "No Detections".to_string()
+                } else {
+                    describe_class_counts(&event.class_counts)
+                } }
+            </td>
+            <td>{ if event.end_timestamp.is_some() { "" } else { "In progress" } }</td>
+        </tr>
+    }
+}
+
+#[component]
+pub fn EventSearch() -> impl IntoView {
+    let (error, set_error) = signal(None::<String>);
+    let (loading, set_loading) = signal(false);
+    let (events, set_events) = signal(None::<Vec<EventInfo>>);
+
+    let event_service = match use_context::<EventService>() {
+        Some(s) => s,
+        None => return view! {
+            <div>Error</div>
+        }.into_any()
+    };
+
+    Effect::new(move |_| {
+        let event_service = event_service.clone();
+
+        set_error.set(None);
+        set_loading.set(true);
+
+        spawn_local(async move {
+            let search_options = EventSearchOptions::default();
+            match event_service.search(&search_options).await {
+                Err(e) => {
+                    set_loading.set(false);
+                    set_error.set(Some(format!("Error: {}", e)));
+                }
+                Ok(found) => {
+                    set_loading.set(false);
+                    set_events.set(Some(found));
+                }
+            }
+        });
+    });
+
+    view! {
+        <div class="event-search-component card">
+            <header class="card-header">
+                <p class="card-header-title">
+                    <span class="icon has-text-info" style="margin-right: 0.5em;"><i class="fas fa-search"></i></span>
+                    "Event Search"
+                </p>
+            </header>
+            <div class="card-content">
+                <div style="margin-bottom: 1em;">
+                    <ErrorDisplay error=error/>
+                </div>
+                { move ||
+                    if loading.get() {
+                        view! {
+                            <div class="notification is-info is-light">Loading...</div>
+                        }.into_any()
+                    }
+                    else {
+                        view! {
+                            <table class="table is-striped is-hoverable is-fullwidth">
+                                <thead>
+                                    <tr>
+                                        <th>"Started"</th>
+                                        <th>"Trigger"</th>
+                                        <th>"Frames"</th>
+                                        <th>"Peak Motion"</th>
+                                        <th>"Seen"</th>
+                                        <th></th>
+                                    </tr>
+                                </thead>
+                                <tbody>
+                                    <For
+                                        each=move || events.get().unwrap_or_else(Vec::new)
+                                        key=|event| event.event_id.clone()
+                                        let (event)
+                                    >
+                                        <EventInfoRow event=event/>
+                                    </For>
+                                </tbody>
+                            </table>
+                        }.into_any()
+                    }
+                }
+            </div>
+        </div>
+    }.into_any()
+}
@@ -2,6 +2,7 @@ mod admin;
 mod app;
 mod daemon_control;
 mod error_display;
+mod event_search;
 mod image_display;
 mod image_search;
 mod navbar;
@@ -12,6 +13,7 @@ pub use admin::*;
 pub use app::*;
 pub use error_display::*;
 pub use daemon_control::*;
+pub use event_search::*;
 pub use image_display::*;
 pub use image_search::*;
 pub use navbar::*;
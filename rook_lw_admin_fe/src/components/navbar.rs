@@ -30,6 +30,9 @@ pub fn NavBar() -> impl IntoView {
                 class=move || if is_active.get() { "navbar-menu is-active" } else { "navbar-menu" }
             >
                 <div class="navbar-start">
+                    <a class="navbar-item" href="/admin/events" on:click=move |_| set_is_active.set(false)>
+                        "Event Search"
+                    </a>
                     <a class="navbar-item" href="/admin/images" on:click=move |_| set_is_active.set(false)>
                         "Image Search"
                     </a>
@@ -0,0 +1,35 @@
+use gloo_net::http::Request;
+use serde_qs;
+
+use rook_lw_models::event::{EventInfo, EventSearchOptions};
+
+use crate::RookLWAppResult;
+use crate::services::response_ok;
+
+#[derive(Debug, Clone)]
+pub struct EventService {
+    pub base_path: String,
+}
+
+impl EventService {
+
+    pub fn new(base_path: impl Into<String>) -> Self {
+        Self { base_path: base_path.into() }
+    }
+
+    pub async fn search(&self, search_options: &EventSearchOptions)
+        -> RookLWAppResult<Vec<EventInfo>>
+    {
+        let query_str = serde_qs::to_string(search_options)?;
+        let url = format!("{}/api/event?{}", &self.base_path, &query_str);
+
+        let resp = Request::get(url.as_str())
+            .send()
+            .await?;
+
+        let resp = response_ok(resp).await?;
+
+        let events = resp.json::<Vec<EventInfo>>().await?;
+        Ok(events)
+    }
+}
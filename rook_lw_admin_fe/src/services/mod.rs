@@ -1,10 +1,12 @@
 
 mod image_info_service;
 mod daemon_service;
+mod event_service;
 mod response_util;
 mod serverops_service;
 
 pub use image_info_service::*;
 pub use daemon_service::*;
+pub use event_service::*;
 pub use response_util::*;
 pub use serverops_service::*;
\ No newline at end of file
@@ -0,0 +1,152 @@
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+
+use crate::{ControlError, ControlRequest, ControlResponse, ControlResult, DaemonStatus};
+
+// Long enough for a snapshot, which waits for the camera.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Sends requests to the daemon over its control socket.
+///
+/// Each request uses a new connection, so a client can be shared freely and
+/// keeps working across daemon restarts.
+#[derive(Debug, Clone)]
+pub struct ControlClient {
+    socket_path: PathBuf,
+    timeout: Duration,
+}
+
+impl ControlClient {
+    pub fn new(socket_path: impl Into<PathBuf>) -> Self {
+        Self {
+            socket_path: socket_path.into(),
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// How long to wait for the daemon to answer.
+    pub fn set_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Send a request. An error response of the daemon is returned as [`ControlError::Daemon`].
+    pub fn send(&self, request: ControlRequest) -> ControlResult<ControlResponse> {
+        match self.exchange(request)? {
+            ControlResponse::Error { message } => Err(ControlError::Daemon(message)),
+            response => Ok(response),
+        }
+    }
+
+    pub fn status(&self) -> ControlResult<DaemonStatus> {
+        match self.send(ControlRequest::Status)? {
+            ControlResponse::Status(status) => Ok(status),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    pub fn pause(&self) -> ControlResult<String> {
+        self.send_command(ControlRequest::Pause)
+    }
+
+    pub fn resume(&self) -> ControlResult<String> {
+        self.send_command(ControlRequest::Resume)
+    }
+
+    pub fn trigger(&self) -> ControlResult<String> {
+        self.send_command(ControlRequest::Trigger)
+    }
+
+    /// Path of the stored snapshot, relative to the image directory.
+    pub fn snapshot(&self) -> ControlResult<String> {
+        match self.send(ControlRequest::Snapshot)? {
+            ControlResponse::Snapshot { image_path } => Ok(image_path),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    pub fn reload_config(&self) -> ControlResult<String> {
+        self.send_command(ControlRequest::ReloadConfig)
+    }
+
+    pub fn stop(&self) -> ControlResult<String> {
+        self.send_command(ControlRequest::Stop)
+    }
+
+    fn send_command(&self, request: ControlRequest) -> ControlResult<String> {
+        match self.send(request)? {
+            ControlResponse::Done { message } => Ok(message),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    #[cfg(unix)]
+    fn exchange(&self, request: ControlRequest) -> ControlResult<ControlResponse> {
+        let mut stream = UnixStream::connect(&self.socket_path)
+            .map_err(|e| ControlError::Unavailable(format!("{}: {}", self.socket_path.display(), e)))?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+
+        let mut line = serde_json::to_string(&request)?;
+        line.push('\n');
+        stream.write_all(line.as_bytes())?;
+        stream.flush()?;
+
+        let mut response = String::new();
+        BufReader::new(&stream).read_line(&mut response)?;
+        if response.is_empty() {
+            return Err(ControlError::Protocol("Connection closed without a response".into()));
+        }
+        Ok(serde_json::from_str(&response)?)
+    }
+
+    #[cfg(not(unix))]
+    fn exchange(&self, _request: ControlRequest) -> ControlResult<ControlResponse> {
+        Err(ControlError::Unavailable("Control sockets are only supported on unix".into()))
+    }
+}
+
+fn unexpected(response: ControlResponse) -> ControlError {
+    ControlError::Protocol(format!("Unexpected response: {:?}", response))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    use std::os::unix::net::UnixListener;
+
+    #[test]
+    fn requests_and_responses_are_json_lines() {
+        let socket_path = std::env::temp_dir().join(format!("rook_lw_control_{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&socket_path);
+        let listener = UnixListener::bind(&socket_path).unwrap();
+
+        let server = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+            for response in [
+                r#"{"result":"done","message":"Paused"}"#,
+                r#"{"result":"error","message":"No camera"}"#,
+            ] {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = String::new();
+                BufReader::new(&stream).read_line(&mut request).unwrap();
+                requests.push(request);
+                writeln!(stream, "{}", response).unwrap();
+            }
+            requests
+        });
+
+        let client = ControlClient::new(&socket_path);
+        assert_eq!(client.pause().unwrap(), "Paused");
+        assert!(matches!(client.snapshot(), Err(ControlError::Daemon(message)) if message == "No camera"));
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests, vec!["{\"command\":\"pause\"}\n", "{\"command\":\"snapshot\"}\n"]);
+        std::fs::remove_file(&socket_path).unwrap();
+    }
+}
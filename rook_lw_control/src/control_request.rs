@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum ControlRequest {
+    /// Stage health, configuration and last event.
+    Status,
+
+    /// Stop watching for motion until resumed. Queued images are still processed.
+    Pause,
+
+    Resume,
+
+    /// Capture an event as if motion was detected.
+    Trigger,
+
+    /// Store a single frame, without starting an event.
+    Snapshot,
+
+    /// Read the configuration file again.
+    ReloadConfig,
+
+    /// Shut down gracefully, draining the queued images.
+    Stop,
+}
@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+use crate::DaemonStatus;
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum ControlResponse {
+    /// The request was carried out.
+    Done { message: String },
+
+    Status(DaemonStatus),
+
+    /// A snapshot was stored at this path, relative to the image directory.
+    Snapshot { image_path: String },
+
+    Error { message: String },
+}
@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+
+use rook_lw_models::event::EventInfo;
+use rook_lw_models::process::StageStatus;
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct DaemonStatus {
+    /// True while motion watching is paused.
+    pub paused: bool,
+
+    pub stages: Vec<StageStatus>,
+
+    /// The configuration the daemon is running with.
+    pub config: serde_json::Value,
+
+    /// The most recent event, if any was recorded.
+    pub last_event: Option<EventInfo>,
+}
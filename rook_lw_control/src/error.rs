@@ -0,0 +1,26 @@
+use thiserror::Error;
+
+pub type ControlResult<T> = Result<T, ControlError>;
+
+#[derive(Debug, Error)]
+pub enum ControlError {
+    /// The daemon is not listening on the control socket.
+    #[error("Daemon not reachable: {0}")]
+    Unavailable(String),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Protocol error: {0}")]
+    Protocol(String),
+
+    /// The daemon received the request but could not carry it out.
+    #[error("Daemon error: {0}")]
+    Daemon(String),
+}
+
+impl From<serde_json::Error> for ControlError {
+    fn from(err: serde_json::Error) -> Self {
+        ControlError::Protocol(format!("{err}"))
+    }
+}
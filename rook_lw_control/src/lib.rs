@@ -0,0 +1,17 @@
+//! Control protocol of the daemon, and a client for it.
+//!
+//! The daemon listens on a unix socket. A client connects, writes one request
+//! as a line of JSON and reads one response line back, then the daemon closes
+//! the connection.
+
+mod error;
+mod control_request;
+mod control_response;
+mod daemon_status;
+mod control_client;
+
+pub use error::*;
+pub use control_request::*;
+pub use control_response::*;
+pub use daemon_status::*;
+pub use control_client::*;
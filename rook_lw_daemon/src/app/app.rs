@@ -1,52 +1,130 @@
 use crate::RookLWResult;
-use crate::events::ImageProcessingEvent;
-use crate::tasks::motion_watcher::MotionWatcher;
-use crate::tasks::image_storer::ImageStorer;
-use crate::tasks::image_detector::ImageDetector;
-use crate::prodcon::{ProducerTask, ConsumerTask};
+use crate::app::{install_reload_handler, start_metrics_server, ConfigReloader, ControlServer, Pipeline};
+use crate::prodcon::{ShutdownSignal, Supervisor, HealthRegistry, PipelineMetrics};
+use crate::tasks::motion_watcher::WatcherControl;
 
-use tracing::error;
+use tracing::{error, info, warn};
+
+use std::time::{Duration, Instant};
+
+// How often finished tasks are checked for while waiting on them.
+const JOIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
 
 pub struct App {
-    motion_watcher: Box<dyn MotionWatcher>,
-    image_storer: ImageStorer,
-    image_detector: ImageDetector,
+    pipeline: Pipeline,
+    shutdown_deadline: Duration,
+    health: HealthRegistry,
+    metrics: PipelineMetrics,
+    metrics_address: Option<String>,
+    watcher_control: WatcherControl,
+    control_server: Option<ControlServer>,
+    config_reloader: Option<ConfigReloader>,
 }
 
 impl App {
 
-    pub fn new(
-        motion_watcher: Box<dyn MotionWatcher>,
-        image_storer: ImageStorer,
-        image_detector: ImageDetector) -> Self {
-        
+    pub fn new(pipeline: Pipeline) -> Self {
         Self {
-            motion_watcher,
-            image_storer,
-            image_detector,
+            pipeline,
+            shutdown_deadline: Duration::from_secs(30),
+            health: HealthRegistry::new(),
+            metrics: PipelineMetrics::new(),
+            metrics_address: None,
+            watcher_control: WatcherControl::new(),
+            control_server: None,
+            config_reloader: None,
         }
     }
 
-    pub fn run(self) -> RookLWResult<()> {
-        // ImageDiffMotionWatcher produces CaptureEvents; a separate worker receives and processes them.
-        // Bounded provides backpressure so we don't buffer unbounded image data.
-        let (motion_detected_tx, motion_detected_rx) = crossbeam_channel::bounded::<ImageProcessingEvent>(64);
+    /// How long to wait for queued images to be processed after shutdown is requested.
+    pub fn set_shutdown_deadline(&mut self, shutdown_deadline: Duration) -> &mut Self {
+        self.shutdown_deadline = shutdown_deadline;
+        self
+    }
+
+    /// Health of the pipeline stages, updated while the app runs.
+    pub fn health(&self) -> HealthRegistry {
+        self.health.clone()
+    }
+
+    pub fn metrics(&self) -> PipelineMetrics {
+        self.metrics.clone()
+    }
+
+    /// Serve metrics and stage health over HTTP on this address while the app runs.
+    pub fn set_metrics_address(&mut self, metrics_address: impl Into<String>) -> &mut Self {
+        self.metrics_address = Some(metrics_address.into());
+        self
+    }
+
+    /// Pauses the motion watchers and sends them commands.
+    pub fn watcher_control(&self) -> WatcherControl {
+        self.watcher_control.clone()
+    }
+
+    /// Serve the control socket while the app runs.
+    pub fn set_control_server(&mut self, control_server: ControlServer) -> &mut Self {
+        self.control_server = Some(control_server);
+        self
+    }
+
+    /// Reload the configuration on SIGHUP while the app runs.
+    pub fn set_config_reloader(&mut self, config_reloader: ConfigReloader) -> &mut Self {
+        self.config_reloader = Some(config_reloader);
+        self
+    }
 
-        // ImageDetector produces ImageProcessingEvents; ImageStorer receives and processes them.
-        let (object_detected_tx, object_detected_rx) = crossbeam_channel::bounded::<ImageProcessingEvent>(64);
+    /// Run the pipeline until `shutdown` is requested and the queues are drained,
+    /// or the shutdown deadline passes.
+    pub fn run(self, shutdown: ShutdownSignal) -> RookLWResult<()> {
+        let App {
+            pipeline,
+            shutdown_deadline,
+            health,
+            metrics,
+            metrics_address,
+            watcher_control,
+            control_server,
+            config_reloader,
+        } = self;
 
-        let App { mut motion_watcher, image_storer, mut image_detector } = self;
+        let mut supervisor = Supervisor::new(health.clone(), shutdown.clone());
+        supervisor.set_metrics(metrics.clone());
+
+        if let Some(metrics_address) = &metrics_address {
+            start_metrics_server(metrics_address, metrics.clone(), health.clone())?;
+        }
+        if let Some(config_reloader) = config_reloader {
+            install_reload_handler(config_reloader)?;
+        }
+        if let Some(control_server) = control_server {
+            control_server.start(watcher_control.clone(), health.clone(), shutdown.clone())?;
+        }
 
-        motion_watcher.connect(motion_detected_tx);
-        image_detector.connect(object_detected_tx);
+        // Stages are connected by bounded queues, which provide backpressure so
+        // we don't buffer unbounded image data.
+        let handles = pipeline.start(&supervisor, &metrics, &watcher_control)?;
 
-        let handles = vec![
-            motion_watcher.start(),
-            image_detector.start_listener(motion_detected_rx),
-            image_storer.start_listener(object_detected_rx),
-        ];
+        // Each stage exits when the stage before it has exited and its queue is
+        // drained, so the pipeline shuts down in order once the watcher stops.
+        let mut deadline: Option<Instant> = None;
+        while !handles.iter().all(|handle| handle.is_finished()) {
+            if deadline.is_none() && shutdown.is_requested() {
+                info!(deadline_seconds = shutdown_deadline.as_secs(), "Shutting down, draining pipeline");
+                deadline = Some(Instant::now() + shutdown_deadline);
+            }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                warn!(
+                    running_count = handles.iter().filter(|handle| !handle.is_finished()).count(),
+                    "Shutdown deadline passed, abandoning running tasks"
+                );
+                break;
+            }
+            std::thread::sleep(JOIN_POLL_INTERVAL);
+        }
 
-        for handle in handles {
+        // Tasks still running past the deadline are left to end with the process.
+        for handle in handles.into_iter().filter(|handle| handle.is_finished()) {
             match handle.join() {
                 Ok(result) => {
                     if let Err(e) = result {
@@ -59,6 +137,18 @@ impl App {
             }
         }
 
+        for stage in health.snapshot() {
+            info!(
+                stage = %stage.name,
+                health = ?stage.health,
+                processed_count = stage.processed_count,
+                error_count = stage.error_count,
+                restart_count = stage.restart_count,
+                "Stage status"
+            );
+        }
+
+        info!("Pipeline stopped");
         Ok(())
     }
 }
\ No newline at end of file
@@ -1,6 +1,11 @@
 use crate::{RookLWError, RookLWResult};
+use crate::image::object_detection::ClassPolicy;
+use crate::prodcon::OverflowPolicy;
+use crate::tasks::near_duplicate_filter::NearDuplicateMode;
 use serde::{Deserialize, Serialize};
 
+use std::fmt::Display;
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(default)]
 pub struct AppConfiguration {
@@ -16,9 +21,41 @@ pub struct AppConfiguration {
     // Sqlite database path
     pub database_path: String,
 
+    // Time allowed for queued images to be processed on shutdown
+    pub shutdown_deadline_seconds: u64,
+
+    // Sqlite database holding the items of durable stage queues
+    pub durable_queue_path: String,
+
+    // Times a durable queue item is recovered after a crash before it is discarded
+    pub durable_queue_max_attempts: u32,
+
+    // Prometheus metrics and stage health, served over HTTP
+    pub use_metrics_server: bool,
+    pub metrics_server_address: String,
+
+    // Unix socket taking control requests, see rook_lw_control
+    pub use_control_socket: bool,
+    pub control_socket_path: String,
+
+    // Pipeline graph; when empty the default chain is used
+    pub pipeline_stages: Vec<PipelineStageConfiguration>,
+
+    // Error policy per pipeline stage
+    pub motion_watcher_error_policy: ErrorPolicyType,
+    pub image_detector_error_policy: ErrorPolicyType,
+    pub object_tracker_error_policy: ErrorPolicyType,
+    pub near_duplicate_filter_error_policy: ErrorPolicyType,
+    pub privacy_redactor_error_policy: ErrorPolicyType,
+    pub image_storer_error_policy: ErrorPolicyType,
+    pub error_policy_retry_count: u32,
+    pub error_policy_max_restarts: u32,
+    pub error_policy_initial_backoff_ms: u64,
+    pub error_policy_max_backoff_ms: u64,
+
     // Motion watcher settings
     pub use_motion_watcher: bool,
-    pub motion_watcher_type: String,
+    pub motion_watcher_type: MotionWatcherType,
     pub motion_watcher_count: u32,
     pub motion_watcher_round_interval_ms: u64,
     pub radar_gpio_pin: u32,
@@ -29,7 +66,7 @@ pub struct AppConfiguration {
     pub image_capturer_capture_interval_ms: u64,
 
     // motion detector settings
-    pub motion_detector_type: String,
+    pub motion_detector_type: MotionDetectorType,
 
     // Y Plane
     pub yplane_motion_percentile: f32,
@@ -45,8 +82,8 @@ pub struct AppConfiguration {
     pub yplane_rolling_z_alpha: f64,
     pub yplane_rolling_z_threshold: f32,
 
-    // object detector settings: opencv or onnx
-    pub object_detector_type: String,
+    // object detector settings: opencv or yolov8
+    pub object_detector_type: ObjectDetectorType,
 
     // opencv object detector settings
     pub opencv_model_config_path: String,
@@ -58,6 +95,243 @@ pub struct AppConfiguration {
     pub yolov8_model_path: String,
     pub yolov8_model_names_path: String,
     pub yolov8_model_confidence_threshold: f32,
+    pub yolov8_intra_threads: usize,
+
+    // image detector settings: detect in crops around motion regions
+    pub image_detector_use_motion_regions: bool,
+    pub image_detector_motion_region_padding: f32,
+    pub image_detector_motion_region_max_coverage: f32,
+
+    // image detector settings: which captures without detections are stored
+    pub storage_policy: StoragePolicyType,
+    pub negative_sample_rate: f32,
+
+    // JPEG quality of stored images, and of stored captures without detections
+    pub jpeg_quality: u8,
+    pub negative_jpeg_quality: u8,
+
+    // object tracker settings
+    pub object_tracker_iou_threshold: f32,
+    pub object_tracker_max_age: u32,
+
+    // static detection suppression
+    pub use_static_suppression: bool,
+    pub static_suppression_mode: StaticSuppressionModeType,
+    pub static_suppression_down_weight: f32,
+    pub static_suppression_iou_threshold: f32,
+    pub static_suppression_min_events: u32,
+    pub static_suppression_min_duration_minutes: i64,
+    pub static_suppression_expiry_minutes: i64,
+
+    // near-duplicate filter settings
+    pub use_near_duplicate_filter: bool,
+    pub near_duplicate_mode: NearDuplicateMode,
+    pub near_duplicate_embedding_threshold: f32,
+    pub near_duplicate_hash_max_distance: u32,
+    pub near_duplicate_window_seconds: i64,
+
+    // interestingness score settings
+    pub use_interestingness_score: bool,
+    pub interestingness_rarity_days: i64,
+    pub interestingness_confidence_weight: f32,
+    pub interestingness_rarity_weight: f32,
+    pub interestingness_novelty_weight: f32,
+    pub interestingness_sharpness_weight: f32,
+
+    // hero frame score settings: which image best shows an event
+    pub hero_sharpness_weight: f32,
+    pub hero_confidence_weight: f32,
+    pub hero_size_weight: f32,
+    pub hero_centering_weight: f32,
+
+    // embedding cluster settings
+    pub use_cluster_assignment: bool,
+    pub cluster_min_similarity: f32,
+    pub cluster_min_size: u32,
+    pub cluster_iterations: u32,
+
+    // privacy redaction settings
+    pub use_privacy_redaction: bool,
+    pub privacy_redaction_classes: Vec<String>,
+    pub privacy_redaction_mode: PrivacyRedactionModeType,
+    pub privacy_redaction_padding: f32,
+    pub privacy_redaction_block_size: u32,
+    pub privacy_redaction_blur_sigma: f32,
+
+    // Per-class filtering and relabeling applied after object detection
+    pub class_policy: ClassPolicy,
+}
+
+/// Source of capture events.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MotionWatcherType {
+    // Compares frames of the camera
+    ImageDiff,
+
+    // Waits for a radar sensor on a GPIO pin
+    Radar,
+}
+
+/// How the image diff motion watcher compares frames.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MotionDetectorType {
+    #[serde(rename = "yplane_motion_percentile")]
+    YPlaneMotionPercentile,
+
+    #[serde(rename = "yplane_boxed_average")]
+    YPlaneBoxedAverage,
+}
+
+/// Model running the object detection.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObjectDetectorType {
+    #[serde(rename = "opencv")]
+    OpenCV,
+
+    #[serde(rename = "yolov8")]
+    Yolov8,
+}
+
+/// What a pipeline stage does when processing an item fails.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorPolicyType {
+    // Log the error and go on with the next item
+    Skip,
+
+    // Try the item again, error_policy_retry_count times
+    Retry,
+
+    // Reset the stage with a backoff, error_policy_max_restarts times
+    Restart,
+
+    // Stop the daemon
+    Shutdown,
+}
+
+/// Which captures without detections are stored.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StoragePolicyType {
+    All,
+    DetectionsOnly,
+
+    // negative_sample_rate of them
+    SampledNegatives,
+    FirstFrameNegatives,
+}
+
+/// What happens to detections of objects that don't move.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StaticSuppressionModeType {
+    Suppress,
+
+    // By static_suppression_down_weight
+    DownWeight,
+}
+
+/// How images with detections of privacy_redaction_classes are redacted.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PrivacyRedactionModeType {
+    // In blocks of privacy_redaction_block_size
+    Pixelate,
+
+    // With privacy_redaction_blur_sigma
+    Blur,
+
+    // Don't store the image
+    Drop,
+}
+
+/// Type of a pipeline stage. A stage type can be used once its factory is
+/// registered in the stage registry.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum StageType {
+    MotionWatcher,
+    ImageDetector,
+    ObjectTracker,
+    NearDuplicateFilter,
+    PrivacyRedactor,
+    ImageStorer,
+    LogNotifier,
+}
+
+impl Display for StageType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The name used in the configuration.
+        match serde_json::to_value(self) {
+            Ok(serde_json::Value::String(name)) => write!(f, "{}", name),
+            _ => write!(f, "{:?}", self),
+        }
+    }
+}
+
+/// One stage of the pipeline graph, a `[[pipeline_stages]]` table.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct PipelineStageConfiguration {
+    pub name: String,
+
+    // Registered stage type; the name when not given
+    #[serde(rename = "type")]
+    pub stage_type: Option<StageType>,
+
+    // Stages receiving this stage's output, each gets a copy
+    pub outputs: Vec<String>,
+
+    // Capacity of the queue in front of this stage
+    pub capacity: usize,
+
+    // What producers do when that queue is full
+    pub overflow_policy: OverflowPolicy,
+
+    // Never drop the first frames of each event; 0 turns this off
+    pub priority_frame_count: u32,
+
+    // Instances of the stage running in parallel, each created by the stage type
+    pub workers: u32,
+
+    // Overrides the error policy of the stage type
+    pub error_policy: Option<ErrorPolicyType>,
+
+    // Keep the queue in front of this stage on disk, so it survives a crash
+    pub durable: bool,
+}
+
+impl PipelineStageConfiguration {
+    pub fn new(name: &str, outputs: &[&str]) -> Self {
+        Self {
+            name: name.to_string(),
+            outputs: outputs.iter().map(|output| output.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    /// The type of the stage, taken from its name when not given. None when
+    /// the name is not a stage type either.
+    pub fn stage_type(&self) -> Option<StageType> {
+        self.stage_type.or_else(|| serde_json::from_value(serde_json::Value::String(self.name.clone())).ok())
+    }
+}
+
+impl Default for PipelineStageConfiguration {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            stage_type: None,
+            outputs: Vec::new(),
+            capacity: 64,
+            overflow_policy: OverflowPolicy::Block,
+            priority_frame_count: 0,
+            workers: 1,
+            error_policy: None,
+            durable: false,
+        }
+    }
 }
 
 impl Default for AppConfiguration {
@@ -68,9 +342,36 @@ impl Default for AppConfiguration {
             image_directory: "var/images".into(),
             database_path: "var/db/image_info.db".into(),
 
+            shutdown_deadline_seconds: 30,
+
+            durable_queue_path: "var/db/queue.db".into(),
+            durable_queue_max_attempts: 3,
+
+            // metrics server defaults
+            use_metrics_server: true,
+            metrics_server_address: "127.0.0.1:9464".into(),
+
+            // control socket defaults
+            use_control_socket: true,
+            control_socket_path: "var/run/rook_lw_daemon.sock".into(),
+
+            pipeline_stages: Vec::new(),
+
+            // error policy defaults
+            motion_watcher_error_policy: ErrorPolicyType::Restart,
+            image_detector_error_policy: ErrorPolicyType::Skip,
+            object_tracker_error_policy: ErrorPolicyType::Skip,
+            near_duplicate_filter_error_policy: ErrorPolicyType::Skip,
+            privacy_redactor_error_policy: ErrorPolicyType::Skip,
+            image_storer_error_policy: ErrorPolicyType::Retry,
+            error_policy_retry_count: 3,
+            error_policy_max_restarts: 5,
+            error_policy_initial_backoff_ms: 1000,
+            error_policy_max_backoff_ms: 60000,
+
             // motion watcher defaults
             use_motion_watcher: true,
-            motion_watcher_type: "image_diff".into(),
+            motion_watcher_type: MotionWatcherType::ImageDiff,
             motion_watcher_count: 20,
             motion_watcher_round_interval_ms: 500,
             radar_gpio_pin: 17,
@@ -81,7 +382,7 @@ impl Default for AppConfiguration {
             image_capturer_capture_interval_ms: 100,
 
             // Which motion detector to use.
-            motion_detector_type: "yplane_motion_percentile".into(),
+            motion_detector_type: MotionDetectorType::YPlaneMotionPercentile,
 
             // y plane motion detector defaults
             yplane_motion_percentile: 0.95,
@@ -98,7 +399,7 @@ impl Default for AppConfiguration {
             yplane_rolling_z_threshold: 2.0,
 
             // object detector defaults
-            object_detector_type: "opencv".into(),
+            object_detector_type: ObjectDetectorType::OpenCV,
 
             // opencv object detector defaults
             opencv_model_config_path: "models/yolov4-tiny.cfg".into(),
@@ -110,6 +411,67 @@ impl Default for AppConfiguration {
             yolov8_model_path: "models/yolov8n.onnx".into(),
             yolov8_model_names_path: "models/coco.names".into(),
             yolov8_model_confidence_threshold: 0.25,
+            yolov8_intra_threads: 4,
+
+            // image detector defaults
+            image_detector_use_motion_regions: false,
+            image_detector_motion_region_padding: 0.25,
+            image_detector_motion_region_max_coverage: 0.6,
+            storage_policy: StoragePolicyType::DetectionsOnly,
+            negative_sample_rate: 0.05,
+            jpeg_quality: 85,
+            negative_jpeg_quality: 60,
+
+            // object tracker defaults
+            object_tracker_iou_threshold: 0.3,
+            object_tracker_max_age: 3,
+
+            // static detection suppression defaults
+            use_static_suppression: false,
+            static_suppression_mode: StaticSuppressionModeType::Suppress,
+            static_suppression_down_weight: 0.5,
+            static_suppression_iou_threshold: 0.8,
+            static_suppression_min_events: 5,
+            static_suppression_min_duration_minutes: 60,
+            static_suppression_expiry_minutes: 180,
+
+            // near-duplicate filter defaults
+            use_near_duplicate_filter: false,
+            near_duplicate_mode: NearDuplicateMode::Drop,
+            near_duplicate_embedding_threshold: 0.97,
+            near_duplicate_hash_max_distance: 4,
+            near_duplicate_window_seconds: 600,
+
+            // interestingness score defaults
+            use_interestingness_score: true,
+            interestingness_rarity_days: 7,
+            interestingness_confidence_weight: 0.3,
+            interestingness_rarity_weight: 0.3,
+            interestingness_novelty_weight: 0.2,
+            interestingness_sharpness_weight: 0.2,
+
+            // hero frame score defaults
+            hero_sharpness_weight: 0.3,
+            hero_confidence_weight: 0.3,
+            hero_size_weight: 0.2,
+            hero_centering_weight: 0.2,
+
+            // embedding cluster defaults
+            use_cluster_assignment: true,
+            cluster_min_similarity: 0.85,
+            cluster_min_size: 3,
+            cluster_iterations: 5,
+
+            // privacy redaction defaults
+            use_privacy_redaction: true,
+            privacy_redaction_classes: vec!["person".to_string()],
+            privacy_redaction_mode: PrivacyRedactionModeType::Pixelate,
+            privacy_redaction_padding: 0.1,
+            privacy_redaction_block_size: 16,
+            privacy_redaction_blur_sigma: 12.0,
+
+            // no class policy by default
+            class_policy: ClassPolicy::default(),
         }
     }
 }
@@ -1,76 +1,482 @@
-use crate::app::{App, AppConfiguration};
-use crate::tasks::image_capturer::ImageCapturer;
+use crate::app::{App, AppConfiguration, ConfigReloader, MotionDetectorType, MotionWatcherType, ObjectDetectorType, ControlServer, LiveStageSettings, PipelineStageConfiguration, Pipeline, PipelineNode, PipelineStage, StageRegistry, StageRole};
+use crate::app::{ErrorPolicyType, PrivacyRedactionModeType, StageType, StaticSuppressionModeType, StoragePolicyType};
+use crate::prodcon::{DurableConsumer, DurableQueue, DurableQueueStore, ErrorPolicy, LiveSettings, RestartPolicy, OverflowSettings, ProducerConsumerTask, WorkerPool};
+use crate::tasks::image_capturer::{CaptureSettings, ImageCapturer};
+use crate::events::ImageProcessingEvent;
 use crate::{RookLWResult, RookLWError};
 use crate::image::object_detection::ObjectDetector;
 use crate::image::object_detection::OpenCVObjectDetector;
 use crate::image::object_detection::Yolov8ObjectDetector;
+use crate::image::object_detection::RegionDetectionSettings;
+use crate::image::object_detection::ClassPolicyObjectDetector;
+use crate::image::object_detection::{StaticDetectionMemory, StaticSuppressionSettings, StaticSuppressionMode};
 use crate::image::frame::FrameSource;
 use crate::image::frame::FrameSourceFactory;
 
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 use crate::image::fourcc::fourcc_to_string;
-use crate::image::motion::{YPlaneMotionDetector, YPlaneRollingZMotionDetector, YPlaneBoxedAverageMotionDetector, YPlaneMotionPercentileDetector};
-use crate::tasks::image_diff_motion_watcher::ImageDiffMotionWatcher;
+use crate::image::motion::{MotionThresholds, YPlaneMotionDetector, YPlaneRollingZMotionDetector, YPlaneBoxedAverageMotionDetector, YPlaneMotionPercentileDetector};
+use crate::tasks::image_diff_motion_watcher::{ImageDiffMotionWatcher, MotionWatchSettings};
 use crate::tasks::radar_motion_watcher::RadarMotionWatcher;
 use crate::tasks::motion_watcher::MotionWatcher;
 use crate::tasks::image_storer::ImageStorer;
-use crate::tasks::image_detector::ImageDetector;
+use crate::tasks::log_notifier::LogNotifier;
+use crate::tasks::event_recorder::EventRecorder;
+use crate::tasks::image_detector::{DetectorMemory, ImageDetector, StoragePolicy};
+use crate::tasks::object_tracker::ObjectTracker;
+use crate::tasks::image_reprocessor::ImageReprocessor;
+use crate::tasks::embedding_clusterer::EmbeddingClusterer;
+use crate::tasks::dataset_exporter::DatasetExporter;
+use crate::tasks::near_duplicate_filter::{NearDuplicateFilter, NearDuplicateSettings};
+use crate::tasks::privacy_redactor::{PrivacyRedactor, RedactionSettings, RedactionMode};
+use crate::image::redaction::RedactionMethod;
+use crate::image::tracking::TrackerSettings;
+use crate::image::interestingness::{InterestingnessScorer, InterestingnessWeights};
+use crate::image::hero_frame::HeroFrameWeights;
+use crate::image::embedding_clustering::ClusteringSettings;
 
 use rook_lw_image_repo::sqlite::create_pool;
+use rook_lw_image_repo::event::{EventRepository, EventRepositorySqlite};
 use rook_lw_image_repo::image_info::{ImageInfoRepository, ImageInfoRepositorySqlite};
 use rook_lw_image_repo::image_store::{ImageStoreRepository, ImageStoreRepositoryFile};
 
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 use std::time::Duration;
 
 use r2d2::Pool;
 use r2d2_sqlite::SqliteConnectionManager;
 
-pub fn create_app() -> RookLWResult<App> {
+pub fn create_app(config_path: &str) -> RookLWResult<App> {
 
     // create app configuration
-    let app_config = AppConfiguration::load("config/rook_lw_daemon.toml")?;
+    let app_config = AppConfiguration::load(config_path)?;
     info!(app_configuration = ?app_config, "App configuration loaded");
 
-    // Create SQLite connection pool
-    let db_pool = create_sqlite_pool(&app_config)?;
+    let live_settings = create_live_stage_settings(&app_config);
+    let stage_registry = create_stage_registry(&live_settings);
+    let pipeline = create_pipeline(&app_config, &stage_registry)?;
+    let config_reloader = ConfigReloader::new(config_path, app_config.clone(), live_settings, create_live_stage_settings);
 
-    let frame_source = create_frame_source(&app_config)?;
+    let mut app = App::new(pipeline);
+    app.set_config_reloader(config_reloader.clone());
+    app.set_shutdown_deadline(Duration::from_secs(app_config.shutdown_deadline_seconds));
+    if app_config.use_metrics_server {
+        app.set_metrics_address(app_config.metrics_server_address.clone());
+    }
+    if app_config.use_control_socket {
+        app.set_control_server(ControlServer::new(
+            app_config.control_socket_path.clone(),
+            config_reloader,
+            create_event_repository(create_sqlite_pool(&app_config)?)?,
+            create_image_store_repository(&app_config)?,
+        ));
+    }
+
+    Ok(app)
+}
 
-    // Create the motion watcher.
-    let mw = create_motion_watcher(&app_config, frame_source)?;
+/// Settings the running stages take over when the configuration is reloaded.
+pub fn create_live_stage_settings(app_config: &AppConfiguration) -> LiveStageSettings {
+    LiveStageSettings {
+        capture: LiveSettings::new(create_capture_settings(app_config)),
+        motion_watch: LiveSettings::new(create_motion_watch_settings(app_config)),
+        class_policy: LiveSettings::new(app_config.class_policy.clone()),
+        storage_policy: LiveSettings::new(create_storage_policy(app_config)),
+        tracker: LiveSettings::new(create_tracker_settings(app_config)),
+    }
+}
 
-    // Job that performs object detection on images.
-    let image_detector = create_image_detector(&app_config)?;
+/// Registry of the stage types the configured pipeline can use. The stages
+/// take replaced settings from `live_settings`.
+pub fn create_stage_registry(live_settings: &LiveStageSettings) -> StageRegistry {
+    let mut registry = StageRegistry::new();
+
+    let watcher_settings = live_settings.clone();
+    let detector_settings = live_settings.clone();
+    // Made with the first detector and shared by the later ones, so the workers
+    // of a pooled detector remember static detections as one detector.
+    let detector_memory: OnceLock<DetectorMemory> = OnceLock::new();
+    let tracker_settings = live_settings.tracker.clone();
+
+    registry
+        .register(StageType::MotionWatcher, StageRole::Source, move |app_config| {
+            let frame_source = create_frame_source(app_config)?;
+            Ok(PipelineStage::Source(create_motion_watcher(app_config, frame_source, &watcher_settings)?))
+        })
+        // Job that performs object detection on images.
+        .register(StageType::ImageDetector, StageRole::Processor, move |app_config| {
+            let memory = match detector_memory.get() {
+                Some(memory) => memory.clone(),
+                None => {
+                    let memory = create_detector_memory(app_config)?;
+                    detector_memory.get_or_init(|| memory).clone()
+                },
+            };
+            Ok(PipelineStage::Processor(Box::new(create_image_detector(app_config, &detector_settings, memory)?)))
+        })
+        // Job that tracks detected objects across the frames of an event.
+        .register(StageType::ObjectTracker, StageRole::Processor, move |app_config| {
+            let mut object_tracker = create_object_tracker(app_config);
+            object_tracker.set_live_settings(tracker_settings.clone());
+            Ok(PipelineStage::Processor(Box::new(object_tracker)))
+        })
+        // Job that drops near-duplicate images before they are stored.
+        .register(StageType::NearDuplicateFilter, StageRole::Processor, |app_config| {
+            info!(mode = ?app_config.near_duplicate_mode, "Using near-duplicate filter");
+            Ok(PipelineStage::Processor(Box::new(create_near_duplicate_filter(app_config))))
+        })
+        // Job that redacts or drops images with people before they are stored.
+        .register(StageType::PrivacyRedactor, StageRole::Processor, |app_config| {
+            info!(
+                classes = ?app_config.privacy_redaction_classes,
+                mode = ?app_config.privacy_redaction_mode,
+                "Using privacy redaction"
+            );
+            Ok(PipelineStage::Processor(Box::new(create_privacy_redactor(app_config))))
+        })
+        // Job that stores images to disk.
+        .register(StageType::ImageStorer, StageRole::Storer, |app_config| {
+            let db_pool = create_sqlite_pool(app_config)?;
+            let image_storer = create_image_storer(
+                app_config,
+                create_image_store_repository(app_config)?,
+                create_image_info_repository(db_pool.clone())?,
+                create_event_repository(db_pool)?,
+            )?;
+            Ok(PipelineStage::Storer(Box::new(image_storer)))
+        })
+        // Job that logs the images the storer stored.
+        .register(StageType::LogNotifier, StageRole::Notifier, |_| {
+            Ok(PipelineStage::Notifier(Box::new(LogNotifier::new())))
+        });
+
+    registry
+}
+
+fn create_pipeline(app_config: &AppConfiguration, stage_registry: &StageRegistry) -> RookLWResult<Pipeline> {
+    let mut pipeline = Pipeline::new();
+    let stage_configs = create_pipeline_stage_configurations(app_config);
+
+    if let Some(problem) = privacy_problems(app_config, stage_registry, &stage_configs).into_iter().next() {
+        return Err(RookLWError::Initialization(problem.message));
+    }
+
+    let durable_queue_store = if stage_configs.iter().any(|stage_config| stage_config.durable) {
+        Some(DurableQueueStore::new(create_pool(&app_config.durable_queue_path)?)?)
+    } else {
+        None
+    };
+
+    for stage_config in stage_configs {
+        let stage_type = stage_config.stage_type().ok_or_else(|| RookLWError::Initialization(format!(
+            "Pipeline stage {} has no type, and its name is not a stage type",
+            stage_config.name
+        )))?;
+        info!(
+            stage = %stage_config.name,
+            stage_type = %stage_type,
+            outputs = ?stage_config.outputs,
+            capacity = stage_config.capacity,
+            overflow_policy = ?stage_config.overflow_policy,
+            "Creating pipeline stage"
+        );
+
+        let error_policy = stage_config.error_policy.unwrap_or_else(|| default_error_policy(app_config, stage_type));
+
+        let durable = match &durable_queue_store {
+            Some(store) if stage_config.durable => Some(DurableQueue::new(
+                &stage_config.name,
+                store.clone(),
+                app_config.durable_queue_max_attempts,
+            )),
+            _ => None,
+        };
+
+        pipeline.add_stage(PipelineNode {
+            name: stage_config.name.clone(),
+            stage: create_pipeline_stage(app_config, stage_registry, &stage_config, stage_type, durable.as_ref())?,
+            outputs: stage_config.outputs.clone(),
+            capacity: stage_config.capacity,
+            overflow: create_overflow_settings(&stage_config),
+            error_policy: create_error_policy(app_config, error_policy),
+            durable,
+        });
+    }
+
+    pipeline.validate()?;
+    Ok(pipeline)
+}
+
+/// Create the stage, as a pool of workers when more than one is configured.
+/// With a durable queue, each worker removes the items it has processed from it.
+fn create_pipeline_stage(
+    app_config: &AppConfiguration,
+    stage_registry: &StageRegistry,
+    stage_config: &PipelineStageConfiguration,
+    stage_type: StageType,
+    durable: Option<&DurableQueue<ImageProcessingEvent>>,
+) -> RookLWResult<PipelineStage> {
+    if stage_config.workers <= 1 {
+        let stage = stage_registry.create(stage_type, app_config)?;
+        let Some(durable) = durable else {
+            return Ok(stage);
+        };
+        return match stage {
+            PipelineStage::Source(_) => Err(RookLWError::Initialization(format!(
+                "Pipeline stage {} can not be durable, it has no input queue",
+                stage_config.name
+            ))),
+            PipelineStage::Processor(task) => Ok(PipelineStage::Processor(Box::new(DurableConsumer::new(task, durable)))),
+            PipelineStage::Sink(task) => Ok(PipelineStage::Sink(Box::new(DurableConsumer::new(task, durable)))),
+            PipelineStage::Storer(task) => Ok(PipelineStage::Storer(Box::new(DurableConsumer::new(task, durable)))),
+            PipelineStage::Notifier(_) => Err(RookLWError::Initialization(format!(
+                "Pipeline stage {} can not be durable, only queues of captured images can",
+                stage_config.name
+            ))),
+        };
+    }
+
+    if stage_type == StageType::ImageDetector && app_config.object_detector_type == ObjectDetectorType::Yolov8 {
+        let thread_count = stage_config.workers as usize * app_config.yolov8_intra_threads;
+        let core_count = std::thread::available_parallelism().map(|count| count.get()).unwrap_or(1);
+        if thread_count > core_count {
+            warn!(
+                workers = stage_config.workers,
+                intra_threads = app_config.yolov8_intra_threads,
+                core_count,
+                "Detector workers times ONNX intra-op threads exceeds the number of cores"
+            );
+        }
+    }
+
+    let mut tasks: Vec<Box<dyn ProducerConsumerTask<ImageProcessingEvent>>> = Vec::new();
+    for _ in 0..stage_config.workers {
+        match stage_registry.create(stage_type, app_config)? {
+            PipelineStage::Processor(task) => match durable {
+                Some(durable) => tasks.push(Box::new(DurableConsumer::new(task, durable))),
+                None => tasks.push(task),
+            },
+            _ => return Err(RookLWError::Initialization(format!(
+                "Pipeline stage {} can not have several workers, only processing stages can",
+                stage_config.name
+            ))),
+        }
+    }
+
+    Ok(PipelineStage::Processor(Box::new(WorkerPool::new(&stage_config.name, tasks))))
+}
+
+/// The configured pipeline stages, or when none are configured the default
+/// chain, with the optional stages that are switched on.
+pub(crate) fn create_pipeline_stage_configurations(app_config: &AppConfiguration) -> Vec<PipelineStageConfiguration> {
+    if !app_config.pipeline_stages.is_empty() {
+        return app_config.pipeline_stages.clone();
+    }
+
+    let mut names = vec!["motion_watcher", "image_detector", "object_tracker"];
+    if app_config.use_near_duplicate_filter {
+        names.push("near_duplicate_filter");
+    }
+    if app_config.use_privacy_redaction {
+        names.push("privacy_redactor");
+    }
+    names.push("image_storer");
+
+    names
+        .iter()
+        .enumerate()
+        .map(|(index, name)| PipelineStageConfiguration::new(name, &names[index + 1..names.len().min(index + 2)]))
+        .collect()
+}
 
-    // Job that stores images to disk.
+/// Stages that may receive images not yet redacted, when the pipeline has a
+/// privacy redactor: the redactors themselves and the stages reached from a
+/// source without passing one. Empty when nothing is redacted.
+pub(crate) fn unredacted_input_stages(stage_configs: &[PipelineStageConfiguration]) -> Vec<&str> {
+    if !stage_configs.iter().any(|stage_config| stage_config.stage_type() == Some(StageType::PrivacyRedactor)) {
+        return Vec::new();
+    }
+
+    let mut reached: Vec<&str> = stage_configs
+        .iter()
+        .filter(|stage_config| !stage_configs.iter().any(|other| other.outputs.contains(&stage_config.name)))
+        .map(|stage_config| stage_config.name.as_str())
+        .collect();
+    let mut index = 0;
+    while index < reached.len() {
+        let name = reached[index];
+        index += 1;
+        let Some(stage_config) = stage_configs.iter().find(|stage_config| stage_config.name == name) else {
+            continue;
+        };
+        if stage_config.stage_type() == Some(StageType::PrivacyRedactor) {
+            continue;
+        }
+        for output in &stage_config.outputs {
+            if !reached.contains(&output.as_str()) {
+                reached.push(output);
+            }
+        }
+    }
+    reached
+}
+
+/// Something in the pipeline that would let unredacted images reach the disk.
+pub(crate) struct PrivacyProblem {
+    /// Index of the stage configuration, None for the pipeline as a whole.
+    pub index: Option<usize>,
+
+    /// Setting of the stage, or of the configuration, that causes the problem.
+    pub setting: &'static str,
+
+    pub message: String,
+}
+
+/// Durable queues and sinks that images reach without passing the privacy
+/// redactor, and configured stages without one while redaction is switched on.
+pub(crate) fn privacy_problems(
+    app_config: &AppConfiguration,
+    stage_registry: &StageRegistry,
+    stage_configs: &[PipelineStageConfiguration],
+) -> Vec<PrivacyProblem> {
+    let redacts = stage_configs.iter().any(|stage_config| stage_config.stage_type() == Some(StageType::PrivacyRedactor));
+    if !redacts {
+        if app_config.use_privacy_redaction && !app_config.pipeline_stages.is_empty() {
+            return vec![PrivacyProblem {
+                index: None,
+                setting: "use_privacy_redaction",
+                message: "The pipeline stages have no privacy_redactor, add one in front of image_storer or switch use_privacy_redaction off".to_string(),
+            }];
+        }
+        return Vec::new();
+    }
+
+    let unredacted = unredacted_input_stages(stage_configs);
+    let mut problems = Vec::new();
+    for (index, stage_config) in stage_configs.iter().enumerate() {
+        if !unredacted.contains(&stage_config.name.as_str()) {
+            continue;
+        }
+        if stage_config.durable {
+            problems.push(PrivacyProblem {
+                index: Some(index),
+                setting: "durable",
+                message: format!("Pipeline stage {} can not be durable, its queue would keep unredacted images on disk", stage_config.name),
+            });
+        }
+        let role = stage_config.stage_type().and_then(|stage_type| stage_registry.role(stage_type));
+        if role.is_some_and(|role| role.is_endpoint()) {
+            problems.push(PrivacyProblem {
+                index: Some(index),
+                setting: "name",
+                message: format!("Pipeline stage {} receives images that have not passed the privacy_redactor", stage_config.name),
+            });
+        }
+    }
+    problems
+}
+
+fn create_overflow_settings(stage_config: &PipelineStageConfiguration) -> OverflowSettings {
+    OverflowSettings {
+        policy: stage_config.overflow_policy,
+        priority_count: stage_config.priority_frame_count,
+    }
+}
+
+fn default_error_policy(app_config: &AppConfiguration, stage_type: StageType) -> ErrorPolicyType {
+    match stage_type {
+        StageType::MotionWatcher => app_config.motion_watcher_error_policy,
+        StageType::ImageDetector => app_config.image_detector_error_policy,
+        StageType::ObjectTracker => app_config.object_tracker_error_policy,
+        StageType::NearDuplicateFilter => app_config.near_duplicate_filter_error_policy,
+        StageType::PrivacyRedactor => app_config.privacy_redactor_error_policy,
+        StageType::ImageStorer => app_config.image_storer_error_policy,
+        StageType::LogNotifier => ErrorPolicyType::Skip,
+    }
+}
+
+/// Create a reprocessor that runs the configured object detector over stored images.
+pub fn create_image_reprocessor(app_config: &AppConfiguration, model_version: Option<String>) -> RookLWResult<ImageReprocessor> {
+    let db_pool = create_sqlite_pool(app_config)?;
     let image_info_repository = create_image_info_repository(db_pool)?;
-    let image_store_repository = create_image_store_repository(&app_config)?;
+    let image_store_repository = create_image_store_repository(app_config)?;
+    let object_detector = create_object_detector(app_config)?;
 
-    let image_storer = create_image_storer(
+    Ok(ImageReprocessor::new(
+        image_info_repository,
         image_store_repository,
+        object_detector,
+        model_version,
+        create_tracker_settings(app_config),
+    ))
+}
+
+pub fn create_embedding_clusterer(app_config: &AppConfiguration) -> RookLWResult<EmbeddingClusterer> {
+    let db_pool = create_sqlite_pool(app_config)?;
+    let image_info_repository = create_image_info_repository(db_pool)?;
+
+    Ok(EmbeddingClusterer::new(
         image_info_repository,
-    )?;
+        ClusteringSettings {
+            min_similarity: app_config.cluster_min_similarity,
+            min_cluster_size: app_config.cluster_min_size,
+            iterations: app_config.cluster_iterations,
+        },
+    ))
+}
 
-    let app = App::new(
-        mw,
-        image_storer,
-        image_detector,
-    );
+pub fn create_dataset_exporter(app_config: &AppConfiguration) -> RookLWResult<DatasetExporter> {
+    let db_pool = create_sqlite_pool(app_config)?;
+    let image_info_repository = create_image_info_repository(db_pool)?;
+    let image_store_repository = create_image_store_repository(app_config)?;
 
-    Ok(app)
+    Ok(DatasetExporter::new(
+        image_info_repository,
+        image_store_repository,
+    ))
 }
 
 fn create_image_storer(
+    app_config: &AppConfiguration,
     image_store_repository: Box<dyn ImageStoreRepository>,
-    image_info_repository: Box<dyn ImageInfoRepository>
+    image_info_repository: Box<dyn ImageInfoRepository>,
+    event_repository: Box<dyn EventRepository>,
     ) -> RookLWResult<ImageStorer>
 {
-    Ok(ImageStorer::new(
+    let mut image_storer = ImageStorer::new(
         image_store_repository,
         image_info_repository,
-    ))
+    );
+    image_storer.set_event_recorder(EventRecorder::new(
+        event_repository,
+        HeroFrameWeights {
+            sharpness: app_config.hero_sharpness_weight,
+            confidence: app_config.hero_confidence_weight,
+            size: app_config.hero_size_weight,
+            centering: app_config.hero_centering_weight,
+        },
+    ));
+
+    if app_config.use_interestingness_score {
+        image_storer.set_interestingness_scorer(
+            InterestingnessScorer::new(InterestingnessWeights {
+                confidence: app_config.interestingness_confidence_weight,
+                rarity: app_config.interestingness_rarity_weight,
+                novelty: app_config.interestingness_novelty_weight,
+                sharpness: app_config.interestingness_sharpness_weight,
+            }),
+            chrono::Duration::days(app_config.interestingness_rarity_days),
+        );
+    }
+
+    image_storer.set_jpeg_quality(app_config.jpeg_quality, app_config.negative_jpeg_quality);
+
+    if app_config.use_cluster_assignment {
+        image_storer.set_cluster_assignment(app_config.cluster_min_similarity);
+    }
+
+    Ok(image_storer)
 }
 
 fn create_image_store_repository(app_config: &AppConfiguration) -> RookLWResult<Box<dyn ImageStoreRepository>> {
@@ -81,11 +487,121 @@ fn create_image_store_repository(app_config: &AppConfiguration) -> RookLWResult<
     Ok(Box::new(repo))
 }
 
-fn create_image_detector(app_config: &AppConfiguration) -> RookLWResult<ImageDetector> {
-    let object_detector = create_object_detector(app_config)?;
-    Ok(ImageDetector::new(
-        object_detector,
-    ))
+fn create_image_detector(app_config: &AppConfiguration, live_settings: &LiveStageSettings, memory: DetectorMemory) -> RookLWResult<ImageDetector> {
+    // Always wrapped, so a class policy added on reload is applied.
+    let mut object_detector = ClassPolicyObjectDetector::new(
+        create_model_object_detector(app_config)?,
+        app_config.class_policy.clone(),
+    );
+    object_detector.set_live_policy(live_settings.class_policy.clone());
+    let mut image_detector = ImageDetector::new(
+        Box::new(object_detector),
+    );
+
+    if app_config.image_detector_use_motion_regions {
+        info!("Using motion regions for object detection");
+        image_detector.set_region_detection(RegionDetectionSettings {
+            padding: app_config.image_detector_motion_region_padding,
+            max_coverage: app_config.image_detector_motion_region_max_coverage,
+        });
+    }
+
+    image_detector.set_memory(memory);
+    image_detector.set_live_storage_policy(live_settings.storage_policy.clone());
+
+    Ok(image_detector)
+}
+
+fn create_detector_memory(app_config: &AppConfiguration) -> RookLWResult<DetectorMemory> {
+    let static_memory = if app_config.use_static_suppression {
+        info!(
+            mode = ?app_config.static_suppression_mode,
+            "Using static detection suppression"
+        );
+        Some(StaticDetectionMemory::new(create_static_suppression_settings(app_config)))
+    } else {
+        None
+    };
+
+    Ok(DetectorMemory::new(static_memory, create_storage_policy(app_config)))
+}
+
+fn create_storage_policy(app_config: &AppConfiguration) -> StoragePolicy {
+    match app_config.storage_policy {
+        StoragePolicyType::All => StoragePolicy::All,
+        StoragePolicyType::DetectionsOnly => StoragePolicy::DetectionsOnly,
+        StoragePolicyType::SampledNegatives => StoragePolicy::SampledNegatives(app_config.negative_sample_rate),
+        StoragePolicyType::FirstFrameNegatives => StoragePolicy::FirstFrameNegatives,
+    }
+}
+
+fn create_static_suppression_settings(app_config: &AppConfiguration) -> StaticSuppressionSettings {
+    let mode = match app_config.static_suppression_mode {
+        StaticSuppressionModeType::Suppress => StaticSuppressionMode::Suppress,
+        StaticSuppressionModeType::DownWeight => StaticSuppressionMode::DownWeight(app_config.static_suppression_down_weight),
+    };
+
+    StaticSuppressionSettings {
+        iou_threshold: app_config.static_suppression_iou_threshold,
+        min_events: app_config.static_suppression_min_events,
+        min_duration: chrono::Duration::minutes(app_config.static_suppression_min_duration_minutes),
+        expiry: chrono::Duration::minutes(app_config.static_suppression_expiry_minutes),
+        mode,
+    }
+}
+
+fn create_object_tracker(app_config: &AppConfiguration) -> ObjectTracker {
+    ObjectTracker::new(create_tracker_settings(app_config))
+}
+
+fn create_error_policy(app_config: &AppConfiguration, policy: ErrorPolicyType) -> ErrorPolicy {
+    match policy {
+        ErrorPolicyType::Skip => ErrorPolicy::Skip,
+        ErrorPolicyType::Retry => ErrorPolicy::Retry(app_config.error_policy_retry_count),
+        ErrorPolicyType::Restart => ErrorPolicy::Restart(RestartPolicy {
+            max_restarts: app_config.error_policy_max_restarts,
+            initial_backoff: Duration::from_millis(app_config.error_policy_initial_backoff_ms),
+            max_backoff: Duration::from_millis(app_config.error_policy_max_backoff_ms),
+        }),
+        ErrorPolicyType::Shutdown => ErrorPolicy::Shutdown,
+    }
+}
+
+fn create_privacy_redactor(app_config: &AppConfiguration) -> PrivacyRedactor {
+    let mode = match app_config.privacy_redaction_mode {
+        PrivacyRedactionModeType::Pixelate => RedactionMode::Redact(RedactionMethod::Pixelate(app_config.privacy_redaction_block_size)),
+        PrivacyRedactionModeType::Blur => RedactionMode::Redact(RedactionMethod::Blur(app_config.privacy_redaction_blur_sigma)),
+        PrivacyRedactionModeType::Drop => RedactionMode::Drop,
+    };
+
+    PrivacyRedactor::new(RedactionSettings {
+        classes: app_config.privacy_redaction_classes.clone(),
+        padding: app_config.privacy_redaction_padding,
+        mode,
+    })
+}
+
+fn create_near_duplicate_filter(app_config: &AppConfiguration) -> NearDuplicateFilter {
+    NearDuplicateFilter::new(NearDuplicateSettings {
+        embedding_threshold: app_config.near_duplicate_embedding_threshold,
+        hash_max_distance: app_config.near_duplicate_hash_max_distance,
+        window: chrono::Duration::seconds(app_config.near_duplicate_window_seconds),
+        mode: app_config.near_duplicate_mode,
+    })
+}
+
+fn create_tracker_settings(app_config: &AppConfiguration) -> TrackerSettings {
+    TrackerSettings {
+        iou_threshold: app_config.object_tracker_iou_threshold,
+        max_age: app_config.object_tracker_max_age,
+    }
+}
+
+fn create_capture_settings(app_config: &AppConfiguration) -> CaptureSettings {
+    CaptureSettings {
+        capture_count: app_config.image_capturer_capture_count,
+        capture_interval: Duration::from_millis(app_config.image_capturer_capture_interval_ms),
+    }
 }
 
 fn creat_image_capturer(app_config: &AppConfiguration, frame_source: Arc<Box<dyn FrameSource + Send + Sync>>) -> ImageCapturer {
@@ -96,11 +612,26 @@ fn creat_image_capturer(app_config: &AppConfiguration, frame_source: Arc<Box<dyn
     )
 }
 
-fn create_motion_watcher(app_config: &AppConfiguration, frame_source: Arc<Box<dyn FrameSource + Send + Sync>>) -> RookLWResult<Box<dyn MotionWatcher>> {
-    let image_capturer = creat_image_capturer(app_config, frame_source.clone());
+fn create_motion_watch_settings(app_config: &AppConfiguration) -> MotionWatchSettings {
+    MotionWatchSettings {
+        motion_detect_interval: Duration::from_millis(app_config.motion_watcher_round_interval_ms),
+        motion_watch_count: app_config.motion_watcher_count,
+        round_interval: Duration::from_millis(app_config.motion_watcher_round_interval_ms),
+        thresholds: create_motion_thresholds(app_config),
+    }
+}
+
+fn create_motion_watcher(
+    app_config: &AppConfiguration,
+    frame_source: Arc<Box<dyn FrameSource + Send + Sync>>,
+    live_settings: &LiveStageSettings,
+) -> RookLWResult<Box<dyn MotionWatcher>> {
+    let mut image_capturer = creat_image_capturer(app_config, frame_source.clone());
+    image_capturer.set_live_settings(live_settings.capture.clone());
+    image_capturer.set_event_repository(create_event_repository(create_sqlite_pool(app_config)?)?);
     
-    match app_config.motion_watcher_type.as_str() {
-        "radar" => {
+    match app_config.motion_watcher_type {
+        MotionWatcherType::Radar => {
             let watcher = RadarMotionWatcher::new(
                 app_config.radar_gpio_chip_path.clone(),
                 app_config.radar_gpio_pin,
@@ -108,8 +639,8 @@ fn create_motion_watcher(app_config: &AppConfiguration, frame_source: Arc<Box<dy
             );
             Ok(Box::new(watcher))
         },
-        _ => {
-            let watcher = ImageDiffMotionWatcher::new(
+        MotionWatcherType::ImageDiff => {
+            let mut watcher = ImageDiffMotionWatcher::new(
                 frame_source.clone(),
                 Duration::from_millis(app_config.motion_watcher_round_interval_ms), // motion detect interval
                 app_config.motion_watcher_count,     // motion watch count
@@ -117,6 +648,7 @@ fn create_motion_watcher(app_config: &AppConfiguration, frame_source: Arc<Box<dy
                 image_capturer,
                 Duration::from_millis(app_config.motion_watcher_round_interval_ms),    // round interval
             );
+            watcher.set_live_settings(live_settings.motion_watch.clone());
             Ok(Box::new(watcher))
         }
     }
@@ -189,35 +721,62 @@ fn create_motion_detector(app_config: &AppConfiguration) -> RookLWResult<Box<dyn
         }
     }
 
-    match app_config.motion_detector_type.as_str() {
-        "yplane_motion_percentile" => {
+    match app_config.motion_detector_type {
+        MotionDetectorType::YPlaneMotionPercentile => {
             add_rolling_z_if_enabled(app_config, YPlaneMotionPercentileDetector::new(
                 app_config.yplane_motion_percentile,
                 app_config.yplane_motion_percentile_threshold,
             ))
         },
-        "yplane_boxed_average" => {
+        MotionDetectorType::YPlaneBoxedAverage => {
             add_rolling_z_if_enabled(app_config, YPlaneBoxedAverageMotionDetector::new(
                 app_config.yplane_boxed_average_motion_detector_box_size, 
                 app_config.yplane_boxed_average_motion_detector_percentile,
                 app_config.yplane_boxed_average_motion_detector_threshold,
             ))
         },
-        other => Err(RookLWError::Initialization(format!(
-            "Unknown motion detector type: {}",
-            other
-        ))),
+    }
+}
+
+/// Thresholds of the configured motion detector type.
+fn create_motion_thresholds(app_config: &AppConfiguration) -> MotionThresholds {
+    let (percentile, percentile_threshold) = match app_config.motion_detector_type {
+        MotionDetectorType::YPlaneMotionPercentile => (app_config.yplane_motion_percentile, app_config.yplane_motion_percentile_threshold),
+        MotionDetectorType::YPlaneBoxedAverage => (
+            app_config.yplane_boxed_average_motion_detector_percentile,
+            app_config.yplane_boxed_average_motion_detector_threshold,
+        ),
+    };
+    MotionThresholds {
+        percentile,
+        percentile_threshold,
+        z_threshold: app_config.yplane_rolling_z_threshold,
     }
 }
 
 fn create_object_detector(app_config: &AppConfiguration) -> RookLWResult<Box<dyn ObjectDetector>> {
-    match app_config.object_detector_type.as_str() {
-        "opencv" => Ok(Box::new(create_opencv_object_detector(app_config)?)),
-        "yolov8" => Ok(Box::new(create_yolov8_object_detector(app_config)?)),
-        other => Err(RookLWError::Initialization(format!(
-            "Unknown object detector type: {}",
-            other
-        ))),
+    add_class_policy_if_configured(app_config, create_model_object_detector(app_config)?)
+}
+
+fn create_model_object_detector(app_config: &AppConfiguration) -> RookLWResult<Box<dyn ObjectDetector>> {
+    let object_detector: Box<dyn ObjectDetector> = match app_config.object_detector_type {
+        ObjectDetectorType::OpenCV => Box::new(create_opencv_object_detector(app_config)?),
+        ObjectDetectorType::Yolov8 => Box::new(create_yolov8_object_detector(app_config)?),
+    };
+
+    Ok(object_detector)
+}
+
+fn add_class_policy_if_configured(app_config: &AppConfiguration, object_detector: Box<dyn ObjectDetector>) -> RookLWResult<Box<dyn ObjectDetector>> {
+    if app_config.class_policy.is_empty() {
+        Ok(object_detector)
+    }
+    else {
+        info!(class_policy = ?app_config.class_policy, "Using class policy for object detection");
+        Ok(Box::new(ClassPolicyObjectDetector::new(
+            object_detector,
+            app_config.class_policy.clone(),
+        )))
     }
 }
 
@@ -237,6 +796,7 @@ fn create_yolov8_object_detector(app_config: &AppConfiguration) -> RookLWResult<
         app_config.yolov8_model_path.as_str(),
         app_config.yolov8_model_names_path.as_str(),
         app_config.yolov8_model_confidence_threshold,
+        app_config.yolov8_intra_threads,
     )?;
 
     Ok(object_detector)
@@ -247,6 +807,10 @@ fn create_sqlite_pool(app_config: &AppConfiguration) -> RookLWResult<Pool<Sqlite
     Ok(pool)
 }
 
+fn create_event_repository(pool: Pool<SqliteConnectionManager>) -> RookLWResult<Box<dyn EventRepository>> {
+    Ok(Box::new(EventRepositorySqlite::new(pool)?))
+}
+
 fn create_image_info_repository(pool: Pool<SqliteConnectionManager>) -> RookLWResult<Box<dyn ImageInfoRepository>> {
     let repo = ImageInfoRepositorySqlite::new(
         pool
@@ -0,0 +1,477 @@
+use crate::{RookLWError, RookLWResult};
+use crate::app::{
+    create_live_stage_settings, create_pipeline_stage_configurations, create_stage_registry, graph_problems,
+    privacy_problems, AppConfiguration, MotionWatcherType, ObjectDetectorType, PipelineStageConfiguration,
+    StageRegistry, StageRole,
+};
+use crate::image::frame::FrameSourceFactory;
+
+use serde_json::Value;
+use toml::Spanned;
+use toml::de::{DeTable, DeValue};
+
+use std::fmt::Display;
+use std::net::SocketAddr;
+use std::ops::Range;
+use std::path::Path;
+
+/// A setting the daemon would fail to start with, or would misbehave with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigProblem {
+    /// Setting name, `pipeline_stages[1].overflow_policy` for nested ones.
+    pub key: String,
+
+    /// Line and column of the setting in the configuration file, starting at 1.
+    /// None for settings left at their default.
+    pub location: Option<(usize, usize)>,
+
+    pub message: String,
+}
+
+impl Display for ConfigProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some((line, column)) = self.location {
+            write!(f, "{}:{}: ", line, column)?;
+        }
+        if !self.key.is_empty() {
+            write!(f, "{}: ", self.key)?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Check the configuration file without starting anything: syntax, unknown
+/// settings, enum values, paths, model files and numeric ranges.
+///
+/// Relative paths are checked against the working directory, as the daemon
+/// resolves them. Errors only when the file can't be read.
+pub fn check_config(config_path: &str) -> RookLWResult<Vec<ConfigProblem>> {
+    let config_str = std::fs::read_to_string(config_path)
+        .map_err(|e| RookLWError::Config(format!("Failed to read app configuration file {}: {}", config_path, e)))?;
+    Ok(check_config_str(&config_str))
+}
+
+fn check_config_str(config_str: &str) -> Vec<ConfigProblem> {
+    let table = match DeTable::parse(config_str) {
+        Ok(table) => table,
+        Err(e) => return vec![ConfigProblem {
+            key: String::new(),
+            location: e.span().map(|span| line_column(config_str, span.start)),
+            message: e.message().to_string(),
+        }],
+    };
+    let mut entries = Vec::new();
+    collect_entries("", table.get_ref(), &mut entries);
+
+    let config: AppConfiguration = match toml::from_str(config_str) {
+        Ok(config) => config,
+        Err(e) => {
+            let span = e.span().unwrap_or_default();
+            let entry = entries
+                .iter()
+                .filter(|entry| entry.value_span.start <= span.start && span.end <= entry.value_span.end)
+                .max_by_key(|entry| entry.value_span.start);
+            return vec![ConfigProblem {
+                key: entry.map(|entry| entry.key.clone()).unwrap_or_default(),
+                location: Some(line_column(config_str, entry.map_or(span.start, |entry| entry.key_span.start))),
+                message: e.message().to_string(),
+            }];
+        },
+    };
+
+    let mut checker = ConfigChecker::default();
+    check_unknown_settings(&mut checker, table.get_ref(), &config);
+    check_settings(&mut checker, &config);
+
+    let mut problems: Vec<ConfigProblem> = checker.problems
+        .into_iter()
+        .map(|mut problem| {
+            problem.location = entries
+                .iter()
+                .find(|entry| entry.key == problem.key)
+                .map(|entry| line_column(config_str, entry.key_span.start));
+            problem
+        })
+        .collect();
+    problems.sort_by_key(|problem| problem.location);
+    problems
+}
+
+#[derive(Default)]
+struct ConfigChecker {
+    problems: Vec<ConfigProblem>,
+}
+
+impl ConfigChecker {
+    fn problem(&mut self, key: impl Into<String>, message: impl Into<String>) {
+        self.problems.push(ConfigProblem { key: key.into(), location: None, message: message.into() });
+    }
+
+    fn at_least<T: PartialOrd + Display>(&mut self, key: &str, value: T, min: T) {
+        if value < min {
+            self.problem(key, format!("{} is below the minimum of {}", value, min));
+        }
+    }
+
+    fn between<T: PartialOrd + Display>(&mut self, key: &str, value: T, min: T, max: T) {
+        if value < min || value > max {
+            self.problem(key, format!("{} is outside the range {} to {}", value, min, max));
+        }
+    }
+
+    fn file(&mut self, key: &str, path: &str) {
+        if !Path::new(path).is_file() {
+            self.problem(key, format!("file {} does not exist", path));
+        }
+    }
+
+    /// A path the daemon creates when it is missing.
+    fn created_path(&mut self, key: &str, path: &str, directory: bool) {
+        let path_ref = Path::new(path);
+        if path.is_empty() {
+            self.problem(key, "path is empty");
+        }
+        else if directory && path_ref.exists() && !path_ref.is_dir() {
+            self.problem(key, format!("{} is not a directory", path));
+        }
+        else if !directory && path_ref.is_dir() {
+            self.problem(key, format!("{} is a directory", path));
+        }
+    }
+}
+
+fn check_settings(checker: &mut ConfigChecker, config: &AppConfiguration) {
+    // paths
+    checker.created_path("image_directory", &config.image_directory, true);
+    checker.created_path("database_path", &config.database_path, false);
+    checker.created_path("durable_queue_path", &config.durable_queue_path, false);
+    if config.use_control_socket {
+        checker.created_path("control_socket_path", &config.control_socket_path, false);
+    }
+    if config.use_metrics_server && config.metrics_server_address.parse::<SocketAddr>().is_err() {
+        checker.problem("metrics_server_address", format!("{} is not an address like 127.0.0.1:9464", config.metrics_server_address));
+    }
+    if let Some(frame_source) = &config.frame_source {
+        let available = FrameSourceFactory::available_sources();
+        if !available.contains(&frame_source.as_str()) {
+            checker.problem("frame_source", format!("{} is not one of the sources built in: {:?}", frame_source, available));
+        }
+    }
+
+    // model files
+    match config.object_detector_type {
+        ObjectDetectorType::OpenCV => {
+            checker.file("opencv_model_config_path", &config.opencv_model_config_path);
+            checker.file("opencv_model_weights_path", &config.opencv_model_weights_path);
+            checker.file("opencv_model_names_path", &config.opencv_model_names_path);
+        },
+        ObjectDetectorType::Yolov8 => {
+            checker.file("yolov8_model_path", &config.yolov8_model_path);
+            checker.file("yolov8_model_names_path", &config.yolov8_model_names_path);
+        },
+    }
+    if config.use_motion_watcher && config.motion_watcher_type == MotionWatcherType::Radar
+        && let Some(chip_path) = &config.radar_gpio_chip_path
+        && !Path::new(chip_path).exists()
+    {
+        checker.problem("radar_gpio_chip_path", format!("{} does not exist", chip_path));
+    }
+
+    check_pipeline_stages(checker, config);
+    check_ranges(checker, config);
+}
+
+/// Settings out of their range, without looking at files or the pipeline.
+/// Reloads apply none of the settings when any of them is reported.
+pub fn check_config_values(config: &AppConfiguration) -> Vec<ConfigProblem> {
+    let mut checker = ConfigChecker::default();
+    check_ranges(&mut checker, config);
+    checker.problems
+}
+
+fn check_ranges(checker: &mut ConfigChecker, config: &AppConfiguration) {
+    checker.at_least("motion_watcher_count", config.motion_watcher_count, 1);
+    // The image diff watcher hands on the two frames it compared as the first captures.
+    let min_capture_count = if config.use_motion_watcher && config.motion_watcher_type == MotionWatcherType::ImageDiff { 2 } else { 1 };
+    checker.at_least("image_capturer_capture_count", config.image_capturer_capture_count, min_capture_count);
+    checker.between("yplane_motion_percentile", config.yplane_motion_percentile, 0.0, 1.0);
+    checker.between("yplane_motion_percentile_threshold", config.yplane_motion_percentile_threshold, 0.0, 1.0);
+    checker.at_least("yplane_boxed_average_motion_detector_box_size", config.yplane_boxed_average_motion_detector_box_size, 1);
+    checker.between("yplane_boxed_average_motion_detector_percentile", config.yplane_boxed_average_motion_detector_percentile, 0.0, 1.0);
+    checker.between("yplane_boxed_average_motion_detector_threshold", config.yplane_boxed_average_motion_detector_threshold, 0.0, 1.0);
+    checker.between("yplane_rolling_z_alpha", config.yplane_rolling_z_alpha, f64::MIN_POSITIVE, 1.0);
+    checker.at_least("yplane_rolling_z_threshold", config.yplane_rolling_z_threshold, 0.0);
+    checker.between("opencv_model_confidence_threshold", config.opencv_model_confidence_threshold, 0.0, 1.0);
+    checker.between("yolov8_model_confidence_threshold", config.yolov8_model_confidence_threshold, 0.0, 1.0);
+    checker.at_least("yolov8_intra_threads", config.yolov8_intra_threads, 1);
+    checker.at_least("image_detector_motion_region_padding", config.image_detector_motion_region_padding, 0.0);
+    checker.between("image_detector_motion_region_max_coverage", config.image_detector_motion_region_max_coverage, 0.0, 1.0);
+    checker.between("negative_sample_rate", config.negative_sample_rate, 0.0, 1.0);
+    checker.between("jpeg_quality", config.jpeg_quality, 1, 100);
+    checker.between("negative_jpeg_quality", config.negative_jpeg_quality, 1, 100);
+    checker.between("object_tracker_iou_threshold", config.object_tracker_iou_threshold, 0.0, 1.0);
+    checker.between("static_suppression_down_weight", config.static_suppression_down_weight, 0.0, 1.0);
+    checker.between("static_suppression_iou_threshold", config.static_suppression_iou_threshold, 0.0, 1.0);
+    checker.between("near_duplicate_embedding_threshold", config.near_duplicate_embedding_threshold, 0.0, 1.0);
+    checker.between("near_duplicate_hash_max_distance", config.near_duplicate_hash_max_distance, 0, 64);
+    checker.at_least("near_duplicate_window_seconds", config.near_duplicate_window_seconds, 0);
+    checker.between("cluster_min_similarity", config.cluster_min_similarity, 0.0, 1.0);
+    checker.at_least("cluster_min_size", config.cluster_min_size, 1);
+    checker.at_least("privacy_redaction_padding", config.privacy_redaction_padding, 0.0);
+    checker.at_least("privacy_redaction_block_size", config.privacy_redaction_block_size, 1);
+    checker.at_least("privacy_redaction_blur_sigma", config.privacy_redaction_blur_sigma, 0.0);
+    for (key, weight) in [
+        ("interestingness_confidence_weight", config.interestingness_confidence_weight),
+        ("interestingness_rarity_weight", config.interestingness_rarity_weight),
+        ("interestingness_novelty_weight", config.interestingness_novelty_weight),
+        ("interestingness_sharpness_weight", config.interestingness_sharpness_weight),
+        ("hero_sharpness_weight", config.hero_sharpness_weight),
+        ("hero_confidence_weight", config.hero_confidence_weight),
+        ("hero_size_weight", config.hero_size_weight),
+        ("hero_centering_weight", config.hero_centering_weight),
+    ] {
+        checker.at_least(key, weight, 0.0);
+    }
+    if config.error_policy_initial_backoff_ms > config.error_policy_max_backoff_ms {
+        checker.problem("error_policy_initial_backoff_ms", format!(
+            "{} is above error_policy_max_backoff_ms of {}",
+            config.error_policy_initial_backoff_ms,
+            config.error_policy_max_backoff_ms
+        ));
+    }
+}
+
+fn check_pipeline_stages(checker: &mut ConfigChecker, config: &AppConfiguration) {
+    let registry = create_stage_registry(&create_live_stage_settings(config));
+    let stage_types: Vec<String> = registry.stage_types().iter().map(|stage_type| stage_type.to_string()).collect();
+    let stage_configs = create_pipeline_stage_configurations(config);
+
+    // The default chain only has registered stage types and default stage settings.
+    if !config.pipeline_stages.is_empty() {
+        for (index, stage_config) in stage_configs.iter().enumerate() {
+            let key = |name: &str| format!("pipeline_stages[{}].{}", index, name);
+            let registered = stage_config.stage_type().and_then(|stage_type| registry.role(stage_type)).is_some();
+            if !registered {
+                checker.problem(
+                    key(if stage_config.stage_type.is_some() { "type" } else { "name" }),
+                    format!("unknown stage type {}, expected one of {:?}", stage_config.name, stage_types),
+                );
+            }
+            checker.at_least(&key("capacity"), stage_config.capacity, 1);
+            checker.at_least(&key("workers"), stage_config.workers, 1);
+        }
+        check_pipeline_graph(checker, &registry, &stage_configs);
+    }
+
+    for problem in privacy_problems(config, &registry, &stage_configs) {
+        let key = match problem.index {
+            Some(index) => format!("pipeline_stages[{}].{}", index, problem.setting),
+            None => problem.setting.to_string(),
+        };
+        checker.problem(key, problem.message);
+    }
+}
+
+/// Report what would keep the daemon from starting the pipeline graph, at the
+/// stage it was found at.
+fn check_pipeline_graph(checker: &mut ConfigChecker, registry: &StageRegistry, stage_configs: &[PipelineStageConfiguration]) {
+    // Unknown stage types are reported already, and leave the roles unknown.
+    let Some(nodes) = stage_configs
+        .iter()
+        .map(|stage_config| stage_config
+            .stage_type()
+            .and_then(|stage_type| registry.role(stage_type))
+            .map(|role| (stage_config.name.as_str(), role, stage_config.outputs.as_slice())))
+        .collect::<Option<Vec<(&str, StageRole, &[String])>>>()
+    else {
+        return;
+    };
+
+    for problem in graph_problems(&nodes) {
+        let index = problem.stage
+            .as_deref()
+            .and_then(|stage| stage_configs.iter().position(|stage_config| stage_config.name == stage));
+        let key = match index {
+            Some(index) if stage_configs[index].outputs.is_empty() => format!("pipeline_stages[{}].name", index),
+            Some(index) => format!("pipeline_stages[{}].outputs", index),
+            None => "pipeline_stages".to_string(),
+        };
+        checker.problem(key, problem.message);
+    }
+}
+
+/// Report settings in the file that the configuration doesn't have, which
+/// would otherwise be ignored silently.
+fn check_unknown_settings(checker: &mut ConfigChecker, table: &DeTable, config: &AppConfiguration) {
+    let Ok(known) = serde_json::to_value(config) else {
+        return;
+    };
+    check_unknown_keys(checker, "", table, &known);
+
+    let stage_known = serde_json::to_value(PipelineStageConfiguration::default()).unwrap_or_default();
+    if let Some(stages) = table.iter().find(|(key, _)| key.get_ref() == "pipeline_stages").and_then(|(_, value)| value.get_ref().as_array()) {
+        for (index, stage) in stages.iter().enumerate() {
+            if let Some(stage) = stage.get_ref().as_table() {
+                check_unknown_keys(checker, &format!("pipeline_stages[{}].", index), stage, &stage_known);
+            }
+        }
+    }
+}
+
+fn check_unknown_keys(checker: &mut ConfigChecker, prefix: &str, table: &DeTable, known: &Value) {
+    for (key, value) in table.iter() {
+        let name = format!("{}{}", prefix, key.get_ref());
+        match known.get(key.get_ref().as_ref()) {
+            None => checker.problem(name, "unknown setting"),
+            // Only settings grouped in a struct are known by name; maps take any key.
+            Some(Value::Object(fields)) if !fields.is_empty() => {
+                if let Some(value) = value.get_ref().as_table() {
+                    check_unknown_keys(checker, &format!("{}.", name), value, &Value::Object(fields.clone()));
+                }
+            },
+            Some(_) => {},
+        }
+    }
+}
+
+/// A setting in the file, with the byte ranges of its key and value.
+struct ConfigEntry {
+    key: String,
+    key_span: Range<usize>,
+    value_span: Range<usize>,
+}
+
+fn collect_entries(prefix: &str, table: &DeTable, entries: &mut Vec<ConfigEntry>) {
+    for (key, value) in table.iter() {
+        let name = format!("{}{}", prefix, key.get_ref());
+        collect_value(&name, key.span(), value, entries);
+    }
+}
+
+fn collect_value(name: &str, key_span: Range<usize>, value: &Spanned<DeValue>, entries: &mut Vec<ConfigEntry>) {
+    entries.push(ConfigEntry { key: name.to_string(), key_span: key_span.clone(), value_span: value.span() });
+    match value.get_ref() {
+        DeValue::Table(table) => collect_entries(&format!("{}.", name), table, entries),
+        DeValue::Array(array) => {
+            for (index, item) in array.iter().enumerate() {
+                // Tables of an array have no key of their own; point at the array.
+                let item_key_span = if item.get_ref().is_table() { key_span.clone() } else { item.span() };
+                collect_value(&format!("{}[{}]", name, index), item_key_span, item, entries);
+            }
+        },
+        _ => {},
+    }
+}
+
+fn line_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.chars().rev().take_while(|c| *c != '\n').count() + 1;
+    (line, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn problems_point_at_the_setting() {
+        let problems = check_config_str("jpeg_quality = 0\nmotion_watcher_count = 5\nmotion_watchr_count = 5\n");
+        let problem = problems.iter().find(|problem| problem.key == "jpeg_quality").unwrap();
+        assert_eq!(problem.to_string(), "1:1: jpeg_quality: 0 is outside the range 1 to 100");
+        let problem = problems.iter().find(|problem| problem.key == "motion_watchr_count").unwrap();
+        assert_eq!(problem.to_string(), "3:1: motion_watchr_count: unknown setting");
+
+        let problems = check_config_str("jpeg_quality = 85\n\nmotion_detector_type = \"yplane_percentile\"\n");
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].key, "motion_detector_type");
+        assert_eq!(problems[0].location, Some((3, 1)));
+        assert!(problems[0].message.contains("unknown variant `yplane_percentile`"));
+
+        let problems = check_config_str("storage_policy = \"some_negatives\"\n");
+        assert_eq!((problems[0].key.as_str(), problems[0].location), ("storage_policy", Some((1, 1))));
+        let problems = check_config_str(
+            "[[pipeline_stages]]\nname = \"motion_watcher\"\noutputs = [\"image_storer\"]\n\
+             [[pipeline_stages]]\nname = \"image_storer\"\noverflow_policy = \"drop_best\"\n",
+        );
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].key, "pipeline_stages[1].overflow_policy");
+        assert_eq!(problems[0].location, Some((6, 1)));
+        assert!(problems[0].message.contains("unknown variant `drop_best`"));
+    }
+
+    #[test]
+    fn stages_up_to_the_privacy_redactor_can_not_be_durable() {
+        let stages = |durable_stage: &str| format!(
+            "[[pipeline_stages]]\nname = \"motion_watcher\"\noutputs = [\"image_detector\"]\n\
+             [[pipeline_stages]]\nname = \"image_detector\"\noutputs = [\"privacy_redactor\"]\ndurable = {}\n\
+             [[pipeline_stages]]\nname = \"privacy_redactor\"\noutputs = [\"image_storer\"]\ndurable = {}\n\
+             [[pipeline_stages]]\nname = \"image_storer\"\ndurable = {}\n",
+            durable_stage == "image_detector",
+            durable_stage == "privacy_redactor",
+            durable_stage == "image_storer",
+        );
+        let durable_problems = |durable_stage: &str| -> Vec<String> {
+            check_config_str(&stages(durable_stage))
+                .into_iter()
+                .filter(|problem| problem.key.ends_with(".durable"))
+                .map(|problem| problem.key)
+                .collect()
+        };
+
+        assert_eq!(durable_problems("image_detector"), vec!["pipeline_stages[1].durable"]);
+        assert_eq!(durable_problems("privacy_redactor"), vec!["pipeline_stages[2].durable"]);
+        assert!(durable_problems("image_storer").is_empty());
+    }
+
+    #[test]
+    fn images_can_only_be_stored_after_the_privacy_redactor() {
+        let privacy_problems = |config: &str| -> Vec<String> {
+            check_config_str(config)
+                .into_iter()
+                .filter(|problem| problem.key.ends_with(".name") || problem.key == "use_privacy_redaction")
+                .map(|problem| problem.key)
+                .collect()
+        };
+
+        // A second path from the detector to the storer skips the redactor.
+        let bypass = "[[pipeline_stages]]\nname = \"motion_watcher\"\noutputs = [\"image_detector\"]\n\
+            [[pipeline_stages]]\nname = \"image_detector\"\noutputs = [\"privacy_redactor\", \"image_storer\"]\n\
+            [[pipeline_stages]]\nname = \"privacy_redactor\"\noutputs = [\"image_storer\"]\n\
+            [[pipeline_stages]]\nname = \"image_storer\"\n";
+        assert_eq!(privacy_problems(bypass), vec!["pipeline_stages[3].name"]);
+
+        let without_redactor = "[[pipeline_stages]]\nname = \"motion_watcher\"\noutputs = [\"image_detector\"]\n\
+            [[pipeline_stages]]\nname = \"image_detector\"\noutputs = [\"image_storer\"]\n\
+            [[pipeline_stages]]\nname = \"image_storer\"\n";
+        assert_eq!(privacy_problems(without_redactor), vec!["use_privacy_redaction"]);
+        let without_redaction = format!("use_privacy_redaction = false\n{}", without_redactor);
+        assert!(privacy_problems(&without_redaction).is_empty());
+    }
+
+    #[test]
+    fn graph_problems_point_at_their_stage() {
+        let problems = check_config_str(
+            "[[pipeline_stages]]\nname = \"motion_watcher\"\noutputs = [\"image_detector\"]\n\
+             [[pipeline_stages]]\nname = \"image_detector\"\noutputs = [\"object_tracker\"]\n\
+             [[pipeline_stages]]\nname = \"object_tracker\"\noutputs = [\"image_detector\"]\n\
+             [[pipeline_stages]]\nname = \"image_storer\"\noutputs = [\"object_tracker\"]\n",
+        );
+        let graph_problems: Vec<(&str, &str)> = problems
+            .iter()
+            .filter(|problem| problem.key.starts_with("pipeline_stages"))
+            .map(|problem| (problem.key.as_str(), problem.message.as_str()))
+            .collect();
+        assert_eq!(graph_problems, vec![
+            ("pipeline_stages[1].outputs", "Pipeline has a cycle through stage image_detector"),
+            ("pipeline_stages[3].outputs", "Pipeline stage image_storer outputs stored images to object_tracker, which takes captured images"),
+            ("pipeline_stages[3].outputs", "Pipeline stage image_storer receives no input"),
+        ]);
+
+        let problems = check_config_str("image_capturer_capture_count = 1\n");
+        let problem = problems.iter().find(|problem| problem.key == "image_capturer_capture_count").unwrap();
+        assert_eq!(problem.to_string(), "1:1: image_capturer_capture_count: 1 is below the minimum of 2");
+        let problems = check_config_str("image_capturer_capture_count = 1\nmotion_watcher_type = \"radar\"\n");
+        assert!(!problems.iter().any(|problem| problem.key == "image_capturer_capture_count"));
+    }
+}
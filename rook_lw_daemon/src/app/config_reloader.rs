@@ -0,0 +1,216 @@
+use crate::{RookLWError, RookLWResult};
+use crate::app::{check_config_values, AppConfiguration};
+use crate::image::object_detection::ClassPolicy;
+use crate::image::tracking::TrackerSettings;
+use crate::prodcon::LiveSettings;
+use crate::tasks::image_capturer::CaptureSettings;
+use crate::tasks::image_detector::StoragePolicy;
+use crate::tasks::image_diff_motion_watcher::MotionWatchSettings;
+
+use serde_json::Value;
+use tracing::info;
+
+use std::sync::{Arc, Mutex};
+
+/// Configuration settings applied to the running stages on reload. Changes to
+/// any other setting only take effect after a restart.
+const LIVE_SETTINGS: &[&str] = &[
+    "motion_watcher_count",
+    "motion_watcher_round_interval_ms",
+    "image_capturer_capture_count",
+    "image_capturer_capture_interval_ms",
+    "yplane_motion_percentile",
+    "yplane_motion_percentile_threshold",
+    "yplane_boxed_average_motion_detector_percentile",
+    "yplane_boxed_average_motion_detector_threshold",
+    "yplane_rolling_z_threshold",
+    "class_policy",
+    "storage_policy",
+    "negative_sample_rate",
+    "object_tracker_iou_threshold",
+    "object_tracker_max_age",
+];
+
+type CreateLiveSettings = dyn Fn(&AppConfiguration) -> LiveStageSettings + Send + Sync;
+
+/// Settings of the running stages that a configuration reload can replace.
+/// Every stage created from the configuration holds a clone.
+#[derive(Clone)]
+pub struct LiveStageSettings {
+    pub capture: LiveSettings<CaptureSettings>,
+    pub motion_watch: LiveSettings<MotionWatchSettings>,
+    pub class_policy: LiveSettings<ClassPolicy>,
+    pub storage_policy: LiveSettings<StoragePolicy>,
+    pub tracker: LiveSettings<TrackerSettings>,
+}
+
+impl LiveStageSettings {
+    /// Replace every setting with the one in `other`.
+    pub fn replace_from(&self, other: &LiveStageSettings) {
+        self.capture.replace((*other.capture.current()).clone());
+        self.motion_watch.replace((*other.motion_watch.current()).clone());
+        self.class_policy.replace((*other.class_policy.current()).clone());
+        self.storage_policy.replace(*other.storage_policy.current());
+        self.tracker.replace((*other.tracker.current()).clone());
+    }
+}
+
+/// What a reload changed, by setting name.
+#[derive(Debug, Default, PartialEq)]
+pub struct ReloadReport {
+    /// Settings applied to the running stages.
+    pub applied: Vec<String>,
+
+    /// Settings that differ from the running configuration but need a restart.
+    pub restart_required: Vec<String>,
+}
+
+impl ReloadReport {
+    pub fn message(&self) -> String {
+        let mut message = if self.applied.is_empty() {
+            "No settings to apply".to_string()
+        } else {
+            format!("Applied {}", self.applied.join(", "))
+        };
+        if !self.restart_required.is_empty() {
+            message.push_str(&format!("; restart the daemon to apply {}", self.restart_required.join(", ")));
+        }
+        message
+    }
+}
+
+/// Reads the configuration file again and applies the settings that can
+/// change while the daemon runs.
+///
+/// A reload applies all live settings or, when any of them is invalid, none.
+#[derive(Clone)]
+pub struct ConfigReloader {
+    config_path: String,
+    config: Arc<Mutex<AppConfiguration>>,
+    live_settings: LiveStageSettings,
+    create_live_settings: Arc<CreateLiveSettings>,
+}
+
+impl ConfigReloader {
+    /// `config` is the running configuration, read from `config_path`.
+    /// `create_live_settings` creates the live settings for a configuration.
+    pub fn new<F>(config_path: impl Into<String>, config: AppConfiguration, live_settings: LiveStageSettings, create_live_settings: F) -> Self
+        where F: Fn(&AppConfiguration) -> LiveStageSettings + Send + Sync + 'static
+    {
+        Self {
+            config_path: config_path.into(),
+            config: Arc::new(Mutex::new(config)),
+            live_settings,
+            create_live_settings: Arc::new(create_live_settings),
+        }
+    }
+
+    /// The configuration the daemon is running with, including applied reloads.
+    pub fn config(&self) -> AppConfiguration {
+        self.config.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn reload(&self) -> RookLWResult<ReloadReport> {
+        let loaded = serde_json::to_value(AppConfiguration::load(&self.config_path)?)?;
+
+        let mut config = self.config.lock().unwrap_or_else(|e| e.into_inner());
+        let mut running = serde_json::to_value(&*config)?;
+        let report = compare_settings(&running, &loaded);
+        if report.applied.is_empty() {
+            return Ok(report);
+        }
+
+        if let (Some(running), Some(loaded)) = (running.as_object_mut(), loaded.as_object()) {
+            for name in &report.applied {
+                running.insert(name.clone(), loaded[name].clone());
+            }
+        }
+        let reloaded: AppConfiguration = serde_json::from_value(running)?;
+
+        // Only the applied settings changed; the others are as the daemon started with.
+        let problems: Vec<String> = check_config_values(&reloaded)
+            .into_iter()
+            .filter(|problem| report.applied.contains(&problem.key))
+            .map(|problem| problem.to_string())
+            .collect();
+        if !problems.is_empty() {
+            return Err(RookLWError::Config(format!("Invalid settings, none applied: {}", problems.join("; "))));
+        }
+
+        let live_settings = (self.create_live_settings)(&reloaded);
+        self.live_settings.replace_from(&live_settings);
+        *config = reloaded;
+
+        info!(applied = ?report.applied, restart_required = ?report.restart_required, "Configuration reloaded");
+        Ok(report)
+    }
+}
+
+fn compare_settings(running: &Value, loaded: &Value) -> ReloadReport {
+    let mut report = ReloadReport::default();
+    let (Some(running), Some(loaded)) = (running.as_object(), loaded.as_object()) else {
+        return report;
+    };
+    for (name, value) in loaded {
+        if running.get(name) == Some(value) {
+            continue;
+        }
+        if LIVE_SETTINGS.contains(&name.as_str()) {
+            report.applied.push(name.clone());
+        } else {
+            report.restart_required.push(name.clone());
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compare_settings_splits_live_and_restart_settings() {
+        let running = AppConfiguration::default();
+        let mut loaded = running.clone();
+        loaded.image_capturer_capture_count += 1;
+        loaded.class_policy.ignore.push("bench".to_string());
+        loaded.yolov8_model_path = "models/other.onnx".to_string();
+
+        let report = compare_settings(
+            &serde_json::to_value(&running).unwrap(),
+            &serde_json::to_value(&loaded).unwrap(),
+        );
+        assert_eq!(report.applied, vec!["class_policy", "image_capturer_capture_count"]);
+        assert_eq!(report.restart_required, vec!["yolov8_model_path"]);
+        assert_eq!(
+            report.message(),
+            "Applied class_policy, image_capturer_capture_count; restart the daemon to apply yolov8_model_path"
+        );
+    }
+
+    #[test]
+    fn reload_applies_no_setting_when_one_is_out_of_range() {
+        let config_path = std::env::temp_dir().join(format!("rook_lw_reload_{}.toml", std::process::id()));
+        let config = AppConfiguration::default();
+        let live_settings = crate::app::create_live_stage_settings(&config);
+        let reloader = ConfigReloader::new(
+            config_path.to_string_lossy(),
+            config.clone(),
+            live_settings.clone(),
+            crate::app::create_live_stage_settings,
+        );
+
+        // The image diff watcher hands on two captures of its own.
+        std::fs::write(&config_path, "image_capturer_capture_count = 1\nobject_tracker_iou_threshold = 0.5\n").unwrap();
+        let e = reloader.reload().unwrap_err();
+        assert!(e.to_string().contains("image_capturer_capture_count: 1 is below the minimum of 2"));
+        assert_eq!(live_settings.capture.current().capture_count, config.image_capturer_capture_count);
+        assert_eq!(live_settings.tracker.current().iou_threshold, config.object_tracker_iou_threshold);
+
+        std::fs::write(&config_path, "image_capturer_capture_count = 2\nobject_tracker_iou_threshold = 0.5\n").unwrap();
+        assert_eq!(reloader.reload().unwrap().applied, vec!["image_capturer_capture_count", "object_tracker_iou_threshold"]);
+        assert_eq!(live_settings.capture.current().capture_count, 2);
+
+        let _ = std::fs::remove_file(&config_path);
+    }
+}
@@ -0,0 +1,188 @@
+use crate::{RookLWError, RookLWResult};
+use crate::app::{create_pipeline_stage_configurations, AppConfiguration, ConfigReloader, StageType};
+use crate::image::conversions::dynamic_image_to_jpeg;
+use crate::prodcon::{HealthRegistry, ShutdownSignal};
+use crate::tasks::motion_watcher::WatcherControl;
+
+use rook_lw_control::{ControlRequest, ControlResponse, DaemonStatus};
+use rook_lw_image_repo::event::EventRepository;
+use rook_lw_image_repo::image_store::ImageStoreRepository;
+use rook_lw_models::event::EventSearchOptions;
+
+use tracing::{info, warn};
+
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+use std::thread::spawn;
+use std::time::Duration;
+
+// A client that takes longer than this to send its request is dropped.
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(5);
+
+// How long a snapshot waits for the motion watcher to capture a frame.
+const SNAPSHOT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Serves the control protocol of `rook_lw_control` on a unix socket.
+///
+/// Anyone who can connect to the socket can stop the daemon, so it is only
+/// as protected as its directory.
+pub struct ControlServer {
+    socket_path: String,
+    config_reloader: ConfigReloader,
+    event_repository: Box<dyn EventRepository>,
+    image_store_repository: Box<dyn ImageStoreRepository>,
+}
+
+struct ControlContext {
+    watcher_control: WatcherControl,
+    health: HealthRegistry,
+    shutdown: ShutdownSignal,
+}
+
+impl ControlServer {
+    pub fn new(
+        socket_path: impl Into<String>,
+        config_reloader: ConfigReloader,
+        event_repository: Box<dyn EventRepository>,
+        image_store_repository: Box<dyn ImageStoreRepository>,
+    ) -> Self {
+        Self {
+            socket_path: socket_path.into(),
+            config_reloader,
+            event_repository,
+            image_store_repository,
+        }
+    }
+
+    /// Serve requests on the socket. The server runs on its own thread for
+    /// the lifetime of the process, one request at a time.
+    pub fn start(self, watcher_control: WatcherControl, health: HealthRegistry, shutdown: ShutdownSignal) -> RookLWResult<()> {
+        let socket_path = Path::new(&self.socket_path);
+        if let Some(parent) = socket_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // A socket left by an earlier run can't be bound again.
+        match std::fs::remove_file(socket_path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {},
+        }
+        let listener = UnixListener::bind(socket_path)
+            .map_err(|e| RookLWError::Initialization(format!("Failed to bind control socket {}: {}", self.socket_path, e)))?;
+        info!(socket_path = %self.socket_path, "Serving control socket");
+
+        let context = ControlContext { watcher_control, health, shutdown };
+        spawn(move || {
+            for stream in listener.incoming() {
+                let result = stream
+                    .map_err(RookLWError::from)
+                    .and_then(|stream| self.serve(stream, &context));
+                if let Err(e) = result {
+                    warn!(error = %e, "Failed to serve control request");
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn serve(&self, mut stream: UnixStream, context: &ControlContext) -> RookLWResult<()> {
+        stream.set_read_timeout(Some(CONNECTION_TIMEOUT))?;
+        stream.set_write_timeout(Some(CONNECTION_TIMEOUT))?;
+
+        let mut line = String::new();
+        BufReader::new(&stream).read_line(&mut line)?;
+
+        let response = match serde_json::from_str::<ControlRequest>(&line) {
+            Ok(request) => {
+                info!(request = ?request, "Control request");
+                self.handle(request, context).unwrap_or_else(|e| ControlResponse::Error { message: e.to_string() })
+            },
+            Err(e) => ControlResponse::Error { message: format!("Invalid request: {}", e) },
+        };
+
+        let mut line = serde_json::to_string(&response)?;
+        line.push('\n');
+        stream.write_all(line.as_bytes())?;
+        stream.flush()?;
+        Ok(())
+    }
+
+    fn handle(&self, request: ControlRequest, context: &ControlContext) -> RookLWResult<ControlResponse> {
+        let done = |message: &str| Ok(ControlResponse::Done { message: message.to_string() });
+
+        match request {
+            ControlRequest::Status => {
+                let last_event = self.event_repository
+                    .search_events(&EventSearchOptions { limit: Some(1), ..Default::default() })?
+                    .into_iter()
+                    .next();
+                Ok(ControlResponse::Status(DaemonStatus {
+                    paused: context.watcher_control.is_paused(),
+                    stages: context.health.snapshot(),
+                    config: serde_json::to_value(self.config_reloader.config())?,
+                    last_event,
+                }))
+            },
+            ControlRequest::Pause => {
+                context.watcher_control.set_paused(true);
+                done("Motion watching paused")
+            },
+            ControlRequest::Resume => {
+                context.watcher_control.set_paused(false);
+                done("Motion watching resumed")
+            },
+            ControlRequest::Trigger => {
+                context.watcher_control.trigger()?;
+                done("Capture triggered")
+            },
+            ControlRequest::Snapshot => {
+                check_snapshot_allowed(&self.config_reloader.config())?;
+                let image = context.watcher_control.snapshot(SNAPSHOT_TIMEOUT)?;
+                let jpeg_data = dynamic_image_to_jpeg(&image, Some(self.config_reloader.config().jpeg_quality))?;
+                let image_path = format!("snapshots/{}.jpg", chrono::Local::now().format("%Y%m%d_%H%M%S%.3f"));
+                self.image_store_repository.store(&image_path, &jpeg_data)?;
+                Ok(ControlResponse::Snapshot { image_path })
+            },
+            ControlRequest::ReloadConfig => {
+                let report = self.config_reloader.reload()?;
+                Ok(ControlResponse::Done { message: report.message() })
+            },
+            ControlRequest::Stop => {
+                context.shutdown.request();
+                done("Shutting down")
+            },
+        }
+    }
+}
+
+/// Snapshots are stored as captured, without detection, so they are refused
+/// when the pipeline redacts the images it stores.
+fn check_snapshot_allowed(config: &AppConfiguration) -> RookLWResult<()> {
+    let redacts = create_pipeline_stage_configurations(config)
+        .iter()
+        .any(|stage_config| stage_config.stage_type() == Some(StageType::PrivacyRedactor));
+    if redacts {
+        return Err(RookLWError::Other(
+            "Snapshots are not available while privacy redaction is on, they would be stored unredacted".to_string()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshots_are_refused_while_redacting() {
+        let mut config = AppConfiguration {
+            use_privacy_redaction: true,
+            ..Default::default()
+        };
+        assert!(check_snapshot_allowed(&config).is_err());
+
+        config.use_privacy_redaction = false;
+        assert!(check_snapshot_allowed(&config).is_ok());
+    }
+}
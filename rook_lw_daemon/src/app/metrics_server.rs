@@ -0,0 +1,69 @@
+use crate::{RookLWError, RookLWResult};
+use crate::prodcon::{HealthRegistry, PipelineMetrics};
+
+use tracing::{info, warn};
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread::spawn;
+use std::time::Duration;
+
+// A scrape that takes longer than this is abandoned.
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Serve the pipeline metrics in Prometheus text format at `/metrics`, and
+/// the stage health as JSON at `/health`.
+///
+/// The server runs on its own thread for the lifetime of the process.
+pub fn start_metrics_server(address: &str, metrics: PipelineMetrics, health: HealthRegistry) -> RookLWResult<()> {
+    let listener = TcpListener::bind(address)
+        .map_err(|e| RookLWError::Initialization(format!("Failed to bind metrics server to {}: {}", address, e)))?;
+    info!(address = %address, "Serving metrics");
+
+    spawn(move || {
+        for stream in listener.incoming() {
+            let result = stream.and_then(|stream| serve(stream, &metrics, &health));
+            if let Err(e) = result {
+                warn!(error = %e, "Failed to serve metrics request");
+            }
+        }
+    });
+
+    Ok(())
+}
+
+fn serve(mut stream: TcpStream, metrics: &PipelineMetrics, health: &HealthRegistry) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(CONNECTION_TIMEOUT))?;
+    stream.set_write_timeout(Some(CONNECTION_TIMEOUT))?;
+
+    let mut request_line = String::new();
+    let mut reader = BufReader::new(&stream);
+    reader.read_line(&mut request_line)?;
+    // Read the headers, the request has no body.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (status, content_type, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => ("200 OK", "text/plain; version=0.0.4", metrics.render()),
+        (Some("GET"), Some("/health")) => (
+            "200 OK",
+            "application/json",
+            serde_json::to_string(&health.snapshot()).unwrap_or_default(),
+        ),
+        (Some("GET"), _) => ("404 Not Found", "text/plain", "Not found\n".to_string()),
+        _ => ("405 Method Not Allowed", "text/plain", "Method not allowed\n".to_string()),
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
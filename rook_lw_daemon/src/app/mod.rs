@@ -3,8 +3,22 @@ mod app;
 mod logging;
 mod app_factory;
 mod app_configuration;
+mod metrics_server;
+mod control_server;
+mod config_reloader;
+mod config_check;
+mod pipeline;
+mod stage_registry;
+mod signals;
 
 pub use app::*;
 pub use logging::*;
 pub use app_factory::*;
-pub use app_configuration::*;
\ No newline at end of file
+pub use app_configuration::*;
+pub use metrics_server::*;
+pub use control_server::*;
+pub use config_reloader::*;
+pub use config_check::*;
+pub use pipeline::*;
+pub use stage_registry::*;
+pub use signals::*;
\ No newline at end of file
@@ -0,0 +1,458 @@
+use crate::{RookLWError, RookLWResult};
+use crate::events::{ImageProcessingEvent, StorageEvent};
+use crate::prodcon::{
+    ConsumerTask, ConvertingTask, DurableQueue, ErrorPolicy, OverflowSender, OverflowSettings, PipelineMetrics,
+    ProducerConsumerTask, QueueWaitConsumer, Sheddable, Supervisor, Timed,
+};
+use crate::tasks::motion_watcher::{MotionWatcher, WatcherControl};
+
+use crossbeam_channel::{Receiver, Sender};
+use tracing::info;
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Arc;
+use std::thread::{spawn, JoinHandle};
+
+/// Task of a pipeline stage. The variant decides which edges the stage can
+/// have and the items they carry: captured images as `ImageProcessingEvent`s,
+/// or stored images as `StorageEvent`s.
+pub enum PipelineStage {
+    /// Produces images and has no input, like the motion watcher.
+    Source(Box<dyn MotionWatcher>),
+
+    /// Consumes images and produces images for its outputs.
+    Processor(Box<dyn ProducerConsumerTask<ImageProcessingEvent>>),
+
+    /// Consumes images and has no outputs.
+    Sink(Box<dyn ConsumerTask<ImageProcessingEvent>>),
+
+    /// Consumes images and produces a `StorageEvent` for each image it stored,
+    /// like the storer. It may have no outputs.
+    Storer(Box<dyn ConvertingTask<ImageProcessingEvent, StorageEvent>>),
+
+    /// Consumes `StorageEvent`s and has no outputs, like a notifier.
+    Notifier(Box<dyn ConsumerTask<StorageEvent>>),
+}
+
+/// Items carried by an edge of the graph.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EdgeType {
+    Image,
+    Storage,
+}
+
+impl std::fmt::Display for EdgeType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EdgeType::Image => write!(f, "captured images"),
+            EdgeType::Storage => write!(f, "stored images"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StageRole {
+    Source,
+    Processor,
+    Sink,
+    Storer,
+    Notifier,
+}
+
+impl StageRole {
+    pub fn input(&self) -> Option<EdgeType> {
+        match self {
+            StageRole::Source => None,
+            StageRole::Processor | StageRole::Sink | StageRole::Storer => Some(EdgeType::Image),
+            StageRole::Notifier => Some(EdgeType::Storage),
+        }
+    }
+
+    pub fn output(&self) -> Option<EdgeType> {
+        match self {
+            StageRole::Source | StageRole::Processor => Some(EdgeType::Image),
+            StageRole::Storer => Some(EdgeType::Storage),
+            StageRole::Sink | StageRole::Notifier => None,
+        }
+    }
+
+    /// Whether stages of the role need outputs; a storer may end the graph.
+    fn needs_output(&self) -> bool {
+        matches!(self, StageRole::Source | StageRole::Processor)
+    }
+
+    /// Whether images leave the pipeline at stages of the role, stored or sent elsewhere.
+    pub fn is_endpoint(&self) -> bool {
+        self.input().is_some() && self.output() != Some(EdgeType::Image)
+    }
+}
+
+impl PipelineStage {
+    pub fn role(&self) -> StageRole {
+        match self {
+            PipelineStage::Source(_) => StageRole::Source,
+            PipelineStage::Processor(_) => StageRole::Processor,
+            PipelineStage::Sink(_) => StageRole::Sink,
+            PipelineStage::Storer(_) => StageRole::Storer,
+            PipelineStage::Notifier(_) => StageRole::Notifier,
+        }
+    }
+}
+
+pub struct PipelineNode {
+    pub name: String,
+    pub stage: PipelineStage,
+
+    /// Stages that receive the items this stage produces. With more than one,
+    /// each gets a copy of every item.
+    pub outputs: Vec<String>,
+
+    /// Capacity of the queue in front of this stage.
+    pub capacity: usize,
+
+    /// What producers do when the queue in front of this stage is full.
+    pub overflow: OverflowSettings,
+
+    pub error_policy: ErrorPolicy,
+
+    /// Keeps the queue in front of this stage on disk. The stage must remove
+    /// the items it has processed, by being wrapped in a `DurableConsumer`.
+    /// Only queues of captured images can be durable.
+    pub durable: Option<DurableQueue<ImageProcessingEvent>>,
+}
+
+/// Graph of named stages connected by bounded queues.
+///
+/// Several stages may output to the same stage; they then share its queue.
+/// The graph must be acyclic, so the stages can shut down in order once the
+/// sources stop.
+#[derive(Default)]
+pub struct Pipeline {
+    nodes: Vec<PipelineNode>,
+}
+
+impl Pipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_stage(&mut self, node: PipelineNode) -> &mut Self {
+        self.nodes.push(node);
+        self
+    }
+
+    pub fn validate(&self) -> RookLWResult<()> {
+        let graph: Vec<(&str, StageRole, &[String])> = self.nodes
+            .iter()
+            .map(|node| (node.name.as_str(), node.stage.role(), node.outputs.as_slice()))
+            .collect();
+        validate_graph(&graph)
+    }
+
+    /// Connect the stages and start each on its own supervised thread. The
+    /// sources take pause requests and commands from `watcher_control`.
+    pub fn start(
+        self,
+        supervisor: &Supervisor,
+        metrics: &PipelineMetrics,
+        watcher_control: &WatcherControl,
+    ) -> RookLWResult<Vec<JoinHandle<RookLWResult<()>>>> {
+        self.validate()?;
+
+        let mut image_queues: Queues<ImageProcessingEvent> = Queues::default();
+        let mut storage_queues: Queues<StorageEvent> = Queues::default();
+        let mut recoveries = Vec::new();
+        for node in &self.nodes {
+            match node.stage.role().input() {
+                Some(EdgeType::Image) => {
+                    let (sender, overflow_sender) = image_queues.create(node, metrics);
+                    if let Some(durable) = &node.durable {
+                        // Before anything is sent, so only items of earlier runs are recovered.
+                        if let Some(last_id) = durable.prepare_recovery()? {
+                            recoveries.push((durable.clone(), sender, last_id));
+                        }
+                        overflow_sender.set_journal(Arc::new(durable.clone()));
+                    }
+                },
+                Some(EdgeType::Storage) => {
+                    storage_queues.create(node, metrics);
+                },
+                None => {},
+            }
+        }
+
+        let mut handles = Vec::new();
+        for node in self.nodes {
+            let PipelineNode { name, mut stage, outputs, error_policy, .. } = node;
+            info!(stage = %name, role = ?stage.role(), outputs = ?outputs, "Starting pipeline stage");
+
+            // Validation made sure every output has a queue of the items the stage produces.
+            for output in &outputs {
+                match &mut stage {
+                    PipelineStage::Source(watcher) => watcher.connect_overflow(image_queues.senders[output].clone()),
+                    PipelineStage::Processor(task) => task.connect_overflow(image_queues.senders[output].clone()),
+                    PipelineStage::Storer(task) => task.connect_overflow(storage_queues.senders[output].clone()),
+                    PipelineStage::Sink(_) | PipelineStage::Notifier(_) => {},
+                }
+            }
+
+            let handle = match stage {
+                PipelineStage::Source(mut watcher) => {
+                    watcher.set_metrics(metrics.stage(&name));
+                    watcher.set_control(watcher_control.clone());
+                    supervisor.spawn_producer(&name, move |shutdown| watcher.run(shutdown), error_policy)
+                },
+                PipelineStage::Processor(mut task) => {
+                    task.set_metrics(metrics.stage(&name));
+                    let task = QueueWaitConsumer::new(task, metrics.stage(&name));
+                    supervisor.spawn_consumer(&name, task, image_queues.take_receiver(&name), error_policy)
+                },
+                PipelineStage::Sink(task) => {
+                    let task = QueueWaitConsumer::new(task, metrics.stage(&name));
+                    supervisor.spawn_consumer(&name, task, image_queues.take_receiver(&name), error_policy)
+                },
+                PipelineStage::Storer(mut task) => {
+                    task.set_metrics(metrics.stage(&name));
+                    let task = QueueWaitConsumer::new(task, metrics.stage(&name));
+                    supervisor.spawn_consumer(&name, task, image_queues.take_receiver(&name), error_policy)
+                },
+                PipelineStage::Notifier(task) => {
+                    let task = QueueWaitConsumer::new(task, metrics.stage(&name));
+                    supervisor.spawn_consumer(&name, task, storage_queues.take_receiver(&name), error_policy)
+                },
+            };
+            handles.push(handle);
+        }
+
+        // Only the producing stages may hold senders, or the queues would never close.
+        drop(image_queues);
+        drop(storage_queues);
+
+        // Recovered items are sent alongside new ones; each recovery ends once
+        // its items are queued.
+        for (durable, sender, last_id) in recoveries {
+            handles.push(spawn(move || durable.recover(&sender, last_id)));
+        }
+
+        Ok(handles)
+    }
+}
+
+/// The queues in front of the stages that take one type of item.
+struct Queues<T> {
+    senders: BTreeMap<String, OverflowSender<T>>,
+    receivers: BTreeMap<String, Receiver<T>>,
+}
+
+impl<T> Default for Queues<T> {
+    fn default() -> Self {
+        Self {
+            senders: BTreeMap::new(),
+            receivers: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T: Sheddable + Timed + Send + 'static> Queues<T> {
+    /// Create the queue in front of `node`, returning its plain sender along
+    /// with the one its producers use.
+    fn create(&mut self, node: &PipelineNode, metrics: &PipelineMetrics) -> (Sender<T>, &mut OverflowSender<T>) {
+        let (sender, receiver) = crossbeam_channel::bounded::<T>(node.capacity);
+        metrics.register_queue(&node.name, &receiver);
+        let overflow_sender = OverflowSender::new(
+            &node.name,
+            sender.clone(),
+            receiver.clone(),
+            node.overflow.clone(),
+            metrics.stage(&node.name),
+        );
+        self.receivers.insert(node.name.clone(), receiver);
+        (sender, self.senders.entry(node.name.clone()).or_insert(overflow_sender))
+    }
+
+    fn take_receiver(&mut self, name: &str) -> Receiver<T> {
+        self.receivers.remove(name).expect("queue created for every consuming stage")
+    }
+}
+
+/// A problem with the graph of a pipeline, at the stage it was found at.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphProblem {
+    /// None for problems of the whole graph.
+    pub stage: Option<String>,
+    pub message: String,
+}
+
+fn validate_graph(nodes: &[(&str, StageRole, &[String])]) -> RookLWResult<()> {
+    match graph_problems(nodes).into_iter().next() {
+        Some(problem) => Err(RookLWError::Initialization(problem.message)),
+        None => Ok(()),
+    }
+}
+
+/// Every problem with a graph of named stages with their role and outputs:
+/// missing or duplicate names, no source, edges that don't fit the roles of
+/// their stages or carry items their output does not take, stages without
+/// input, and cycles.
+pub fn graph_problems(nodes: &[(&str, StageRole, &[String])]) -> Vec<GraphProblem> {
+    let mut problems = Vec::new();
+    let mut problem = |stage: Option<&str>, message: String| problems.push(GraphProblem {
+        stage: stage.map(|stage| stage.to_string()),
+        message,
+    });
+
+    let mut roles: BTreeMap<&str, StageRole> = BTreeMap::new();
+    for (name, role, _) in nodes {
+        if name.is_empty() {
+            problem(None, "Pipeline stage without a name".to_string());
+        }
+        else if roles.insert(name, *role).is_some() {
+            problem(Some(name), format!("Duplicate pipeline stage: {}", name));
+        }
+    }
+
+    if !nodes.iter().any(|(_, role, _)| *role == StageRole::Source) {
+        problem(None, "Pipeline has no source stage".to_string());
+    }
+
+    let mut input_counts: BTreeMap<&str, usize> = nodes.iter().map(|(name, _, _)| (*name, 0)).collect();
+    for (name, role, outputs) in nodes {
+        if role.needs_output() && outputs.is_empty() {
+            problem(Some(name), format!("Pipeline stage {} has no outputs", name));
+        }
+        if role.output().is_none() && !outputs.is_empty() {
+            problem(Some(name), format!("Pipeline stage {} produces nothing but has outputs", name));
+        }
+        for output in outputs.iter() {
+            match roles.get(output.as_str()).map(|output_role| output_role.input()) {
+                None => problem(Some(name), format!("Pipeline stage {} outputs to unknown stage {}", name, output)),
+                Some(None) => {
+                    problem(Some(name), format!("Pipeline stage {} outputs to {}, which takes no input", name, output));
+                },
+                Some(Some(input)) => {
+                    if let Some(produced) = role.output() && produced != input {
+                        problem(Some(name), format!(
+                            "Pipeline stage {} outputs {} to {}, which takes {}",
+                            name, produced, output, input
+                        ));
+                    }
+                },
+            }
+            *input_counts.entry(output.as_str()).or_default() += 1;
+        }
+    }
+
+    for (name, role, _) in nodes {
+        if role.input().is_some() && input_counts[name] == 0 {
+            problem(Some(name), format!("Pipeline stage {} receives no input", name));
+        }
+    }
+
+    // Kahn's algorithm; stages left over are on a cycle.
+    let mut remaining = input_counts.clone();
+    let mut ready: VecDeque<&str> = remaining
+        .iter()
+        .filter(|(_, count)| **count == 0)
+        .map(|(name, _)| *name)
+        .collect();
+    while let Some(name) = ready.pop_front() {
+        remaining.remove(name);
+        let outputs = nodes.iter().find(|(n, _, _)| *n == name).map(|(_, _, outputs)| *outputs).unwrap_or_default();
+        for output in outputs {
+            if let Some(count) = remaining.get_mut(output.as_str()) {
+                *count -= 1;
+                if *count == 0 {
+                    ready.push_back(output.as_str());
+                }
+            }
+        }
+    }
+    if let Some(name) = remaining.keys().next() {
+        problem(Some(name), format!("Pipeline has a cycle through stage {}", name));
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outputs(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    fn check(nodes: &[(&str, StageRole, Vec<String>)]) -> Result<(), String> {
+        let graph: Vec<(&str, StageRole, &[String])> = nodes
+            .iter()
+            .map(|(name, role, outputs)| (*name, *role, outputs.as_slice()))
+            .collect();
+        validate_graph(&graph).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn validate_graph_accepts_fan_out_and_fan_in_but_not_cycles() {
+        let fan_out = vec![
+            ("motion_watcher", StageRole::Source, outputs(&["image_detector"])),
+            ("image_detector", StageRole::Processor, outputs(&["image_storer", "notifier"])),
+            ("notifier", StageRole::Sink, outputs(&[])),
+            ("image_storer", StageRole::Sink, outputs(&[])),
+        ];
+        assert_eq!(check(&fan_out), Ok(()));
+
+        let fan_in = vec![
+            ("camera", StageRole::Source, outputs(&["image_storer"])),
+            ("radar", StageRole::Source, outputs(&["image_storer"])),
+            ("image_storer", StageRole::Sink, outputs(&[])),
+        ];
+        assert_eq!(check(&fan_in), Ok(()));
+
+        let cycle = vec![
+            ("motion_watcher", StageRole::Source, outputs(&["a"])),
+            ("a", StageRole::Processor, outputs(&["b"])),
+            ("b", StageRole::Processor, outputs(&["a"])),
+        ];
+        assert!(check(&cycle).unwrap_err().contains("cycle"));
+
+        let unknown = vec![("motion_watcher", StageRole::Source, outputs(&["image_storer"]))];
+        assert!(check(&unknown).unwrap_err().contains("unknown stage image_storer"));
+
+        let into_source = vec![
+            ("motion_watcher", StageRole::Source, outputs(&["image_storer"])),
+            ("image_storer", StageRole::Processor, outputs(&["motion_watcher"])),
+        ];
+        assert!(check(&into_source).unwrap_err().contains("takes no input"));
+
+        let orphan = vec![
+            ("motion_watcher", StageRole::Source, outputs(&["image_storer"])),
+            ("image_storer", StageRole::Sink, outputs(&[])),
+            ("image_detector", StageRole::Processor, outputs(&["image_storer"])),
+        ];
+        assert!(check(&orphan).unwrap_err().contains("image_detector receives no input"));
+    }
+
+    #[test]
+    fn validate_graph_checks_the_items_each_edge_carries() {
+        let notified = vec![
+            ("motion_watcher", StageRole::Source, outputs(&["image_storer"])),
+            ("image_storer", StageRole::Storer, outputs(&["log_notifier"])),
+            ("log_notifier", StageRole::Notifier, outputs(&[])),
+        ];
+        assert_eq!(check(&notified), Ok(()));
+
+        let not_notified = vec![
+            ("motion_watcher", StageRole::Source, outputs(&["image_storer"])),
+            ("image_storer", StageRole::Storer, outputs(&[])),
+        ];
+        assert_eq!(check(&not_notified), Ok(()));
+
+        let notified_of_captures = vec![
+            ("motion_watcher", StageRole::Source, outputs(&["image_storer", "log_notifier"])),
+            ("image_storer", StageRole::Storer, outputs(&[])),
+            ("log_notifier", StageRole::Notifier, outputs(&[])),
+        ];
+        assert!(check(&notified_of_captures)
+            .unwrap_err()
+            .contains("motion_watcher outputs captured images to log_notifier, which takes stored images"));
+    }
+}
@@ -0,0 +1,47 @@
+use crate::{RookLWError, RookLWResult};
+use crate::app::ConfigReloader;
+use crate::prodcon::ShutdownSignal;
+
+use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM};
+use signal_hook::iterator::Signals;
+use tracing::{error, info, warn};
+
+use std::thread::spawn;
+
+/// Request shutdown on SIGTERM or SIGINT. A second signal exits immediately.
+pub fn install_signal_handlers(shutdown: ShutdownSignal) -> RookLWResult<()> {
+    let mut signals = Signals::new([SIGTERM, SIGINT])
+        .map_err(|e| RookLWError::Initialization(format!("Failed to install signal handlers: {}", e)))?;
+
+    spawn(move || {
+        for signal in signals.forever() {
+            if shutdown.is_requested() {
+                warn!(signal, "Signal received during shutdown, exiting");
+                std::process::exit(1);
+            }
+            info!(signal, "Signal received, shutting down");
+            shutdown.request();
+        }
+    });
+
+    Ok(())
+}
+
+
+/// Reload the configuration on SIGHUP.
+pub fn install_reload_handler(config_reloader: ConfigReloader) -> RookLWResult<()> {
+    let mut signals = Signals::new([SIGHUP])
+        .map_err(|e| RookLWError::Initialization(format!("Failed to install reload signal handler: {}", e)))?;
+
+    spawn(move || {
+        for signal in signals.forever() {
+            info!(signal, "Signal received, reloading configuration");
+            match config_reloader.reload() {
+                Ok(report) => info!(result = %report.message(), "Configuration reload finished"),
+                Err(e) => error!(error = %e, "Configuration reload failed, keeping the running configuration"),
+            }
+        }
+    });
+
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use crate::{RookLWError, RookLWResult};
+use crate::app::{AppConfiguration, PipelineStage, StageRole, StageType};
+
+use std::collections::BTreeMap;
+
+pub type StageFactory = Box<dyn Fn(&AppConfiguration) -> RookLWResult<PipelineStage>>;
+
+/// Creates pipeline stages by type, so the configured pipeline can use a new
+/// stage type once its factory is registered.
+#[derive(Default)]
+pub struct StageRegistry {
+    factories: BTreeMap<StageType, (StageRole, StageFactory)>,
+}
+
+impl StageRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the factory of a stage type, whose stages all have `role`.
+    pub fn register<F>(&mut self, stage_type: StageType, role: StageRole, factory: F) -> &mut Self
+        where F: Fn(&AppConfiguration) -> RookLWResult<PipelineStage> + 'static
+    {
+        self.factories.insert(stage_type, (role, Box::new(factory)));
+        self
+    }
+
+    /// Role of the stages of a type, known without creating one.
+    pub fn role(&self, stage_type: StageType) -> Option<StageRole> {
+        self.factories.get(&stage_type).map(|(role, _)| *role)
+    }
+
+    pub fn stage_types(&self) -> Vec<StageType> {
+        self.factories.keys().copied().collect()
+    }
+
+    pub fn create(&self, stage_type: StageType, app_config: &AppConfiguration) -> RookLWResult<PipelineStage> {
+        let (_, factory) = self.factories.get(&stage_type).ok_or_else(|| RookLWError::Initialization(format!(
+            "Pipeline stage type {} is not registered (registered: {})",
+            stage_type,
+            self.stage_types().iter().map(|stage_type| stage_type.to_string()).collect::<Vec<_>>().join(", ")
+        )))?;
+        factory(app_config)
+    }
+}
@@ -0,0 +1,39 @@
+use rook_lw_daemon::app;
+use rook_lw_daemon::{RookLWResult, RookLWError};
+
+use clap::Parser;
+
+/// Group stored images into clusters by embedding similarity.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    /// Daemon configuration file; the database and clustering settings come from it
+    #[arg(long, default_value = "config/rook_lw_daemon.toml")]
+    config: String,
+
+    /// Report the clusters that would be found without writing anything
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Write the report to this JSON file
+    #[arg(long)]
+    report: Option<String>,
+}
+
+fn main() -> RookLWResult<()> {
+    app::init_tracing();
+    let cli = Cli::parse();
+
+    let app_config = app::AppConfiguration::load(&cli.config)?;
+    let clusterer = app::create_embedding_clusterer(&app_config)?;
+
+    let report = clusterer.run(cli.dry_run)?;
+
+    if let Some(report_path) = cli.report {
+        let report_json = serde_json::to_string_pretty(&report)?;
+        std::fs::write(&report_path, report_json)
+            .map_err(|e| RookLWError::Other(format!("Failed to write report {report_path}: {e}")))?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,105 @@
+use rook_lw_daemon::app;
+use rook_lw_daemon::RookLWResult;
+use rook_lw_daemon::tasks::dataset_exporter::{DatasetFormat, ExportOptions, LabelSource};
+
+use rook_lw_models::image::ImageInfoSearchOptions;
+
+use chrono::{DateTime, FixedOffset};
+use clap::{Parser, ValueEnum};
+
+use std::path::PathBuf;
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Format {
+    Yolo,
+    Coco,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Labels {
+    /// Only images with human-verified detections
+    Verified,
+    /// Model detections of every image
+    Model,
+}
+
+/// Export stored images and their boxes as a training dataset.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    /// Daemon configuration file; the database and image directory come from it
+    #[arg(long, default_value = "config/rook_lw_daemon.toml")]
+    config: String,
+
+    /// Directory the dataset is written to
+    #[arg(long)]
+    output: PathBuf,
+
+    #[arg(long, value_enum, default_value_t = Format::Yolo)]
+    format: Format,
+
+    #[arg(long, value_enum, default_value_t = Labels::Verified)]
+    labels: Labels,
+
+    /// Fraction of events put in the validation split
+    #[arg(long, default_value_t = 0.2)]
+    val_fraction: f32,
+
+    /// Only images captured at or after this time (RFC 3339)
+    #[arg(long, value_parser = parse_date)]
+    start_date: Option<DateTime<FixedOffset>>,
+
+    /// Only images captured at or before this time (RFC 3339)
+    #[arg(long, value_parser = parse_date)]
+    end_date: Option<DateTime<FixedOffset>>,
+
+    /// Only images with a detection of this class; can be repeated
+    #[arg(long = "class")]
+    classes: Vec<String>,
+
+    /// Only images with a detection of at least this confidence
+    #[arg(long)]
+    min_confidence: Option<f32>,
+
+    /// Only images whose results came from this model ("unknown" for results without provenance)
+    #[arg(long)]
+    model_name: Option<String>,
+}
+
+fn parse_date(value: &str) -> Result<DateTime<FixedOffset>, String> {
+    DateTime::parse_from_rfc3339(value).map_err(|e| format!("Invalid date {value}: {e}"))
+}
+
+fn main() -> RookLWResult<()> {
+    app::init_tracing();
+    let cli = Cli::parse();
+
+    let app_config = app::AppConfiguration::load(&cli.config)?;
+    let exporter = app::create_dataset_exporter(&app_config)?;
+
+    let options = ExportOptions {
+        output_dir: cli.output,
+        format: match cli.format {
+            Format::Yolo => DatasetFormat::Yolo,
+            Format::Coco => DatasetFormat::Coco,
+        },
+        label_source: match cli.labels {
+            Labels::Verified => LabelSource::Verified,
+            Labels::Model => LabelSource::Model,
+        },
+        val_fraction: cli.val_fraction,
+        search_options: ImageInfoSearchOptions {
+            start_date: cli.start_date,
+            end_date: cli.end_date,
+            detection_classes: cli.classes,
+            detection_class_confidence: cli.min_confidence,
+            model_name: cli.model_name,
+            ..Default::default()
+        },
+    };
+    let report = exporter.run(&options)?;
+
+    println!("{}", serde_json::to_string_pretty(&report)?);
+
+    Ok(())
+}
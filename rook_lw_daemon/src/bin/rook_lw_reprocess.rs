@@ -0,0 +1,77 @@
+use rook_lw_daemon::app;
+use rook_lw_daemon::{RookLWResult, RookLWError};
+use rook_lw_daemon::tasks::image_reprocessor::ReprocessOptions;
+
+use chrono::{DateTime, FixedOffset};
+use clap::Parser;
+
+/// Run the configured object detector again over stored images.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    /// Daemon configuration file; the detector, database and image directory come from it
+    #[arg(long, default_value = "config/rook_lw_daemon.toml")]
+    config: String,
+
+    /// Only images captured at or after this time (RFC 3339)
+    #[arg(long, value_parser = parse_date)]
+    start_date: Option<DateTime<FixedOffset>>,
+
+    /// Only images captured at or before this time (RFC 3339)
+    #[arg(long, value_parser = parse_date)]
+    end_date: Option<DateTime<FixedOffset>>,
+
+    /// Only images whose results came from this model ("unknown" for results without provenance)
+    #[arg(long)]
+    from_model: Option<String>,
+
+    /// Version recorded with the new results
+    #[arg(long)]
+    model_version: Option<String>,
+
+    /// Report what would change without writing anything
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Also reprocess images that already have results from this model
+    #[arg(long)]
+    force: bool,
+
+    /// Number of records read at a time
+    #[arg(long, default_value_t = 100)]
+    batch_size: u32,
+
+    /// Write the report, including per-image differences, to this JSON file
+    #[arg(long)]
+    report: Option<String>,
+}
+
+fn parse_date(value: &str) -> Result<DateTime<FixedOffset>, String> {
+    DateTime::parse_from_rfc3339(value).map_err(|e| format!("Invalid date {value}: {e}"))
+}
+
+fn main() -> RookLWResult<()> {
+    app::init_tracing();
+    let cli = Cli::parse();
+
+    let app_config = app::AppConfiguration::load(&cli.config)?;
+    let mut reprocessor = app::create_image_reprocessor(&app_config, cli.model_version)?;
+
+    let options = ReprocessOptions {
+        start_date: cli.start_date,
+        end_date: cli.end_date,
+        from_model: cli.from_model,
+        dry_run: cli.dry_run,
+        force: cli.force,
+        batch_size: cli.batch_size,
+    };
+    let report = reprocessor.run(&options)?;
+
+    if let Some(report_path) = cli.report {
+        let report_json = serde_json::to_string_pretty(&report)?;
+        std::fs::write(&report_path, report_json)
+            .map_err(|e| RookLWError::Other(format!("Failed to write report {report_path}: {e}")))?;
+    }
+
+    Ok(())
+}
@@ -4,14 +4,23 @@ use chrono::{DateTime, FixedOffset};
 use image::DynamicImage;
 use uuid::Uuid;
 
+use rook_lw_models::event::EventTrigger;
 use rook_lw_models::image::MotionDetectionScore;
 
 #[derive(Clone, Debug)]
 pub struct CaptureEvent {
     pub event_id: Uuid,
     pub event_timestamp: DateTime<FixedOffset>,
+    pub trigger: EventTrigger,
     pub motion_score: MotionDetectionScore,
     pub capture_index: u32,
     pub capture_timestamp: DateTime<FixedOffset>,
     pub image: Arc<DynamicImage>,
 }
+
+impl CaptureEvent {
+    /// Id the image of this capture is stored under.
+    pub fn image_id(&self) -> String {
+        format!("{}_{}", self.event_id, self.capture_index)
+    }
+}
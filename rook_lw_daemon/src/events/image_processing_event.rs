@@ -1,12 +1,154 @@
-
+use crate::{RookLWError, RookLWResult};
 use crate::events::capture_event::CaptureEvent;
 
-use rook_lw_models::image::DetectionResult;
+use crate::prodcon::{QueueItem, Sheddable, Timed};
+
+use chrono::DateTime;
+use image::{DynamicImage, GrayImage, RgbImage, RgbaImage};
+use rook_lw_models::event::EventTrigger;
+use rook_lw_models::image::{DetectionResult, MotionDetectionScore};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 #[derive(Clone, Debug)]
 pub struct ImageProcessingEvent {
     pub capture_event: CaptureEvent,
     pub detection_result: Option<DetectionResult>,
+
+    /// Image id of the earlier image this one is a near-duplicate of.
+    pub duplicate_of: Option<String>,
+
+    /// Set when regions of the image were obscured for privacy.
+    pub redacted: bool,
+
+    /// When the item was last put in a queue between pipeline stages.
+    pub queued_at: Option<Instant>,
+}
+
+impl Timed for ImageProcessingEvent {
+    fn queued_at(&self) -> Option<Instant> {
+        self.queued_at
+    }
+
+    fn set_queued_at(&mut self, queued_at: Instant) {
+        self.queued_at = Some(queued_at);
+    }
+
+    fn age(&self) -> Duration {
+        let now: DateTime<chrono::FixedOffset> = chrono::Local::now().into();
+        (now - self.capture_event.capture_timestamp).to_std().unwrap_or_default()
+    }
+}
+
+impl Sheddable for ImageProcessingEvent {
+    fn shed_score(&self) -> f32 {
+        self.capture_event.motion_score.score
+    }
+
+    fn sequence_index(&self) -> u32 {
+        self.capture_event.capture_index
+    }
+}
+
+/// Everything but the pixels, written ahead of them in a queue item.
+#[derive(Serialize, Deserialize)]
+struct QueuedEventMetadata {
+    event_id: String,
+    event_timestamp: String,
+    #[serde(default)]
+    trigger: EventTrigger,
+    motion_score: MotionDetectionScore,
+    capture_index: u32,
+    capture_timestamp: String,
+    detection_result: Option<DetectionResult>,
+    duplicate_of: Option<String>,
+    redacted: bool,
+    width: u32,
+    height: u32,
+    channels: u8,
+}
+
+/// A queue item is the length of the metadata as 4 little endian bytes, the
+/// metadata as JSON, then the raw pixels. Images are kept uncompressed, so a
+/// recovered frame is exactly the captured one.
+impl QueueItem for ImageProcessingEvent {
+    fn queue_key(&self) -> String {
+        self.capture_event.image_id()
+    }
+
+    fn encode(&self) -> RookLWResult<Vec<u8>> {
+        let capture = &self.capture_event;
+        let (channels, pixels) = match capture.image.as_ref() {
+            DynamicImage::ImageLuma8(image) => (1, image.as_raw().as_slice()),
+            DynamicImage::ImageRgb8(image) => (3, image.as_raw().as_slice()),
+            DynamicImage::ImageRgba8(image) => (4, image.as_raw().as_slice()),
+            _ => {
+                let mut converted = self.clone();
+                converted.capture_event.image = Arc::new(DynamicImage::ImageRgb8(capture.image.to_rgb8()));
+                return converted.encode();
+            },
+        };
+        let metadata = serde_json::to_vec(&QueuedEventMetadata {
+            event_id: capture.event_id.to_string(),
+            event_timestamp: capture.event_timestamp.to_rfc3339(),
+            trigger: capture.trigger,
+            motion_score: capture.motion_score.clone(),
+            capture_index: capture.capture_index,
+            capture_timestamp: capture.capture_timestamp.to_rfc3339(),
+            detection_result: self.detection_result.clone(),
+            duplicate_of: self.duplicate_of.clone(),
+            redacted: self.redacted,
+            width: capture.image.width(),
+            height: capture.image.height(),
+            channels,
+        })?;
+
+        let mut data = Vec::with_capacity(4 + metadata.len() + pixels.len());
+        data.extend_from_slice(&(metadata.len() as u32).to_le_bytes());
+        data.extend_from_slice(&metadata);
+        data.extend_from_slice(pixels);
+        Ok(data)
+    }
+
+    fn decode(data: &[u8]) -> RookLWResult<Self> {
+        let invalid = |message: &str| RookLWError::Parse(format!("Invalid queued event: {}", message));
+
+        let (length, rest) = data.split_first_chunk::<4>().ok_or_else(|| invalid("too short"))?;
+        let length = u32::from_le_bytes(*length) as usize;
+        if rest.len() < length {
+            return Err(invalid("metadata truncated"));
+        }
+        let (metadata, pixels) = rest.split_at(length);
+        let metadata: QueuedEventMetadata = serde_json::from_slice(metadata)?;
+
+        let (width, height, pixels) = (metadata.width, metadata.height, pixels.to_vec());
+        let image = match metadata.channels {
+            1 => GrayImage::from_raw(width, height, pixels).map(DynamicImage::ImageLuma8),
+            3 => RgbImage::from_raw(width, height, pixels).map(DynamicImage::ImageRgb8),
+            4 => RgbaImage::from_raw(width, height, pixels).map(DynamicImage::ImageRgba8),
+            _ => None,
+        }.ok_or_else(|| invalid("image does not match its size"))?;
+
+        let timestamp = |value: &str| DateTime::parse_from_rfc3339(value).map_err(|e| invalid(&e.to_string()));
+        Ok(ImageProcessingEvent {
+            capture_event: CaptureEvent {
+                event_id: Uuid::parse_str(&metadata.event_id).map_err(|e| invalid(&e.to_string()))?,
+                event_timestamp: timestamp(&metadata.event_timestamp)?,
+                trigger: metadata.trigger,
+                motion_score: metadata.motion_score,
+                capture_index: metadata.capture_index,
+                capture_timestamp: timestamp(&metadata.capture_timestamp)?,
+                image: Arc::new(image),
+            },
+            detection_result: metadata.detection_result,
+            duplicate_of: metadata.duplicate_of,
+            redacted: metadata.redacted,
+            queued_at: None,
+        })
+    }
 }
 
-pub type OnImageProcessingEventCallback = Box<dyn Fn(&ImageProcessingEvent) + Send + 'static>;
\ No newline at end of file
+pub type OnImageProcessingEventCallback = Box<dyn Fn(&ImageProcessingEvent) + Send + 'static>;
@@ -1,6 +1,7 @@
 use uuid::Uuid;
 use chrono::{DateTime, FixedOffset};
 
+use rook_lw_models::event::EventTrigger;
 use rook_lw_models::image::MotionDetectionScore;
 use crate::events::capture_event::CaptureEvent;
 
@@ -8,6 +9,7 @@ use crate::events::capture_event::CaptureEvent;
 pub struct MotionDetectionEvent {
     pub event_id: Uuid,
     pub event_timestamp: DateTime<FixedOffset>,
+    pub trigger: EventTrigger,
     pub motion_score: MotionDetectionScore,
     pub capture_events: Vec<CaptureEvent>,
 }
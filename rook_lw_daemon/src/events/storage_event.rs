@@ -1,7 +1,40 @@
 use crate::events::capture_event::CaptureEvent;
+use crate::prodcon::{Sheddable, Timed};
+
+use chrono::DateTime;
+
+use std::time::{Duration, Instant};
 
 #[derive(Clone, Debug)]
 pub struct StorageEvent {
     pub capture_event: CaptureEvent,
     pub image_path: String,
+
+    /// When the item was last put in a queue between pipeline stages.
+    pub queued_at: Option<Instant>,
+}
+
+impl Timed for StorageEvent {
+    fn queued_at(&self) -> Option<Instant> {
+        self.queued_at
+    }
+
+    fn set_queued_at(&mut self, queued_at: Instant) {
+        self.queued_at = Some(queued_at);
+    }
+
+    fn age(&self) -> Duration {
+        let now: DateTime<chrono::FixedOffset> = chrono::Local::now().into();
+        (now - self.capture_event.capture_timestamp).to_std().unwrap_or_default()
+    }
+}
+
+impl Sheddable for StorageEvent {
+    fn shed_score(&self) -> f32 {
+        self.capture_event.motion_score.score
+    }
+
+    fn sequence_index(&self) -> u32 {
+        self.capture_event.capture_index
+    }
 }
@@ -0,0 +1,266 @@
+//! Clustering of image embeddings, to group recurring visitors.
+//!
+//! A leader pass assigns each embedding to the most similar existing cluster,
+//! or starts a new one when no cluster is similar enough. A few k-means style
+//! refinement passes then move the centroids to the mean of their members and
+//! reassign. Embeddings that end up in clusters smaller than the minimum size
+//! are left unassigned, as noise.
+//!
+//! Existing clusters seed the leader pass, so clusters keep their ids between
+//! runs, and new images can be assigned online with [`nearest_cluster`].
+
+use rook_lw_image_repo::image_info::cosine_similarity;
+use rook_lw_models::image::EmbeddingCluster;
+
+#[derive(Debug, Clone)]
+pub struct ClusteringSettings {
+    /// Minimum cosine similarity between an embedding and a centroid to join the cluster.
+    pub min_similarity: f32,
+
+    /// Clusters with fewer members are dropped and their members left unassigned.
+    pub min_cluster_size: u32,
+
+    /// Maximum number of refinement passes after the leader pass.
+    pub iterations: u32,
+}
+
+impl Default for ClusteringSettings {
+    fn default() -> Self {
+        Self {
+            min_similarity: 0.85,
+            min_cluster_size: 3,
+            iterations: 5,
+        }
+    }
+}
+
+/// A cluster found by [`cluster_embeddings`].
+#[derive(Debug, Clone)]
+pub struct Cluster {
+    pub cluster_id: u32,
+
+    /// Normalized mean of the members.
+    pub centroid: Vec<f32>,
+
+    /// Indexes of the member embeddings.
+    pub members: Vec<usize>,
+
+    /// Index of the member closest to the centroid.
+    pub representative: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ClusteringResult {
+    pub clusters: Vec<Cluster>,
+
+    /// Cluster id of each embedding, in input order.
+    pub assignments: Vec<Option<u32>>,
+}
+
+/// Id of the cluster whose centroid is most similar to `embedding`, if any is
+/// at least `min_similarity` similar.
+pub fn nearest_cluster(clusters: &[EmbeddingCluster], embedding: &[f32], min_similarity: f32) -> Option<u32> {
+    nearest(clusters.iter().map(|cluster| (cluster.cluster_id, cluster.centroid.as_slice())), embedding, min_similarity)
+}
+
+fn nearest<'a>(centroids: impl Iterator<Item = (u32, &'a [f32])>, embedding: &[f32], min_similarity: f32) -> Option<u32> {
+    centroids
+        .filter(|(_, centroid)| centroid.len() == embedding.len())
+        .map(|(cluster_id, centroid)| (cluster_id, cosine_similarity(centroid, embedding)))
+        .filter(|(_, similarity)| *similarity >= min_similarity)
+        .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|(cluster_id, _)| cluster_id)
+}
+
+fn normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|v| *v /= norm);
+    }
+}
+
+struct Centroid {
+    cluster_id: u32,
+    centroid: Vec<f32>,
+    sum: Vec<f32>,
+    count: u32,
+}
+
+impl Centroid {
+    fn add(&mut self, embedding: &[f32]) {
+        self.sum.iter_mut().zip(embedding).for_each(|(s, e)| *s += e);
+        self.count += 1;
+        self.centroid = self.sum.clone();
+        normalize(&mut self.centroid);
+    }
+}
+
+/// Cluster `embeddings`, starting from the `seeds` centroids of an earlier run.
+///
+/// Embeddings with a size different from the first one are left unassigned.
+pub fn cluster_embeddings(
+    seeds: &[(u32, Vec<f32>)],
+    embeddings: &[Vec<f32>],
+    settings: &ClusteringSettings,
+) -> ClusteringResult {
+    let Some(dimension) = embeddings.first().map(|embedding| embedding.len()) else {
+        return ClusteringResult::default();
+    };
+
+    let embeddings: Vec<Vec<f32>> = embeddings
+        .iter()
+        .map(|embedding| {
+            let mut embedding = embedding.clone();
+            normalize(&mut embedding);
+            embedding
+        })
+        .collect();
+
+    let mut centroids: Vec<Centroid> = seeds
+        .iter()
+        .filter(|(_, centroid)| centroid.len() == dimension)
+        .map(|(cluster_id, centroid)| Centroid {
+            cluster_id: *cluster_id,
+            centroid: centroid.clone(),
+            sum: centroid.clone(),
+            count: 0,
+        })
+        .collect();
+    let mut next_cluster_id = seeds.iter().map(|(cluster_id, _)| cluster_id + 1).max().unwrap_or(1);
+
+    // Leader pass.
+    let mut assignments: Vec<Option<u32>> = Vec::with_capacity(embeddings.len());
+    for embedding in &embeddings {
+        if embedding.len() != dimension {
+            assignments.push(None);
+            continue;
+        }
+        let cluster_id = nearest(
+            centroids.iter().map(|c| (c.cluster_id, c.centroid.as_slice())),
+            embedding,
+            settings.min_similarity,
+        );
+        match cluster_id.and_then(|id| centroids.iter_mut().find(|c| c.cluster_id == id)) {
+            Some(centroid) => {
+                centroid.add(embedding);
+                assignments.push(Some(centroid.cluster_id));
+            },
+            None => {
+                let mut centroid = Centroid {
+                    cluster_id: next_cluster_id,
+                    centroid: vec![0.0; dimension],
+                    sum: vec![0.0; dimension],
+                    count: 0,
+                };
+                centroid.add(embedding);
+                assignments.push(Some(next_cluster_id));
+                centroids.push(centroid);
+                next_cluster_id += 1;
+            },
+        }
+    }
+
+    // Refinement passes.
+    for _ in 0..settings.iterations {
+        for centroid in centroids.iter_mut() {
+            centroid.sum.iter_mut().for_each(|s| *s = 0.0);
+            centroid.count = 0;
+        }
+        for (embedding, assignment) in embeddings.iter().zip(&assignments) {
+            if let Some(centroid) = assignment.and_then(|id| centroids.iter_mut().find(|c| c.cluster_id == id)) {
+                centroid.add(embedding);
+            }
+        }
+        centroids.retain(|centroid| centroid.count > 0);
+
+        let mut changed = false;
+        for (embedding, assignment) in embeddings.iter().zip(assignments.iter_mut()) {
+            if embedding.len() != dimension {
+                continue;
+            }
+            let cluster_id = nearest(
+                centroids.iter().map(|c| (c.cluster_id, c.centroid.as_slice())),
+                embedding,
+                settings.min_similarity,
+            );
+            if cluster_id != *assignment {
+                *assignment = cluster_id;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    let mut clusters: Vec<Cluster> = centroids
+        .into_iter()
+        .map(|centroid| Cluster {
+            cluster_id: centroid.cluster_id,
+            centroid: centroid.centroid,
+            members: Vec::new(),
+            representative: 0,
+        })
+        .collect();
+    for (index, assignment) in assignments.iter().enumerate() {
+        if let Some(cluster) = assignment.and_then(|id| clusters.iter_mut().find(|c| c.cluster_id == id)) {
+            cluster.members.push(index);
+        }
+    }
+
+    // Small clusters are noise.
+    clusters.retain(|cluster| cluster.members.len() >= settings.min_cluster_size.max(1) as usize);
+    for assignment in assignments.iter_mut() {
+        if assignment.is_some_and(|id| !clusters.iter().any(|c| c.cluster_id == id)) {
+            *assignment = None;
+        }
+    }
+
+    for cluster in clusters.iter_mut() {
+        cluster.representative = cluster.members
+            .iter()
+            .copied()
+            .map(|index| (index, cosine_similarity(&cluster.centroid, &embeddings[index])))
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(index, _)| index)
+            .unwrap_or_default();
+    }
+
+    ClusteringResult { clusters, assignments }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cluster_embeddings_groups_and_keeps_seed_ids() {
+        let fox = [vec![1.0, 0.1, 0.0], vec![0.95, 0.05, 0.1], vec![1.0, 0.0, 0.05]];
+        let deer = [vec![0.0, 1.0, 0.1], vec![0.1, 0.9, 0.0], vec![0.0, 1.0, 0.0]];
+        let stray = vec![0.0, 0.0, 1.0];
+
+        let mut embeddings: Vec<Vec<f32>> = Vec::new();
+        embeddings.extend(fox.iter().cloned());
+        embeddings.extend(deer.iter().cloned());
+        embeddings.push(stray);
+
+        let seeds = vec![(7, vec![0.0, 1.0, 0.0])];
+        let result = cluster_embeddings(&seeds, &embeddings, &ClusteringSettings::default());
+
+        assert_eq!(result.clusters.len(), 2);
+        assert_eq!(&result.assignments[3..6], &[Some(7), Some(7), Some(7)]);
+        assert_eq!(&result.assignments[0..3], &[Some(8), Some(8), Some(8)]);
+        assert_eq!(result.assignments[6], None);
+
+        let clusters: Vec<EmbeddingCluster> = result.clusters
+            .iter()
+            .map(|cluster| EmbeddingCluster {
+                cluster_id: cluster.cluster_id,
+                centroid: cluster.centroid.clone(),
+                ..Default::default()
+            })
+            .collect();
+        assert_eq!(nearest_cluster(&clusters, &[0.9, 0.1, 0.0], 0.85), Some(8));
+        assert_eq!(nearest_cluster(&clusters, &[0.0, 0.0, 1.0], 0.85), None);
+    }
+}
@@ -0,0 +1,117 @@
+//! Hero frame score: how well a capture shows its event.
+//!
+//! Combines, each on a 0 to 1 scale:
+//! - sharpness: the Laplacian variance of the image
+//! - confidence: the highest detection confidence
+//! - size: the area of that detection's box, relative to the frame
+//! - centering: how close the centre of that box is to the centre of the frame
+//!
+//! Captures without detections score 0 on all but sharpness, so a frame with
+//! an animal in it is preferred over a sharper empty one.
+
+use crate::image::interestingness::combine_components;
+use crate::image::sharpness::sharpness_score;
+
+use rook_lw_models::image::{Detection, DetectionResult};
+
+use image::{DynamicImage, GenericImageView};
+
+#[derive(Debug, Clone)]
+pub struct HeroFrameWeights {
+    pub sharpness: f32,
+    pub confidence: f32,
+    pub size: f32,
+    pub centering: f32,
+}
+
+impl Default for HeroFrameWeights {
+    fn default() -> Self {
+        Self {
+            sharpness: 0.3,
+            confidence: 0.3,
+            size: 0.2,
+            centering: 0.2,
+        }
+    }
+}
+
+// Fraction of the frame a box must cover for the full size component.
+const FULL_SIZE_FRACTION: f32 = 0.25;
+
+/// Size and centering of a detection in a frame of the given size.
+fn box_components(detection: &Detection, width: u32, height: u32) -> (f32, f32) {
+    let (width, height) = (width.max(1) as f32, height.max(1) as f32);
+
+    let area_fraction = (detection.width.max(0) * detection.height.max(0)) as f32 / (width * height);
+    let size = (area_fraction / FULL_SIZE_FRACTION).min(1.0);
+
+    // Distance from the centre, relative to the distance from the centre to a corner.
+    let (center_x, center_y) = detection.center();
+    let dx = (center_x as f32 - width / 2.0) / (width / 2.0);
+    let dy = (center_y as f32 - height / 2.0) / (height / 2.0);
+    let centering = (1.0 - (dx * dx + dy * dy).sqrt() / 2f32.sqrt()).clamp(0.0, 1.0);
+
+    (size, centering)
+}
+
+pub fn hero_frame_score(image: &DynamicImage, detection_result: Option<&DetectionResult>, weights: &HeroFrameWeights) -> f32 {
+    let (width, height) = image.dimensions();
+
+    let best = detection_result
+        .iter()
+        .flat_map(|result| result.detections.iter())
+        .max_by(|a, b| a.confidence.total_cmp(&b.confidence));
+
+    let (confidence, size, centering) = match best {
+        Some(detection) => {
+            let (size, centering) = box_components(detection, width, height);
+            (detection.confidence, size, centering)
+        },
+        None => (0.0, 0.0, 0.0),
+    };
+
+    combine_components(&[
+        (weights.sharpness, Some(sharpness_score(image))),
+        (weights.confidence, Some(confidence)),
+        (weights.size, Some(size)),
+        (weights.centering, Some(centering)),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detection(x: i32, y: i32, size: i32, confidence: f32) -> Detection {
+        Detection {
+            class_name: "fox".to_string(),
+            confidence,
+            x,
+            y,
+            width: size,
+            height: size,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn hero_frame_score_prefers_large_centred_confident_detections() {
+        let image = DynamicImage::new_rgb8(200, 100);
+        let weights = HeroFrameWeights::default();
+        let score = |detections: Vec<Detection>| {
+            hero_frame_score(&image, Some(&DetectionResult::new(detections)), &weights)
+        };
+
+        let centred = score(vec![detection(80, 30, 40, 0.8)]);
+        let corner = score(vec![detection(0, 0, 40, 0.8)]);
+        let small = score(vec![detection(95, 45, 10, 0.8)]);
+        let unsure = score(vec![detection(80, 30, 40, 0.3)]);
+        let empty = score(vec![]);
+
+        assert!(centred > corner);
+        assert!(centred > small);
+        assert!(centred > unsure);
+        assert!(corner > empty);
+        assert_eq!(box_components(&detection(80, 30, 40, 0.8), 200, 100), (0.32, 1.0));
+    }
+}
@@ -0,0 +1,149 @@
+//! Interestingness score of a stored image.
+//!
+//! Combines, each on a 0 to 1 scale:
+//! - confidence: the highest detection confidence
+//! - rarity: how rarely the detected classes were seen over the last days
+//! - novelty: how far the image embedding is from the centroid of recent images
+//! - sharpness: the Laplacian variance of the image
+//!
+//! The score is the weighted mean of the components that could be computed,
+//! e.g. novelty is left out for images without an embedding.
+
+use crate::image::sharpness::sharpness_score;
+
+use rook_lw_image_repo::image_info::cosine_similarity;
+use rook_lw_models::image::DetectionResult;
+
+use image::DynamicImage;
+
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone)]
+pub struct InterestingnessWeights {
+    pub confidence: f32,
+    pub rarity: f32,
+    pub novelty: f32,
+    pub sharpness: f32,
+}
+
+impl Default for InterestingnessWeights {
+    fn default() -> Self {
+        Self {
+            confidence: 0.3,
+            rarity: 0.3,
+            novelty: 0.2,
+            sharpness: 0.2,
+        }
+    }
+}
+
+// Weight of a new embedding in the running centroid.
+const CENTROID_ALPHA: f32 = 0.05;
+
+/// Rarity of a class seen `count` times out of `total` detections, 1 for unseen classes.
+pub fn class_rarity(count: u32, total: u32) -> f32 {
+    if count == 0 || total == 0 {
+        return 1.0;
+    }
+    let rarity = 1.0 - (1.0 + count as f64).ln() / (1.0 + total as f64).ln();
+    rarity.clamp(0.0, 1.0) as f32
+}
+
+/// Weighted mean of the available `(weight, value)` components.
+pub fn combine_components(components: &[(f32, Option<f32>)]) -> f32 {
+    let (weighted_sum, weight_sum) = components
+        .iter()
+        .filter_map(|(weight, value)| value.map(|value| (*weight, value)))
+        .fold((0.0, 0.0), |(sum, weights), (weight, value)| (sum + weight * value, weights + weight));
+
+    if weight_sum > 0.0 {
+        weighted_sum / weight_sum
+    } else {
+        0.0
+    }
+}
+
+pub struct InterestingnessScorer {
+    weights: InterestingnessWeights,
+    class_counts: BTreeMap<String, u32>,
+    centroid: Option<Vec<f32>>,
+}
+
+impl InterestingnessScorer {
+    pub fn new(weights: InterestingnessWeights) -> Self {
+        Self {
+            weights,
+            class_counts: BTreeMap::new(),
+            centroid: None,
+        }
+    }
+
+    /// Number of images per class over the rarity period.
+    pub fn set_class_counts(&mut self, class_counts: BTreeMap<String, u32>) {
+        self.class_counts = class_counts;
+    }
+
+    /// Score an image, and add its embedding to the centroid of recent images.
+    pub fn score(&mut self, image: &DynamicImage, detection_result: Option<&DetectionResult>) -> f32 {
+        let detections = detection_result.map(|result| result.detections.as_slice()).unwrap_or_default();
+
+        let confidence = detections
+            .iter()
+            .map(|detection| detection.confidence)
+            .reduce(f32::max);
+
+        let total: u32 = self.class_counts.values().sum();
+        let rarity = detections
+            .iter()
+            .map(|detection| {
+                class_rarity(self.class_counts.get(&detection.class_name).copied().unwrap_or(0), total)
+            })
+            .reduce(f32::max);
+
+        let embedding = detection_result.and_then(|result| result.embeddings.as_deref());
+        let novelty = embedding.and_then(|embedding| self.novelty(embedding));
+
+        let sharpness = Some(sharpness_score(image));
+
+        combine_components(&[
+            (self.weights.confidence, confidence),
+            (self.weights.rarity, rarity),
+            (self.weights.novelty, novelty),
+            (self.weights.sharpness, sharpness),
+        ])
+    }
+
+    /// Distance from the centroid, then move the centroid towards the embedding.
+    fn novelty(&mut self, embedding: &[f32]) -> Option<f32> {
+        match &mut self.centroid {
+            Some(centroid) if centroid.len() == embedding.len() => {
+                let novelty = (1.0 - cosine_similarity(centroid, embedding)).clamp(0.0, 1.0);
+                for (c, e) in centroid.iter_mut().zip(embedding) {
+                    *c += CENTROID_ALPHA * (e - *c);
+                }
+                Some(novelty)
+            },
+            _ => {
+                self.centroid = Some(embedding.to_vec());
+                None
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interestingness_components() {
+        assert_eq!(class_rarity(0, 100), 1.0);
+        assert_eq!(class_rarity(100, 100), 0.0);
+        assert!(class_rarity(2, 100) > class_rarity(50, 100));
+
+        // Missing components do not count.
+        assert_eq!(combine_components(&[(0.5, Some(1.0)), (0.5, None)]), 1.0);
+        assert_eq!(combine_components(&[(0.3, Some(1.0)), (0.1, Some(0.0))]), 0.75);
+        assert_eq!(combine_components(&[(0.3, None)]), 0.0);
+    }
+}
@@ -2,8 +2,15 @@ pub mod frame;
 pub mod yplane;
 pub mod fourcc;
 pub mod conversions;
+pub mod perceptual_hash;
+pub mod redaction;
+pub mod embedding_clustering;
+pub mod sharpness;
+pub mod interestingness;
+pub mod hero_frame;
 pub mod motion;
 pub mod object_detection;
+pub mod tracking;
 
 #[cfg(feature = "libcamera")]
 pub mod libcamera;
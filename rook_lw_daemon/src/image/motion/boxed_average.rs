@@ -1,6 +1,8 @@
 use crate::{RookLWError, RookLWResult};
 use crate::image::yplane::YPlane;
 
+use rook_lw_models::image::MotionRegion;
+
 /// Compute per-box average luma differences between two Y planes.
 ///
 /// Divides each image into a grid of `divisions x divisions` boxes and computes
@@ -97,3 +99,111 @@ pub fn compute_boxed_averages(
     Ok(differences)
 }
 
+/// Group boxes whose difference meets `threshold` into motion regions.
+///
+/// `differences` is the row-major output of [`compute_boxed_averages`]. Boxes that
+/// touch (including diagonally) are merged into one region, and each region is
+/// returned as a bounding rectangle in pixel coordinates of a `width x height` frame.
+/// Regions are ordered by descending score.
+pub fn find_motion_regions(
+    differences: &[f32],
+    divisions: usize,
+    width: usize,
+    height: usize,
+    threshold: f32,
+) -> Vec<MotionRegion> {
+    if divisions == 0 || differences.len() != divisions * divisions {
+        return Vec::new();
+    }
+
+    let box_width = width / divisions;
+    let box_height = height / divisions;
+
+    let mut visited = vec![false; differences.len()];
+    let mut regions = Vec::new();
+
+    for start in 0..differences.len() {
+        if visited[start] || differences[start] < threshold {
+            continue;
+        }
+
+        // Flood fill the connected set of boxes over the threshold.
+        let (mut min_x, mut min_y) = (divisions, divisions);
+        let (mut max_x, mut max_y) = (0, 0);
+        let mut score = 0.0f32;
+
+        let mut stack = vec![start];
+        visited[start] = true;
+
+        while let Some(index) = stack.pop() {
+            let (box_x, box_y) = (index % divisions, index / divisions);
+            min_x = min_x.min(box_x);
+            min_y = min_y.min(box_y);
+            max_x = max_x.max(box_x);
+            max_y = max_y.max(box_y);
+            score = score.max(differences[index]);
+
+            for dy in -1i64..=1 {
+                for dx in -1i64..=1 {
+                    let nx = box_x as i64 + dx;
+                    let ny = box_y as i64 + dy;
+                    if nx < 0 || ny < 0 || nx >= divisions as i64 || ny >= divisions as i64 {
+                        continue;
+                    }
+                    let neighbor = ny as usize * divisions + nx as usize;
+                    if !visited[neighbor] && differences[neighbor] >= threshold {
+                        visited[neighbor] = true;
+                        stack.push(neighbor);
+                    }
+                }
+            }
+        }
+
+        // The last row / column of boxes absorbs any remainder pixels.
+        let start_x = min_x * box_width;
+        let start_y = min_y * box_height;
+        let end_x = if max_x == divisions - 1 { width } else { (max_x + 1) * box_width };
+        let end_y = if max_y == divisions - 1 { height } else { (max_y + 1) * box_height };
+
+        regions.push(MotionRegion {
+            x: start_x as i32,
+            y: start_y as i32,
+            width: (end_x - start_x) as i32,
+            height: (end_y - start_y) as i32,
+            score,
+        });
+    }
+
+    regions.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+    regions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_motion_regions_merges_touching_boxes() {
+        // 4x4 grid over a 100x100 frame, two separate areas of motion.
+        let differences = vec![
+            0.5, 0.3, 0.0, 0.0,
+            0.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, 0.1, 0.2,
+        ];
+
+        let regions = find_motion_regions(&differences, 4, 100, 100, 0.1);
+        assert_eq!(regions.len(), 2);
+
+        assert_eq!((regions[0].x, regions[0].y, regions[0].width, regions[0].height), (0, 0, 50, 25));
+        assert_eq!(regions[0].score, 0.5);
+
+        assert_eq!((regions[1].x, regions[1].y, regions[1].width, regions[1].height), (50, 75, 50, 25));
+    }
+
+    #[test]
+    fn find_motion_regions_empty_below_threshold() {
+        let differences = vec![0.01; 9];
+        assert!(find_motion_regions(&differences, 3, 90, 90, 0.02).is_empty());
+    }
+}
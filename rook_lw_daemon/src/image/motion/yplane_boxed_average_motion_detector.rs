@@ -1,6 +1,6 @@
 use rook_lw_models::image::MotionDetectionScore;
 
-use super::YPlaneMotionDetector;
+use super::{MotionThresholds, YPlaneMotionDetector, find_motion_regions};
 use crate::image::yplane::YPlane;
 use crate::RookLWResult;
 
@@ -24,12 +24,13 @@ impl YPlaneBoxedAverageMotionDetector {
 
 impl YPlaneMotionDetector for YPlaneBoxedAverageMotionDetector {
     fn detect_motion(&mut self, a: &YPlane<'_>, b: &YPlane<'_>) -> RookLWResult<MotionDetectionScore> {
-        let mut scores = crate::image::motion::boxed_average::compute_boxed_averages(
+        let differences = crate::image::motion::boxed_average::compute_boxed_averages(
             a,
             b,
             self.box_size,
         )?;
 
+        let mut scores = differences.clone();
         scores.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
 
         let index = ((self.percentile.clamp(0.0, 1.0) * (scores.len() - 1) as f32).round()) as usize;
@@ -37,6 +38,14 @@ impl YPlaneMotionDetector for YPlaneBoxedAverageMotionDetector {
 
         let detected = score >= self.percentile_threshold;
 
+        // Only locate the motion when it is going to be acted on.
+        let regions = if detected {
+            find_motion_regions(&differences, self.box_size, a.width, a.height, self.percentile_threshold)
+        }
+        else {
+            Vec::new()
+        };
+
         let mut properties = HashMap::new();
         properties.insert("percentile".to_string(), format!("{}", self.percentile));
 
@@ -44,6 +53,12 @@ impl YPlaneMotionDetector for YPlaneBoxedAverageMotionDetector {
             score,
             detected,
             properties,
+            regions,
         })
     }
+
+    fn set_thresholds(&mut self, thresholds: &MotionThresholds) {
+        self.percentile = thresholds.percentile;
+        self.percentile_threshold = thresholds.percentile_threshold;
+    }
 }
@@ -3,6 +3,22 @@ use crate::RookLWResult;
 
 use rook_lw_models::image::MotionDetectionScore;
 
+/// Settings of a motion detector that can change while it runs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MotionThresholds {
+    /// Percentile of the differences taken as the motion score.
+    pub percentile: f32,
+
+    /// Score from which motion is detected.
+    pub percentile_threshold: f32,
+
+    /// Rolling z score from which motion is detected, when rolling z is used.
+    pub z_threshold: f32,
+}
+
 pub trait YPlaneMotionDetector: Send {
     fn detect_motion(&mut self, a: &YPlane<'_>, b: &YPlane<'_>) -> RookLWResult<MotionDetectionScore>;
+
+    /// Replace the thresholds, keeping any state learned so far.
+    fn set_thresholds(&mut self, thresholds: &MotionThresholds);
 }
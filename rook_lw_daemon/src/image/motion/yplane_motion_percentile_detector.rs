@@ -1,6 +1,6 @@
 use rook_lw_models::image::MotionDetectionScore;
 
-use super::YPlaneMotionDetector;
+use super::{MotionThresholds, YPlaneMotionDetector};
 use crate::image::yplane::YPlane;
 use crate::RookLWResult;
 
@@ -38,6 +38,12 @@ impl YPlaneMotionDetector for YPlaneMotionPercentileDetector {
             score,
             detected,
             properties,
+            regions: Vec::new(),
         })
     }
+
+    fn set_thresholds(&mut self, thresholds: &MotionThresholds) {
+        self.percentile = thresholds.percentile;
+        self.percentile_threshold = thresholds.percentile_threshold;
+    }
 }
@@ -1,6 +1,6 @@
 use rook_lw_models::image::MotionDetectionScore;
 
-use super::YPlaneMotionDetector;
+use super::{MotionThresholds, YPlaneMotionDetector};
 use crate::image::yplane::YPlane;
 use crate::RookLWResult;
 use crate::stats::RollingZ;
@@ -35,6 +35,13 @@ impl<T: YPlaneMotionDetector> YPlaneMotionDetector for YPlaneRollingZMotionDetec
             score: z_score,
             detected,
             properties: result.properties,
+            regions: result.regions,
         })
     }
+
+    /// The rolling statistics are kept, so detection continues where it was.
+    fn set_thresholds(&mut self, thresholds: &MotionThresholds) {
+        self.z_threshold = thresholds.z_threshold;
+        self.detector.set_thresholds(thresholds);
+    }
 }
\ No newline at end of file
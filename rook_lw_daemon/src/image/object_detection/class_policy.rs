@@ -0,0 +1,193 @@
+//! Class policy applied to the output of any object detector.
+//!
+//! Models report every class they know about with a single confidence cutoff.
+//! The policy lets the configuration decide per class what is worth keeping:
+//! - `ignore`: classes that are always dropped (e.g. "bench", "traffic light")
+//! - `min_confidence`: per-class confidence cutoffs (e.g. "person" at 0.3)
+//! - `rename`: rename or merge labels (e.g. cat/dog to "small mammal")
+//!
+//! Ignore lists and confidence cutoffs match the label reported by the model,
+//! renaming happens last.
+
+use crate::RookLWResult;
+use crate::prodcon::LiveSettings;
+use super::ObjectDetector;
+
+use rook_lw_models::image::{DetectionResult, ModelProvenance};
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info};
+
+use std::collections::HashMap;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct ClassPolicy {
+    /// Minimum confidence for classes without their own entry in `min_confidence`.
+    pub default_min_confidence: Option<f32>,
+
+    /// Minimum confidence per class.
+    pub min_confidence: HashMap<String, f32>,
+
+    /// Classes that are always dropped.
+    pub ignore: Vec<String>,
+
+    /// Replacement labels. Several classes can map to the same label to merge them.
+    pub rename: HashMap<String, String>,
+}
+
+impl ClassPolicy {
+
+    /// True when the policy would leave every detection untouched.
+    pub fn is_empty(&self) -> bool {
+        self.default_min_confidence.is_none() &&
+        self.min_confidence.is_empty() &&
+        self.ignore.is_empty() &&
+        self.rename.is_empty()
+    }
+
+    /// Filter and relabel the detections of a result. Embeddings are kept as is,
+    /// and the detections as they were in `model_detections`.
+    ///
+    /// Renamed detections keep the class id reported by the model.
+    pub fn apply(&self, result: DetectionResult) -> DetectionResult {
+        let DetectionResult { detections, embeddings, suppressed, provenance, inference_duration_ms, model_detections } = result;
+        let model_detections = model_detections.or_else(|| (!self.is_empty()).then(|| detections.clone()));
+
+        let detections = detections
+            .into_iter()
+            .filter(|detection| {
+                if self.ignore.iter().any(|c| c == &detection.class_name) {
+                    return false;
+                }
+                let min_confidence = self.min_confidence
+                    .get(&detection.class_name)
+                    .copied()
+                    .or(self.default_min_confidence);
+                match min_confidence {
+                    Some(min_confidence) => detection.confidence >= min_confidence,
+                    None => true,
+                }
+            })
+            .map(|mut detection| {
+                if let Some(new_name) = self.rename.get(&detection.class_name) {
+                    detection.class_name = new_name.clone();
+                }
+                detection
+            })
+            .collect();
+
+        DetectionResult {
+            detections,
+            embeddings,
+            suppressed,
+            provenance,
+            inference_duration_ms,
+            model_detections,
+        }
+    }
+}
+
+/// Applies a [`ClassPolicy`] to the results of another object detector.
+pub struct ClassPolicyObjectDetector {
+    policy: ClassPolicy,
+    live_policy: Option<LiveSettings<ClassPolicy>>,
+    detector: Box<dyn ObjectDetector>,
+}
+
+impl ClassPolicyObjectDetector {
+    pub fn new(detector: Box<dyn ObjectDetector>, policy: ClassPolicy) -> Self {
+        Self {
+            policy,
+            live_policy: None,
+            detector,
+        }
+    }
+
+    /// Take a replaced policy into use from the next image on.
+    pub fn set_live_policy(&mut self, live_policy: LiveSettings<ClassPolicy>) -> &mut Self {
+        self.live_policy = Some(live_policy);
+        self
+    }
+}
+
+impl ObjectDetector for ClassPolicyObjectDetector {
+    fn detect(
+        &mut self,
+        image: &image::DynamicImage,
+    ) -> RookLWResult<DetectionResult> {
+        if let Some(policy) = self.live_policy.as_mut().and_then(|live_policy| live_policy.changed()) {
+            info!(class_policy = ?policy, "Using new class policy");
+            self.policy = (*policy).clone();
+        }
+
+        let raw_result = self.detector.detect(image)?;
+        let raw_count = raw_result.detections.len();
+
+        if tracing::enabled!(tracing::Level::DEBUG) {
+            for detection in &raw_result.detections {
+                debug!(
+                    class_name = %detection.class_name,
+                    confidence = %format!("{}", detection.confidence),
+                    "Raw detection before class policy"
+                );
+            }
+        }
+
+        let result = self.policy.apply(raw_result);
+
+        info!(
+            raw_detection_count = raw_count,
+            filtered_detection_count = result.detections.len(),
+            "Applied class policy"
+        );
+
+        Ok(result)
+    }
+
+    fn provenance(&self) -> ModelProvenance {
+        self.detector.provenance()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rook_lw_models::image::Detection;
+
+    fn detection(class_name: &str, confidence: f32) -> Detection {
+        Detection {
+            class_name: class_name.to_string(),
+            confidence,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn class_policy_filters_and_renames() {
+        let policy = ClassPolicy {
+            default_min_confidence: Some(0.5),
+            min_confidence: HashMap::from([("person".to_string(), 0.3)]),
+            ignore: vec!["bench".to_string()],
+            rename: HashMap::from([
+                ("cat".to_string(), "small mammal".to_string()),
+                ("dog".to_string(), "small mammal".to_string()),
+            ]),
+        };
+
+        let result = policy.apply(DetectionResult::new(vec![
+            detection("person", 0.35),
+            detection("potted plant", 0.35),
+            detection("bench", 0.9),
+            detection("cat", 0.6),
+            detection("dog", 0.7),
+        ]));
+
+        let names: Vec<&str> = result.detections.iter().map(|d| d.class_name.as_str()).collect();
+        assert_eq!(names, vec!["person", "small mammal", "small mammal"]);
+
+        // The model's own labels are kept for privacy redaction.
+        let model_names: Vec<&str> = result.model_detections.iter().flatten().map(|d| d.class_name.as_str()).collect();
+        assert_eq!(model_names, vec!["person", "potted plant", "bench", "cat", "dog"]);
+    }
+}
@@ -1,8 +1,16 @@
 
 mod object_detector;
+mod model_file;
 mod opencv_object_detector;
 mod yolov8_object_detector;
+mod region_detection;
+mod class_policy;
+mod static_detection_memory;
 
 pub use object_detector::*;
+pub use model_file::*;
 pub use opencv_object_detector::*;
-pub use yolov8_object_detector::*;
\ No newline at end of file
+pub use yolov8_object_detector::*;
+pub use region_detection::*;
+pub use class_policy::*;
+pub use static_detection_memory::*;
\ No newline at end of file
@@ -0,0 +1,18 @@
+//! Helpers for describing the model file an object detector was loaded from.
+
+use sha2::{Digest, Sha256};
+
+use std::path::Path;
+
+/// SHA-256 of the model file contents, as lowercase hex.
+pub fn model_file_hash(model_data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(model_data))
+}
+
+/// File name of the model, used as its name in the model provenance.
+pub fn model_file_name(model_path: &Path) -> String {
+    model_path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| model_path.display().to_string())
+}
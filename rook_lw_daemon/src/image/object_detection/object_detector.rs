@@ -1,6 +1,6 @@
 use crate::RookLWResult;
 
-use rook_lw_models::image::DetectionResult;
+use rook_lw_models::image::{DetectionResult, ModelProvenance};
 
 use image::DynamicImage;
 
@@ -15,4 +15,7 @@ pub trait ObjectDetector: Send {
         image: &DynamicImage,
     ) -> RookLWResult<DetectionResult>;
 
+    /// Describes the model and settings used, recorded with every result.
+    fn provenance(&self) -> ModelProvenance;
+
 }
\ No newline at end of file
@@ -1,9 +1,9 @@
 /// Object detection using YOLO (Darknet) models with OpenCV DNN module.
 
 use crate::{RookLWResult, RookLWError};
-use super::ObjectDetector;
+use super::{ObjectDetector, model_file_hash, model_file_name};
 
-use rook_lw_models::image::{Detection, DetectionResult};
+use rook_lw_models::image::{Detection, DetectionResult, ModelProvenance};
 
 use opencv::{
     core::{Mat, Scalar, Size, Rect, Vector},
@@ -13,6 +13,7 @@ use opencv::{
 use std::path::Path;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
+use std::time::Instant;
 use anyhow::Context;
 
 // OpenCV Net is thread-safe for inference, so this is safe for our use case
@@ -25,6 +26,8 @@ pub struct OpenCVObjectDetector {
     confidence_threshold: f32,
     nms_threshold: f32,
     input_size: i32,
+    model_name: String,
+    model_hash: String,
 }
 
 impl OpenCVObjectDetector {
@@ -68,6 +71,10 @@ impl OpenCVObjectDetector {
         net.set_preferable_target(DNN_TARGET_CPU)
             .context("Failed to set target")?;
 
+        let model_hash = model_file_hash(
+            &std::fs::read(weights_path).context("Failed to read weights file")?
+        );
+
         // Load class names
         let file = File::open(classes_path).context("Failed to open classes file")?;
         let reader = BufReader::new(file);
@@ -81,6 +88,8 @@ impl OpenCVObjectDetector {
             confidence_threshold,
             nms_threshold: 0.4,
             input_size: 416,
+            model_name: model_file_name(weights_path),
+            model_hash,
         })
     }
 
@@ -100,6 +109,7 @@ impl OpenCVObjectDetector {
 
     /// Detect objects in an image.
     pub fn detect(&mut self, image: &Mat) -> RookLWResult<DetectionResult> {
+        let timer = Instant::now();
         let image_width = image.cols();
         let image_height = image.rows();
 
@@ -197,10 +207,27 @@ impl OpenCVObjectDetector {
                 y: bbox.y.max(0),
                 width: bbox.width.max(0),
                 height: bbox.height.max(0),
+                track_id: None,
             });
         }
 
-        Ok(DetectionResult::new(detections))
+        let mut result = DetectionResult::new(detections);
+        result.provenance = Some(self.provenance());
+        result.inference_duration_ms = Some(timer.elapsed().as_millis() as u64);
+        Ok(result)
+    }
+
+    pub fn provenance(&self) -> ModelProvenance {
+        ModelProvenance {
+            detector_type: "opencv".to_string(),
+            model_name: self.model_name.clone(),
+            model_version: None,
+            model_hash: Some(self.model_hash.clone()),
+            input_width: Some(self.input_size as u32),
+            input_height: Some(self.input_size as u32),
+            confidence_threshold: Some(self.confidence_threshold),
+            nms_threshold: Some(self.nms_threshold),
+        }
     }
 
 }
@@ -215,6 +242,10 @@ impl ObjectDetector for OpenCVObjectDetector {
             .map_err(|e| RookLWError::Image(format!("OpenCV conversion error: {e}")))?;
         self.detect(&mat_ref)
     }
+
+    fn provenance(&self) -> ModelProvenance {
+        self.provenance()
+    }
 }
 
 /// Convert a DynamicImage to an OpenCV Mat (CV_8UC3, RGB order)
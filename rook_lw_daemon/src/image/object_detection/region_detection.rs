@@ -0,0 +1,170 @@
+//! Plan object detection crops around motion regions.
+//!
+//! Running the detector on the whole frame is the most expensive part of the
+//! pipeline. When the motion stage reports where the change happened, the
+//! detector can instead run on a few padded crops around those areas. Each crop
+//! is scaled to the model input by the detector, so small subjects also get more
+//! pixels than they would in a full-frame pass.
+
+use rook_lw_models::image::MotionRegion;
+
+/// Settings for detecting objects in motion regions instead of the full frame.
+#[derive(Debug, Clone)]
+pub struct RegionDetectionSettings {
+    /// Fraction of a region's width / height added to each side before cropping.
+    pub padding: f32,
+
+    /// Fall back to a full-frame pass when the crops would cover more than this
+    /// fraction of the frame.
+    pub max_coverage: f32,
+}
+
+impl Default for RegionDetectionSettings {
+    fn default() -> Self {
+        Self {
+            padding: 0.25,
+            max_coverage: 0.6,
+        }
+    }
+}
+
+/// A crop of the full frame, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DetectionCrop {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl DetectionCrop {
+    pub fn area(&self) -> u64 {
+        self.width as u64 * self.height as u64
+    }
+
+    fn intersects(&self, other: &DetectionCrop) -> bool {
+        self.x < other.x + other.width &&
+        other.x < self.x + self.width &&
+        self.y < other.y + other.height &&
+        other.y < self.y + self.height
+    }
+
+    fn union(&self, other: &DetectionCrop) -> DetectionCrop {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        let x_max = (self.x + self.width).max(other.x + other.width);
+        let y_max = (self.y + self.height).max(other.y + other.height);
+        DetectionCrop { x, y, width: x_max - x, height: y_max - y }
+    }
+}
+
+/// Work out which crops of an `image_width x image_height` frame to run detection on.
+///
+/// Each region is padded and grown to a square (the model input is square, so this
+/// avoids stretching the subject), then overlapping crops are merged.
+///
+/// Returns `None` when a full-frame pass should be used instead: there are no
+/// regions, or the crops would cover more than `max_coverage` of the frame.
+pub fn plan_detection_crops(
+    regions: &[MotionRegion],
+    image_width: u32,
+    image_height: u32,
+    settings: &RegionDetectionSettings,
+) -> Option<Vec<DetectionCrop>> {
+    if regions.is_empty() || image_width == 0 || image_height == 0 {
+        return None;
+    }
+
+    let mut crops: Vec<DetectionCrop> = regions
+        .iter()
+        .filter(|region| region.width > 0 && region.height > 0)
+        .map(|region| padded_square_crop(region, image_width, image_height, settings.padding))
+        .collect();
+
+    if crops.is_empty() {
+        return None;
+    }
+
+    // Merge until no two crops overlap, so no area is detected twice.
+    let mut merged = true;
+    while merged {
+        merged = false;
+        'outer: for i in 0..crops.len() {
+            for j in (i + 1)..crops.len() {
+                if crops[i].intersects(&crops[j]) {
+                    crops[i] = crops[i].union(&crops[j]);
+                    crops.remove(j);
+                    merged = true;
+                    break 'outer;
+                }
+            }
+        }
+    }
+
+    let covered: u64 = crops.iter().map(|c| c.area()).sum();
+    let coverage = covered as f64 / (image_width as u64 * image_height as u64) as f64;
+
+    if coverage > settings.max_coverage as f64 {
+        return None;
+    }
+
+    Some(crops)
+}
+
+fn padded_square_crop(region: &MotionRegion, image_width: u32, image_height: u32, padding: f32) -> DetectionCrop {
+    let pad_x = region.width as f32 * padding.max(0.0);
+    let pad_y = region.height as f32 * padding.max(0.0);
+
+    let side = (region.width as f32 + 2.0 * pad_x).max(region.height as f32 + 2.0 * pad_y);
+    let center_x = region.x as f32 + region.width as f32 / 2.0;
+    let center_y = region.y as f32 + region.height as f32 / 2.0;
+
+    let (x, width) = fit_span(center_x, side, image_width);
+    let (y, height) = fit_span(center_y, side, image_height);
+
+    DetectionCrop { x, y, width, height }
+}
+
+/// Place a span of `length` centered on `center`, shifted to stay within `0..limit`.
+fn fit_span(center: f32, length: f32, limit: u32) -> (u32, u32) {
+    let length = (length.ceil() as u32).clamp(1, limit);
+    let start = (center - length as f32 / 2.0).round().max(0.0) as u32;
+    let start = start.min(limit - length);
+    (start, length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn region(x: i32, y: i32, width: i32, height: i32) -> MotionRegion {
+        MotionRegion { x, y, width, height, score: 1.0 }
+    }
+
+    #[test]
+    fn plan_detection_crops_pads_to_square_within_frame() {
+        let settings = RegionDetectionSettings { padding: 0.5, max_coverage: 0.6 };
+        let crops = plan_detection_crops(&[region(0, 100, 100, 50)], 1000, 1000, &settings).unwrap();
+
+        // 100 wide + 50 each side = 200 square, shifted right to stay inside the frame.
+        assert_eq!(crops, vec![DetectionCrop { x: 0, y: 25, width: 200, height: 200 }]);
+    }
+
+    #[test]
+    fn plan_detection_crops_merges_overlapping() {
+        let settings = RegionDetectionSettings { padding: 0.0, max_coverage: 0.6 };
+        let crops = plan_detection_crops(
+            &[region(100, 100, 100, 100), region(150, 150, 100, 100)],
+            1000, 1000, &settings,
+        ).unwrap();
+
+        assert_eq!(crops, vec![DetectionCrop { x: 100, y: 100, width: 150, height: 150 }]);
+    }
+
+    #[test]
+    fn plan_detection_crops_falls_back_to_full_frame() {
+        let settings = RegionDetectionSettings::default();
+        assert!(plan_detection_crops(&[], 1000, 1000, &settings).is_none());
+        assert!(plan_detection_crops(&[region(0, 0, 900, 900)], 1000, 1000, &settings).is_none());
+    }
+}
@@ -0,0 +1,266 @@
+//! Memory of detections that never move.
+//!
+//! Some scenes contain background objects the model keeps mistaking for an
+//! animal, such as a rock detected as "sheep" in almost every event. The memory
+//! remembers detections whose class and box stay nearly identical across many
+//! events, and once one has been seen for long enough it is marked static and
+//! suppressed (or down-weighted) in later results.
+//!
+//! A static entry expires when it has not been seen for a while, so an animal
+//! that rests in the same spot for a few events is not muted for good.
+//!
+//! The memory lives in the detector of a camera, so it is per camera, and it
+//! starts empty when the daemon starts.
+
+use crate::image::tracking::box_iou;
+
+use rook_lw_models::image::{Detection, DetectionResult};
+
+use chrono::{DateTime, Duration, FixedOffset};
+use tracing::info;
+use uuid::Uuid;
+
+/// What to do with detections that match a static entry.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StaticSuppressionMode {
+    /// Move them to the `suppressed` debug field of the result.
+    Suppress,
+
+    /// Keep them, with their confidence multiplied by the given factor.
+    DownWeight(f32),
+}
+
+#[derive(Debug, Clone)]
+pub struct StaticSuppressionSettings {
+    /// Minimum IoU between a detection and a remembered box to count as the same object.
+    pub iou_threshold: f32,
+
+    /// Number of distinct events a box must be seen in before it is static.
+    pub min_events: u32,
+
+    /// Time between the first and the last sighting before a box is static.
+    pub min_duration: Duration,
+
+    /// Entries not seen for this long are forgotten.
+    pub expiry: Duration,
+
+    pub mode: StaticSuppressionMode,
+}
+
+impl Default for StaticSuppressionSettings {
+    fn default() -> Self {
+        Self {
+            iou_threshold: 0.8,
+            min_events: 5,
+            min_duration: Duration::minutes(60),
+            expiry: Duration::minutes(180),
+            mode: StaticSuppressionMode::Suppress,
+        }
+    }
+}
+
+// Upper bound on remembered boxes, the least recently seen are dropped first.
+const MAX_ENTRIES: usize = 1000;
+
+#[derive(Debug, Clone)]
+struct StaticEntry {
+    class_name: String,
+    // x, y, width, height
+    bbox: (f32, f32, f32, f32),
+    event_count: u32,
+    last_event_id: Uuid,
+    first_seen: DateTime<FixedOffset>,
+    last_seen: DateTime<FixedOffset>,
+    is_static: bool,
+}
+
+impl StaticEntry {
+    fn observe(&mut self, detection: &Detection, event_id: Uuid, timestamp: DateTime<FixedOffset>) {
+        // Follow slow drift of the box (e.g. shadows through the day).
+        let (x, y, width, height) = detection_box(detection);
+        self.bbox = (
+            self.bbox.0 * 0.9 + x * 0.1,
+            self.bbox.1 * 0.9 + y * 0.1,
+            self.bbox.2 * 0.9 + width * 0.1,
+            self.bbox.3 * 0.9 + height * 0.1,
+        );
+
+        if self.last_event_id != event_id {
+            self.event_count += 1;
+            self.last_event_id = event_id;
+        }
+        if timestamp > self.last_seen {
+            self.last_seen = timestamp;
+        }
+    }
+}
+
+fn detection_box(detection: &Detection) -> (f32, f32, f32, f32) {
+    (detection.x as f32, detection.y as f32, detection.width as f32, detection.height as f32)
+}
+
+pub struct StaticDetectionMemory {
+    settings: StaticSuppressionSettings,
+    entries: Vec<StaticEntry>,
+}
+
+impl StaticDetectionMemory {
+    pub fn new(settings: StaticSuppressionSettings) -> Self {
+        Self {
+            settings,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Suppress or down-weight the static detections of a result, then remember
+    /// all of its detections.
+    ///
+    /// Returns the number of detections that matched a static entry.
+    pub fn apply(
+        &mut self,
+        event_id: Uuid,
+        timestamp: DateTime<FixedOffset>,
+        result: &mut DetectionResult,
+    ) -> usize {
+        let expiry = self.settings.expiry;
+        self.entries.retain(|entry| timestamp - entry.last_seen <= expiry);
+
+        let mut static_count = 0;
+        let mut kept = Vec::with_capacity(result.detections.len());
+
+        for mut detection in std::mem::take(&mut result.detections) {
+            let entry_index = self.find_entry(&detection);
+            let is_static = entry_index.is_some_and(|i| self.entries[i].is_static);
+
+            match entry_index {
+                Some(i) => self.entries[i].observe(&detection, event_id, timestamp),
+                None => self.entries.push(StaticEntry {
+                    class_name: detection.class_name.clone(),
+                    bbox: detection_box(&detection),
+                    event_count: 1,
+                    last_event_id: event_id,
+                    first_seen: timestamp,
+                    last_seen: timestamp,
+                    is_static: false,
+                }),
+            }
+
+            if !is_static {
+                kept.push(detection);
+                continue;
+            }
+
+            static_count += 1;
+            match self.settings.mode {
+                StaticSuppressionMode::Suppress => result.suppressed.push(detection),
+                StaticSuppressionMode::DownWeight(factor) => {
+                    detection.confidence *= factor;
+                    kept.push(detection);
+                },
+            }
+        }
+
+        result.detections = kept;
+        self.mark_static();
+
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.sort_by_key(|entry| std::cmp::Reverse(entry.last_seen));
+            self.entries.truncate(MAX_ENTRIES);
+        }
+
+        static_count
+    }
+
+    /// Forget every remembered box, static or not.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    fn find_entry(&self, detection: &Detection) -> Option<usize> {
+        let bbox = detection_box(detection);
+        self.entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.class_name == detection.class_name)
+            .map(|(i, entry)| (i, box_iou(entry.bbox, bbox)))
+            .filter(|(_, iou)| *iou >= self.settings.iou_threshold)
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(i, _)| i)
+    }
+
+    fn mark_static(&mut self) {
+        for entry in self.entries.iter_mut().filter(|entry| !entry.is_static) {
+            if entry.event_count >= self.settings.min_events &&
+                entry.last_seen - entry.first_seen >= self.settings.min_duration {
+                entry.is_static = true;
+                info!(
+                    class_name = %entry.class_name,
+                    x = entry.bbox.0 as i32,
+                    y = entry.bbox.1 as i32,
+                    width = entry.bbox.2 as i32,
+                    height = entry.bbox.3 as i32,
+                    event_count = entry.event_count,
+                    "Detection marked static"
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rock() -> Detection {
+        Detection {
+            class_name: "sheep".to_string(),
+            confidence: 0.4,
+            x: 200,
+            y: 300,
+            width: 120,
+            height: 90,
+            ..Default::default()
+        }
+    }
+
+    fn at_minutes(minutes: i64) -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339("2024-06-01T08:00:00+00:00").unwrap() + Duration::minutes(minutes)
+    }
+
+    #[test]
+    fn static_detection_memory_suppresses_then_expires() {
+        let mut memory = StaticDetectionMemory::new(StaticSuppressionSettings::default());
+
+        // Five events over two hours.
+        for i in 0..5 {
+            let mut result = DetectionResult::new(vec![rock()]);
+            assert_eq!(memory.apply(Uuid::new_v4(), at_minutes(i * 30), &mut result), 0);
+            assert_eq!(result.detections.len(), 1);
+        }
+
+        let mut result = DetectionResult::new(vec![rock()]);
+        assert_eq!(memory.apply(Uuid::new_v4(), at_minutes(150), &mut result), 1);
+        assert!(result.detections.is_empty());
+        assert_eq!(result.suppressed.len(), 1);
+
+        // Not seen for longer than the expiry.
+        let mut result = DetectionResult::new(vec![rock()]);
+        assert_eq!(memory.apply(Uuid::new_v4(), at_minutes(150 + 181), &mut result), 0);
+        assert_eq!(result.detections.len(), 1);
+    }
+
+    #[test]
+    fn static_detection_memory_counts_events_not_frames() {
+        let mut memory = StaticDetectionMemory::new(StaticSuppressionSettings::default());
+
+        // Many frames, but a single event, is an animal resting, not a rock.
+        let event_id = Uuid::new_v4();
+        for i in 0..20 {
+            let mut result = DetectionResult::new(vec![rock()]);
+            memory.apply(event_id, at_minutes(i * 10), &mut result);
+        }
+
+        let mut result = DetectionResult::new(vec![rock()]);
+        assert_eq!(memory.apply(Uuid::new_v4(), at_minutes(210), &mut result), 0);
+    }
+}
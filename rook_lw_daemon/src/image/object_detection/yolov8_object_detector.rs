@@ -7,13 +7,14 @@
 //! - Better maintained and documented by Ultralytics
 
 use crate::RookLWResult;
-use crate::image::object_detection::ObjectDetector;
-use rook_lw_models::image::{Detection, DetectionResult};
+use crate::image::object_detection::{ObjectDetector, model_file_hash, model_file_name};
+use rook_lw_models::image::{Detection, DetectionResult, ModelProvenance};
 
 use anyhow::{Context, Result};
 use std::path::Path;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
+use std::time::Instant;
 
 use ort::{
     session::builder::GraphOptimizationLevel,
@@ -35,6 +36,8 @@ pub struct Yolov8ObjectDetector {
     nms_threshold: f32,
     input_width: usize,
     input_height: usize,
+    model_name: String,
+    model_hash: String,
 }
 
 impl Yolov8ObjectDetector {
@@ -52,25 +55,28 @@ impl Yolov8ObjectDetector {
     /// let detector = Yolov8ObjectDetector::new(
     ///     "yolov8n.onnx",
     ///     "coco.names",
-    ///     0.25
+    ///     0.25,
+    ///     4
     /// )?;
     /// ```
     pub fn new<P: AsRef<Path>>(
         model_path: P,
         classes_path: P,
         confidence_threshold: f32,
+        intra_threads: usize,
     ) -> RookLWResult<Self> {
         let model_path = model_path.as_ref();
         let classes_path = classes_path.as_ref();
 
         // Load ONNX model
+        let model_data = std::fs::read(model_path).context("Failed to read model file")?;
         let session = Session::builder()
             .context("Failed to create session builder")?
             .with_optimization_level(GraphOptimizationLevel::Level3)
             .context("Failed to set optimization level")?
-            .with_intra_threads(4)
+            .with_intra_threads(intra_threads)
             .context("Failed to set intra threads")?
-            .commit_from_memory(std::fs::read(model_path).context("Failed to read model file")?.as_slice())
+            .commit_from_memory(model_data.as_slice())
             .context("Failed to load ONNX model")?;
 
         tracing::info!(model_file = %model_path.display(), "Loaded model file.");
@@ -89,6 +95,8 @@ impl Yolov8ObjectDetector {
             nms_threshold: 0.45, // YOLOv8 default
             input_width: 640,    // YOLOv8 default input size
             input_height: 640,
+            model_name: model_file_name(model_path),
+            model_hash: model_file_hash(&model_data),
         })
     }
 
@@ -121,7 +129,8 @@ impl Yolov8ObjectDetector {
         image: &image::DynamicImage,
     ) -> RookLWResult<DetectionResult> {
         let (orig_width, orig_height) = image.dimensions();
-        
+        let timer = Instant::now();
+
         // Preprocess directly from DynamicImage
         let input_tensor = self.preprocess(image)?;
         
@@ -155,7 +164,23 @@ impl Yolov8ObjectDetector {
         
         drop(outputs);
 
-        self.post_process(&shape_vec, &data_vec, embeddings, orig_width as i32, orig_height as i32)
+        let mut result = self.post_process(&shape_vec, &data_vec, embeddings, orig_width as i32, orig_height as i32)?;
+        result.provenance = Some(self.provenance());
+        result.inference_duration_ms = Some(timer.elapsed().as_millis() as u64);
+        Ok(result)
+    }
+
+    pub fn provenance(&self) -> ModelProvenance {
+        ModelProvenance {
+            detector_type: "yolov8".to_string(),
+            model_name: self.model_name.clone(),
+            model_version: None,
+            model_hash: Some(self.model_hash.clone()),
+            input_width: Some(self.input_width as u32),
+            input_height: Some(self.input_height as u32),
+            confidence_threshold: Some(self.confidence_threshold),
+            nms_threshold: Some(self.nms_threshold),
+        }
     }
 
     /// Preprocess image for YOLOv8 inference.
@@ -289,12 +314,17 @@ impl Yolov8ObjectDetector {
                 y: y.max(0.0) as i32,
                 width: width.max(0.0) as i32,
                 height: height.max(0.0) as i32,
+                track_id: None,
             });
         }
 
         Ok(DetectionResult {
             detections,
             embeddings,
+            suppressed: Vec::new(),
+            provenance: None,
+            inference_duration_ms: None,
+            model_detections: None,
         })
     }
 
@@ -399,4 +429,8 @@ impl ObjectDetector for Yolov8ObjectDetector {
     ) -> RookLWResult<DetectionResult> {
         self.detect(image)
     }
+
+    fn provenance(&self) -> ModelProvenance {
+        self.provenance()
+    }
 }
@@ -0,0 +1,50 @@
+//! Difference hash (dHash) of an image.
+//!
+//! The image is reduced to a 9x8 grayscale thumbnail and each bit records
+//! whether a pixel is brighter than its right neighbour. Similar images have
+//! hashes that differ in few bits, regardless of small changes in exposure.
+
+use image::DynamicImage;
+use image::imageops::FilterType;
+
+/// 64-bit difference hash of the image.
+pub fn dhash(image: &DynamicImage) -> u64 {
+    let thumbnail = image.resize_exact(9, 8, FilterType::Triangle).to_luma8();
+
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let left = thumbnail.get_pixel(x, y)[0];
+            let right = thumbnail.get_pixel(x + 1, y)[0];
+            hash = (hash << 1) | (left > right) as u64;
+        }
+    }
+    hash
+}
+
+/// Number of bits that differ between two hashes.
+pub fn hamming_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GrayImage, Luma};
+
+    fn gradient(width: u32, height: u32, offset: u8) -> DynamicImage {
+        DynamicImage::ImageLuma8(GrayImage::from_fn(width, height, |x, y| {
+            Luma([((x * 255 / width) as u8).wrapping_add(((y * 7) % 13) as u8).saturating_add(offset)])
+        }))
+    }
+
+    #[test]
+    fn dhash_ignores_brightness_and_size() {
+        let a = dhash(&gradient(640, 480, 0));
+        let b = dhash(&gradient(320, 240, 10));
+        assert!(hamming_distance(a, b) <= 4);
+
+        let flipped = dhash(&gradient(640, 480, 0).fliph());
+        assert!(hamming_distance(a, flipped) > 32);
+    }
+}
@@ -0,0 +1,107 @@
+//! Irreversible obscuring of image regions.
+
+use image::{DynamicImage, GenericImage, GenericImageView, Rgba};
+use image::imageops;
+
+/// How a region is obscured.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RedactionMethod {
+    /// Gaussian blur with the given sigma, in pixels.
+    Blur(f32),
+
+    /// Replace each block of the given size with its mean color.
+    Pixelate(u32),
+}
+
+/// Region around a box, grown by `padding` times its width and height on each
+/// side and clipped to the image. None when nothing of it is inside the image.
+pub fn padded_region(
+    (x, y, width, height): (i32, i32, i32, i32),
+    padding: f32,
+    image_width: u32,
+    image_height: u32,
+) -> Option<(u32, u32, u32, u32)> {
+    let pad_x = (width as f32 * padding).round() as i64;
+    let pad_y = (height as f32 * padding).round() as i64;
+
+    let left = (x as i64 - pad_x).clamp(0, image_width as i64);
+    let top = (y as i64 - pad_y).clamp(0, image_height as i64);
+    let right = (x as i64 + width as i64 + pad_x).clamp(0, image_width as i64);
+    let bottom = (y as i64 + height as i64 + pad_y).clamp(0, image_height as i64);
+
+    if right <= left || bottom <= top {
+        return None;
+    }
+    Some((left as u32, top as u32, (right - left) as u32, (bottom - top) as u32))
+}
+
+/// Obscure a region of the image in place.
+pub fn redact_region(image: &mut DynamicImage, (x, y, width, height): (u32, u32, u32, u32), method: RedactionMethod) {
+    let region = image.view(x, y, width, height).to_image();
+
+    let redacted = match method {
+        RedactionMethod::Blur(sigma) => imageops::fast_blur(&region, sigma.max(1.0)),
+        RedactionMethod::Pixelate(block_size) => pixelate(&region, block_size.max(1)),
+    };
+
+    // The region was cut from the image, so it always fits.
+    let _ = image.copy_from(&redacted, x, y);
+}
+
+fn pixelate(region: &image::RgbaImage, block_size: u32) -> image::RgbaImage {
+    let mut pixelated = region.clone();
+    let (width, height) = region.dimensions();
+
+    for block_y in (0..height).step_by(block_size as usize) {
+        for block_x in (0..width).step_by(block_size as usize) {
+            let block_width = block_size.min(width - block_x);
+            let block_height = block_size.min(height - block_y);
+
+            let mut sum = [0u64; 4];
+            for y in block_y..block_y + block_height {
+                for x in block_x..block_x + block_width {
+                    let pixel = region.get_pixel(x, y);
+                    for (s, c) in sum.iter_mut().zip(pixel.0) {
+                        *s += c as u64;
+                    }
+                }
+            }
+            let count = (block_width * block_height) as u64;
+            let mean = Rgba(sum.map(|s| (s / count) as u8));
+
+            for y in block_y..block_y + block_height {
+                for x in block_x..block_x + block_width {
+                    pixelated.put_pixel(x, y, mean);
+                }
+            }
+        }
+    }
+    pixelated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbImage;
+
+    #[test]
+    fn redact_region_pixelates_only_the_padded_box() {
+        let mut image = DynamicImage::ImageRgb8(RgbImage::from_fn(64, 64, |x, y| {
+            image::Rgb([(x * 4) as u8, (y * 4) as u8, 0])
+        }));
+        let original = image.clone();
+
+        // Box of 20x20 at (10, 10), padded by 5 on each side; clipped on the left.
+        let region = padded_region((-2, 10, 20, 20), 0.25, 64, 64).unwrap();
+        assert_eq!(region, (0, 5, 23, 30));
+
+        redact_region(&mut image, region, RedactionMethod::Pixelate(8));
+
+        assert_eq!(image.get_pixel(0, 5), image.get_pixel(7, 12));
+        assert_ne!(image.get_pixel(0, 5), original.get_pixel(0, 5));
+        assert_eq!(image.get_pixel(40, 40), original.get_pixel(40, 40));
+        assert_eq!(image.get_pixel(0, 4), original.get_pixel(0, 4));
+
+        assert_eq!(padded_region((70, 70, 10, 10), 0.1, 64, 64), None);
+    }
+}
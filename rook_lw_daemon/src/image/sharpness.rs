@@ -0,0 +1,73 @@
+//! Image sharpness as the variance of the Laplacian.
+//!
+//! Blurry images (motion blur, out of focus, fog on the lens) have few strong
+//! edges, so the Laplacian response is flat and its variance is low.
+
+use image::{DynamicImage, GenericImageView};
+use image::imageops::FilterType;
+
+// Images are reduced to at most this width first; sharpness is compared
+// between images of the same camera, so the absolute scale does not matter.
+const MAX_WIDTH: u32 = 640;
+
+// Laplacian variance at which the sharpness score is 0.5.
+const SHARPNESS_SCALE: f64 = 100.0;
+
+/// Sharpness on a 0 to 1 scale.
+pub fn sharpness_score(image: &DynamicImage) -> f32 {
+    let variance = laplacian_variance(image);
+    (variance / (variance + SHARPNESS_SCALE)) as f32
+}
+
+/// Variance of the 4-neighbour Laplacian of the grayscale image.
+pub fn laplacian_variance(image: &DynamicImage) -> f64 {
+    let (width, height) = image.dimensions();
+    let gray = if width > MAX_WIDTH {
+        let scaled_height = (height as u64 * MAX_WIDTH as u64 / width as u64).max(1) as u32;
+        image.resize_exact(MAX_WIDTH, scaled_height, FilterType::Triangle).to_luma8()
+    } else {
+        image.to_luma8()
+    };
+
+    let (width, height) = gray.dimensions();
+    if width < 3 || height < 3 {
+        return 0.0;
+    }
+
+    let mut sum = 0.0;
+    let mut sum_squares = 0.0;
+    for y in 1..height - 1 {
+        for x in 1..width - 1 {
+            let center = gray.get_pixel(x, y)[0] as f64;
+            let laplacian = gray.get_pixel(x - 1, y)[0] as f64
+                + gray.get_pixel(x + 1, y)[0] as f64
+                + gray.get_pixel(x, y - 1)[0] as f64
+                + gray.get_pixel(x, y + 1)[0] as f64
+                - 4.0 * center;
+            sum += laplacian;
+            sum_squares += laplacian * laplacian;
+        }
+    }
+
+    let count = ((width - 2) * (height - 2)) as f64;
+    let mean = sum / count;
+    sum_squares / count - mean * mean
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GrayImage, Luma};
+
+    #[test]
+    fn laplacian_variance_prefers_sharp_images() {
+        let sharp = DynamicImage::ImageLuma8(GrayImage::from_fn(64, 64, |x, y| {
+            Luma([if (x / 4 + y / 4) % 2 == 0 { 0 } else { 255 }])
+        }));
+        let blurred = sharp.blur(3.0);
+        let flat = DynamicImage::ImageLuma8(GrayImage::from_pixel(64, 64, Luma([128])));
+
+        assert!(laplacian_variance(&sharp) > laplacian_variance(&blurred));
+        assert_eq!(laplacian_variance(&flat), 0.0);
+    }
+}
@@ -0,0 +1,75 @@
+/// Constant-velocity Kalman filter for a single coordinate.
+///
+/// State is position and velocity. Bounding boxes are tracked with one filter per
+/// box coordinate (center x, center y, width, height), which is simpler than the
+/// coupled filter in the SORT paper and behaves the same for short events.
+#[derive(Debug, Clone)]
+pub struct KalmanAxis {
+    position: f32,
+    velocity: f32,
+
+    // Covariance matrix [[p00, p01], [p01, p11]]
+    p00: f32,
+    p01: f32,
+    p11: f32,
+
+    process_noise: f32,
+    measurement_noise: f32,
+}
+
+impl KalmanAxis {
+    pub fn new(position: f32, process_noise: f32, measurement_noise: f32) -> Self {
+        Self {
+            position,
+            velocity: 0.0,
+            p00: measurement_noise,
+            p01: 0.0,
+            // Nothing is known about velocity until the second measurement.
+            p11: 1000.0,
+            process_noise,
+            measurement_noise,
+        }
+    }
+
+    pub fn position(&self) -> f32 {
+        self.position
+    }
+
+    pub fn velocity(&self) -> f32 {
+        self.velocity
+    }
+
+    /// Advance the state by `dt` steps.
+    pub fn predict(&mut self, dt: f32) {
+        self.position += self.velocity * dt;
+
+        // P = F P F^T + Q, F = [[1, dt], [0, 1]]
+        let q = self.process_noise;
+        let p00 = self.p00 + dt * (2.0 * self.p01 + dt * self.p11) + q * dt * dt * dt / 3.0;
+        let p01 = self.p01 + dt * self.p11 + q * dt * dt / 2.0;
+        let p11 = self.p11 + q * dt;
+
+        self.p00 = p00;
+        self.p01 = p01;
+        self.p11 = p11;
+    }
+
+    /// Correct the state with a measured position.
+    pub fn update(&mut self, measurement: f32) {
+        let innovation = measurement - self.position;
+        let s = self.p00 + self.measurement_noise;
+        let k0 = self.p00 / s;
+        let k1 = self.p01 / s;
+
+        self.position += k0 * innovation;
+        self.velocity += k1 * innovation;
+
+        let p00 = (1.0 - k0) * self.p00;
+        let p01 = (1.0 - k0) * self.p01;
+        let p11 = self.p11 - k1 * self.p01;
+
+        self.p00 = p00;
+        self.p01 = p01;
+        self.p11 = p11;
+    }
+}
@@ -0,0 +1,6 @@
+
+mod kalman;
+mod sort_tracker;
+
+pub use kalman::*;
+pub use sort_tracker::*;
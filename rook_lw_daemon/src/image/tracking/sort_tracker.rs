@@ -0,0 +1,249 @@
+//! SORT-style multi-object tracking across the frames of an event.
+//!
+//! Each track predicts where its box will be in the next frame with a Kalman
+//! filter, and detections are associated with tracks by IoU against those
+//! predictions. Detections that match no track start a new one, so every
+//! detection ends up with a track id that is stable for the same object.
+//!
+//! Association is greedy by highest IoU rather than the Hungarian assignment
+//! used by SORT; with the handful of objects in a trail-cam frame the results
+//! are the same.
+
+use super::KalmanAxis;
+
+use rook_lw_models::image::Detection;
+
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone)]
+pub struct TrackerSettings {
+    /// Minimum IoU between a predicted track box and a detection to match them.
+    pub iou_threshold: f32,
+
+    /// Number of frames a track survives without a matching detection.
+    pub max_age: u32,
+}
+
+impl Default for TrackerSettings {
+    fn default() -> Self {
+        Self {
+            iou_threshold: 0.3,
+            max_age: 3,
+        }
+    }
+}
+
+// Noise for the box Kalman filters, in pixels.
+const PROCESS_NOISE: f32 = 10.0;
+const MEASUREMENT_NOISE: f32 = 25.0;
+
+#[derive(Debug, Clone)]
+struct Track {
+    track_id: u32,
+    class_name: String,
+    // center x, center y, width, height
+    axes: [KalmanAxis; 4],
+    last_frame: u32,
+}
+
+impl Track {
+    fn new(track_id: u32, detection: &Detection, frame_index: u32) -> Self {
+        let measurement = detection_measurement(detection);
+        Self {
+            track_id,
+            class_name: detection.class_name.clone(),
+            axes: measurement.map(|m| KalmanAxis::new(m, PROCESS_NOISE, MEASUREMENT_NOISE)),
+            last_frame: frame_index,
+        }
+    }
+
+    fn predict(&mut self, dt: f32) {
+        for axis in self.axes.iter_mut() {
+            axis.predict(dt);
+        }
+    }
+
+    fn update(&mut self, detection: &Detection, frame_index: u32) {
+        let measurement = detection_measurement(detection);
+        for (axis, m) in self.axes.iter_mut().zip(measurement) {
+            axis.update(m);
+        }
+        self.last_frame = frame_index;
+    }
+
+    fn predicted_box(&self) -> (f32, f32, f32, f32) {
+        let [cx, cy, w, h] = &self.axes;
+        let width = w.position().max(1.0);
+        let height = h.position().max(1.0);
+        (cx.position() - width / 2.0, cy.position() - height / 2.0, width, height)
+    }
+}
+
+fn detection_measurement(detection: &Detection) -> [f32; 4] {
+    [
+        detection.x as f32 + detection.width as f32 / 2.0,
+        detection.y as f32 + detection.height as f32 / 2.0,
+        detection.width as f32,
+        detection.height as f32,
+    ]
+}
+
+fn detection_box(detection: &Detection) -> (f32, f32, f32, f32) {
+    (detection.x as f32, detection.y as f32, detection.width as f32, detection.height as f32)
+}
+
+/// Intersection over union of two `(x, y, width, height)` boxes.
+pub fn box_iou(box_a: (f32, f32, f32, f32), box_b: (f32, f32, f32, f32)) -> f32 {
+    let (x1, y1, w1, h1) = box_a;
+    let (x2, y2, w2, h2) = box_b;
+
+    let inter_width = ((x1 + w1).min(x2 + w2) - x1.max(x2)).max(0.0);
+    let inter_height = ((y1 + h1).min(y2 + h2) - y1.max(y2)).max(0.0);
+    let inter_area = inter_width * inter_height;
+
+    let union_area = w1 * h1 + w2 * h2 - inter_area;
+    if union_area > 0.0 {
+        inter_area / union_area
+    } else {
+        0.0
+    }
+}
+
+/// Tracks the objects of a single event. Create a new tracker for each event.
+pub struct SortTracker {
+    settings: TrackerSettings,
+    tracks: Vec<Track>,
+    next_track_id: u32,
+    last_frame: Option<u32>,
+    class_counts: BTreeMap<String, u32>,
+}
+
+impl SortTracker {
+    pub fn new(settings: TrackerSettings) -> Self {
+        Self {
+            settings,
+            tracks: Vec::new(),
+            next_track_id: 1,
+            last_frame: None,
+            class_counts: BTreeMap::new(),
+        }
+    }
+
+    /// Assign track ids to the detections of the frame at `frame_index`.
+    ///
+    /// Frames should be given in capture order. Frames can be skipped (for
+    /// example frames without detections); tracks are predicted forward by the
+    /// gap in frame index.
+    pub fn update(&mut self, frame_index: u32, detections: &mut [Detection]) {
+        let dt = match self.last_frame {
+            Some(last_frame) if frame_index > last_frame => (frame_index - last_frame) as f32,
+            _ => 1.0,
+        };
+
+        for track in self.tracks.iter_mut() {
+            track.predict(dt);
+        }
+
+        let max_age = self.settings.max_age;
+        self.tracks.retain(|track| frame_index.saturating_sub(track.last_frame) <= max_age);
+
+        // Candidate pairs of (iou, track index, detection index), best first.
+        let mut candidates = Vec::new();
+        for (track_index, track) in self.tracks.iter().enumerate() {
+            let predicted = track.predicted_box();
+            for (detection_index, detection) in detections.iter().enumerate() {
+                if detection.class_name != track.class_name {
+                    continue;
+                }
+                let iou = box_iou(predicted, detection_box(detection));
+                if iou >= self.settings.iou_threshold {
+                    candidates.push((iou, track_index, detection_index));
+                }
+            }
+        }
+        candidates.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+
+        let mut track_matched = vec![false; self.tracks.len()];
+        let mut detection_matched = vec![false; detections.len()];
+
+        for (_iou, track_index, detection_index) in candidates {
+            if track_matched[track_index] || detection_matched[detection_index] {
+                continue;
+            }
+            track_matched[track_index] = true;
+            detection_matched[detection_index] = true;
+
+            let track = &mut self.tracks[track_index];
+            track.update(&detections[detection_index], frame_index);
+            detections[detection_index].track_id = Some(track.track_id);
+        }
+
+        for (detection_index, detection) in detections.iter_mut().enumerate() {
+            if detection_matched[detection_index] {
+                continue;
+            }
+            let track = Track::new(self.next_track_id, detection, frame_index);
+            detection.track_id = Some(track.track_id);
+            *self.class_counts.entry(track.class_name.clone()).or_insert(0) += 1;
+            self.tracks.push(track);
+            self.next_track_id += 1;
+        }
+
+        self.last_frame = Some(frame_index);
+    }
+
+    /// Number of distinct tracks seen so far, per class.
+    pub fn class_counts(&self) -> &BTreeMap<String, u32> {
+        &self.class_counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detection(class_name: &str, x: i32, y: i32) -> Detection {
+        Detection {
+            class_name: class_name.to_string(),
+            confidence: 0.9,
+            x,
+            y,
+            width: 100,
+            height: 80,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn sort_tracker_keeps_ids_for_moving_objects() {
+        let mut tracker = SortTracker::new(TrackerSettings::default());
+
+        for frame_index in 0..5u32 {
+            let offset = frame_index as i32 * 15;
+            let mut detections = vec![
+                detection("deer", 100 + offset, 100),
+                detection("deer", 600 - offset, 300),
+            ];
+            tracker.update(frame_index, &mut detections);
+            assert_eq!(detections[0].track_id, Some(1));
+            assert_eq!(detections[1].track_id, Some(2));
+        }
+
+        assert_eq!(tracker.class_counts().get("deer"), Some(&2));
+    }
+
+    #[test]
+    fn sort_tracker_new_track_after_max_age() {
+        let mut tracker = SortTracker::new(TrackerSettings { iou_threshold: 0.3, max_age: 1 });
+
+        let mut detections = vec![detection("fox", 100, 100)];
+        tracker.update(0, &mut detections);
+
+        // Same place, but the track was not seen for too many frames.
+        let mut detections = vec![detection("fox", 100, 100)];
+        tracker.update(3, &mut detections);
+
+        assert_eq!(detections[0].track_id, Some(2));
+        assert_eq!(tracker.class_counts().get("fox"), Some(&2));
+    }
+}
@@ -1,8 +1,66 @@
 use rook_lw_daemon::app;
-use rook_lw_daemon::RookLWResult;
+use rook_lw_daemon::{RookLWError, RookLWResult};
+use rook_lw_daemon::prodcon::ShutdownSignal;
+
+use clap::{Parser, Subcommand};
+
+/// Watch the camera for motion, detect objects and store the images.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    /// Configuration file; relative paths in it are relative to the working directory
+    #[arg(long, default_value = "config/rook_lw_daemon.toml")]
+    config: String,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run the daemon (the default)
+    Run,
+
+    /// Check the configuration file and report every problem with its line, without starting anything
+    CheckConfig,
+
+    /// Print the configuration with every setting at its default
+    PrintDefaultConfig,
+}
 
 fn main() -> RookLWResult<()> {
+    let cli = Cli::parse();
+
+    match cli.command.unwrap_or(Command::Run) {
+        Command::Run => run(&cli.config),
+        Command::CheckConfig => check_config(&cli.config),
+        Command::PrintDefaultConfig => {
+            let config_str = toml::to_string(&app::AppConfiguration::default())
+                .map_err(|e| RookLWError::Config(format!("Failed to write default configuration: {}", e)))?;
+            print!("{}", config_str);
+            Ok(())
+        },
+    }
+}
+
+fn run(config_path: &str) -> RookLWResult<()> {
     app::init_tracing();
-    let app = app::create_app()?;
-    app.run()
+    let shutdown = ShutdownSignal::new();
+    app::install_signal_handlers(shutdown.clone())?;
+    let app = app::create_app(config_path)?;
+    app.run(shutdown)
+}
+
+fn check_config(config_path: &str) -> RookLWResult<()> {
+    let problems = app::check_config(config_path)?;
+    for problem in &problems {
+        eprintln!("{}:{}", config_path, problem);
+    }
+    if problems.is_empty() {
+        println!("{}: ok", config_path);
+        Ok(())
+    }
+    else {
+        Err(RookLWError::Config(format!("{} problems in {}", problems.len(), config_path)))
+    }
 }
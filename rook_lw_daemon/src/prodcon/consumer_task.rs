@@ -1,4 +1,5 @@
 use crate::RookLWResult;
+use super::{ErrorPolicy, Escalation};
 
 use std::thread::{JoinHandle, spawn};
 use std::any::type_name;
@@ -9,10 +10,28 @@ use crossbeam_channel::Receiver;
 pub trait ConsumerTask<T: Send + 'static>: Send {
     fn consume(&mut self, item: T) -> RookLWResult<()>;
 
+    /// Bring the task back to a clean state after a failure, when it is
+    /// restarted by a supervisor.
+    fn reset(&mut self) -> RookLWResult<()> {
+        Ok(())
+    }
+
+    /// Apply the error policy of the stage to the items here, for a task that
+    /// processes them elsewhere, like a pool of workers, and return true. Such
+    /// a task also records the processing time of its items, and stops the
+    /// pipeline through `escalation` when the policy calls for it.
+    fn take_error_policy(&mut self, _policy: &ErrorPolicy, _escalation: &Escalation) -> bool {
+        false
+    }
+
+    /// Called once the receiver is closed and drained.
+    fn finish(&mut self) {}
+
     fn run_listener(&mut self, item: Receiver<T>) -> RookLWResult<()> {
         for received in item.iter() {
             self.consume(received)?;
         }
+        self.finish();
         Ok(())
     }
 
@@ -56,4 +75,24 @@ pub trait ConsumerTask<T: Send + 'static>: Send {
             }
         })
     }
-}
\ No newline at end of file
+}
+
+/// Lets boxed tasks, such as the stages of a configured pipeline, be run like
+/// the task they contain.
+impl<T: Send + 'static, C: ConsumerTask<T> + ?Sized> ConsumerTask<T> for Box<C> {
+    fn consume(&mut self, item: T) -> RookLWResult<()> {
+        (**self).consume(item)
+    }
+
+    fn reset(&mut self) -> RookLWResult<()> {
+        (**self).reset()
+    }
+
+    fn take_error_policy(&mut self, policy: &ErrorPolicy, escalation: &Escalation) -> bool {
+        (**self).take_error_policy(policy, escalation)
+    }
+
+    fn finish(&mut self) {
+        (**self).finish()
+    }
+}
@@ -0,0 +1,339 @@
+use crate::{RookLWError, RookLWResult};
+use super::{ConsumerTask, ErrorPolicy, Escalation, ProducerCallbacks, ProducerTask, StageMetrics};
+
+use r2d2::{Pool, PooledConnection};
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{params, OptionalExtension};
+use tracing::{info, warn};
+
+use std::marker::PhantomData;
+
+/// Items that can be written to a [`DurableQueue`].
+pub trait QueueItem: Sized {
+    /// Identifies the item within a queue; an item pushed again replaces it.
+    fn queue_key(&self) -> String;
+
+    fn encode(&self) -> RookLWResult<Vec<u8>>;
+
+    fn decode(data: &[u8]) -> RookLWResult<Self>;
+}
+
+/// Sqlite database holding the items of all durable queues.
+#[derive(Clone)]
+pub struct DurableQueueStore {
+    pool: Pool<SqliteConnectionManager>,
+}
+
+impl DurableQueueStore {
+    pub fn new(pool: Pool<SqliteConnectionManager>) -> RookLWResult<Self> {
+        let store = Self { pool };
+        store.connection()?
+            .execute_batch(
+                "CREATE TABLE IF NOT EXISTS queue_item (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    queue TEXT NOT NULL,
+                    item_key TEXT NOT NULL,
+                    data BLOB NOT NULL,
+                    attempts INTEGER NOT NULL DEFAULT 0,
+                    UNIQUE (queue, item_key)
+                );"
+            )
+            .map_err(database_error)?;
+        Ok(store)
+    }
+
+    fn connection(&self) -> RookLWResult<PooledConnection<SqliteConnectionManager>> {
+        self.pool.get().map_err(|e| RookLWError::Database(format!("Failed to get queue connection: {}", e)))
+    }
+}
+
+fn database_error(e: rusqlite::Error) -> RookLWError {
+    RookLWError::Database(format!("Durable queue error: {}", e))
+}
+
+/// Persistent copy of the items in the queue in front of a stage.
+///
+/// Items are written when they are sent and removed once the stage has
+/// processed them, so after a crash the items that were still queued or being
+/// processed can be sent again. Delivery is at least once: an item that was
+/// processed just before a crash is processed again.
+pub struct DurableQueue<T> {
+    name: String,
+    store: DurableQueueStore,
+
+    /// Items recovered more often than this are discarded, so an item that
+    /// crashes the daemon is not retried forever.
+    max_attempts: u32,
+
+    item_type: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for DurableQueue<T> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            store: self.store.clone(),
+            max_attempts: self.max_attempts,
+            item_type: PhantomData,
+        }
+    }
+}
+
+impl<T: QueueItem> DurableQueue<T> {
+    pub fn new(name: &str, store: DurableQueueStore, max_attempts: u32) -> Self {
+        Self {
+            name: name.to_string(),
+            store,
+            max_attempts,
+            item_type: PhantomData,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn push(&self, item: &T) -> RookLWResult<()> {
+        let data = item.encode()?;
+        self.store.connection()?
+            .execute(
+                "INSERT INTO queue_item (queue, item_key, data) VALUES (?1, ?2, ?3)
+                ON CONFLICT (queue, item_key) DO UPDATE SET data = excluded.data",
+                params![self.name, item.queue_key(), data],
+            )
+            .map_err(database_error)?;
+        Ok(())
+    }
+
+    pub fn remove(&self, item: &T) -> RookLWResult<()> {
+        self.store.connection()?
+            .execute(
+                "DELETE FROM queue_item WHERE queue = ?1 AND item_key = ?2",
+                params![self.name, item.queue_key()],
+            )
+            .map_err(database_error)?;
+        Ok(())
+    }
+
+    /// Count a recovery attempt for every item left from an earlier run, and
+    /// discard the items that reached the maximum. Returns the id of the last
+    /// item to recover, or None when there is nothing to recover.
+    ///
+    /// Call this before anything is sent, so new items are not recovered too.
+    pub fn prepare_recovery(&self) -> RookLWResult<Option<i64>> {
+        let connection = self.store.connection()?;
+        connection
+            .execute("UPDATE queue_item SET attempts = attempts + 1 WHERE queue = ?1", params![self.name])
+            .map_err(database_error)?;
+        let discarded_count = connection
+            .execute(
+                "DELETE FROM queue_item WHERE queue = ?1 AND attempts > ?2",
+                params![self.name, self.max_attempts],
+            )
+            .map_err(database_error)?;
+        if discarded_count > 0 {
+            warn!(queue = %self.name, discarded_count, "Discarded queued items that failed recovery too often");
+        }
+
+        let (recover_count, last_id): (i64, Option<i64>) = connection
+            .query_row(
+                "SELECT COUNT(*), MAX(id) FROM queue_item WHERE queue = ?1",
+                params![self.name],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(database_error)?;
+        if recover_count > 0 {
+            info!(queue = %self.name, recover_count, "Recovering queued items");
+        }
+        Ok(last_id)
+    }
+
+    /// The stored item after `after_id`, in the order the items were first pushed.
+    /// Items that can not be decoded are discarded.
+    fn next_stored(&self, after_id: i64) -> RookLWResult<Option<(i64, T)>> {
+        let connection = self.store.connection()?;
+        loop {
+            let row: Option<(i64, String, Vec<u8>)> = connection
+                .query_row(
+                    "SELECT id, item_key, data FROM queue_item WHERE queue = ?1 AND id > ?2 ORDER BY id LIMIT 1",
+                    params![self.name, after_id],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+                )
+                .optional()
+                .map_err(database_error)?;
+
+            let Some((id, item_key, data)) = row else {
+                return Ok(None);
+            };
+            match T::decode(&data) {
+                Ok(item) => return Ok(Some((id, item))),
+                Err(e) => {
+                    warn!(queue = %self.name, item_key = %item_key, error = %e, "Discarding queued item that can not be decoded");
+                    connection
+                        .execute("DELETE FROM queue_item WHERE id = ?1", params![id])
+                        .map_err(database_error)?;
+                },
+            }
+        }
+    }
+
+    /// Send the items up to `last_id` into the queue again, oldest first. The
+    /// items are already stored, so this sends past any overflow policy.
+    pub fn recover(&self, sender: &crossbeam_channel::Sender<T>, last_id: i64) -> RookLWResult<()> {
+        let mut after_id = 0;
+        while let Some((id, item)) = self.next_stored(after_id)? {
+            if id > last_id {
+                break;
+            }
+            sender.send(item)?;
+            after_id = id;
+        }
+        Ok(())
+    }
+}
+
+/// Writing side of a durable queue, for senders that don't know how items are encoded.
+pub trait QueueJournal<T>: Send + Sync {
+    /// Store the item before it is queued.
+    fn record(&self, item: &T) -> RookLWResult<()>;
+
+    /// Remove an item that was dropped instead of queued.
+    fn forget(&self, item: &T) -> RookLWResult<()>;
+}
+
+impl<T: QueueItem> QueueJournal<T> for DurableQueue<T> {
+    fn record(&self, item: &T) -> RookLWResult<()> {
+        self.push(item)
+    }
+
+    fn forget(&self, item: &T) -> RookLWResult<()> {
+        self.remove(item)
+    }
+}
+
+/// Removes each item from the durable queue once the wrapped task has
+/// processed it.
+///
+/// A failed item stays in the queue, and is processed again after a restart
+/// until it runs out of recovery attempts.
+pub struct DurableConsumer<C> {
+    task: C,
+    queue_name: String,
+    store: DurableQueueStore,
+}
+
+impl<C> DurableConsumer<C> {
+    pub fn new<T: QueueItem>(task: C, queue: &DurableQueue<T>) -> Self {
+        Self {
+            task,
+            queue_name: queue.name.clone(),
+            store: queue.store.clone(),
+        }
+    }
+}
+
+impl<T, C> ConsumerTask<T> for DurableConsumer<C>
+    where T: QueueItem + Send + 'static, C: ConsumerTask<T>
+{
+    fn consume(&mut self, item: T) -> RookLWResult<()> {
+        let item_key = item.queue_key();
+        self.task.consume(item)?;
+        self.store.connection()?
+            .execute(
+                "DELETE FROM queue_item WHERE queue = ?1 AND item_key = ?2",
+                params![self.queue_name, item_key],
+            )
+            .map_err(database_error)?;
+        Ok(())
+    }
+
+    fn reset(&mut self) -> RookLWResult<()> {
+        self.task.reset()
+    }
+
+    fn take_error_policy(&mut self, policy: &ErrorPolicy, escalation: &Escalation) -> bool {
+        self.task.take_error_policy(policy, escalation)
+    }
+
+    fn finish(&mut self) {
+        self.task.finish()
+    }
+}
+
+impl<T, C> ProducerTask<T> for DurableConsumer<C>
+    where T: Send + Clone + 'static, C: ProducerTask<T>
+{
+    fn get_producer_callbacks(&mut self) -> &mut ProducerCallbacks<T> {
+        self.task.get_producer_callbacks()
+    }
+
+    fn set_metrics(&mut self, metrics: StageMetrics) {
+        self.task.set_metrics(metrics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Frame(u32);
+
+    impl QueueItem for Frame {
+        fn queue_key(&self) -> String {
+            self.0.to_string()
+        }
+
+        fn encode(&self) -> RookLWResult<Vec<u8>> {
+            Ok(self.0.to_le_bytes().to_vec())
+        }
+
+        fn decode(data: &[u8]) -> RookLWResult<Self> {
+            let bytes = data.try_into().map_err(|_| RookLWError::Parse("bad frame".to_string()))?;
+            Ok(Frame(u32::from_le_bytes(bytes)))
+        }
+    }
+
+    struct Recorder(Vec<Frame>);
+
+    impl ConsumerTask<Frame> for Recorder {
+        fn consume(&mut self, item: Frame) -> RookLWResult<()> {
+            self.0.push(item);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn unprocessed_items_are_recovered_in_order_until_out_of_attempts() {
+        let dir = std::env::temp_dir().join(format!("rook_lw_durable_queue_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let pool = Pool::new(SqliteConnectionManager::file(dir.join("queue.db"))).unwrap();
+        let store = DurableQueueStore::new(pool).unwrap();
+        let queue = DurableQueue::<Frame>::new("image_detector", store, 2);
+
+        // First run: three frames queued, the first one processed before the crash.
+        for frame in 1..=3 {
+            queue.push(&Frame(frame)).unwrap();
+        }
+        let mut consumer = DurableConsumer::new(Recorder(Vec::new()), &queue);
+        consumer.consume(Frame(1)).unwrap();
+
+        // Second run: the other two come back, oldest first, but not a frame
+        // captured after startup.
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let last_id = queue.prepare_recovery().unwrap().unwrap();
+        queue.push(&Frame(4)).unwrap();
+        queue.recover(&sender, last_id).unwrap();
+        assert_eq!(receiver.try_iter().collect::<Vec<_>>(), vec![Frame(2), Frame(3)]);
+        consumer.consume(Frame(4)).unwrap();
+
+        // Third run: still unprocessed. Fourth run: out of attempts, discarded.
+        let last_id = queue.prepare_recovery().unwrap().unwrap();
+        queue.recover(&sender, last_id).unwrap();
+        assert_eq!(receiver.try_iter().count(), 2);
+        assert_eq!(queue.prepare_recovery().unwrap(), None);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,70 @@
+use std::sync::{Arc, Mutex};
+
+/// Settings of a running task that can be replaced from another thread, for
+/// example when the configuration is reloaded.
+///
+/// Clones share the settings. Each clone notices a replacement once, through
+/// [`LiveSettings::changed`], so a task checks for it between items and always
+/// works with a whole set of settings, never a mix of old and new.
+pub struct LiveSettings<S> {
+    shared: Arc<Mutex<(u64, Arc<S>)>>,
+    seen_version: u64,
+}
+
+impl<S> Clone for LiveSettings<S> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+            seen_version: self.seen_version,
+        }
+    }
+}
+
+impl<S> LiveSettings<S> {
+    pub fn new(settings: S) -> Self {
+        Self {
+            shared: Arc::new(Mutex::new((0, Arc::new(settings)))),
+            seen_version: 0,
+        }
+    }
+
+    /// Replace the settings for every clone.
+    pub fn replace(&self, settings: S) {
+        let mut shared = self.shared.lock().unwrap_or_else(|e| e.into_inner());
+        *shared = (shared.0 + 1, Arc::new(settings));
+    }
+
+    pub fn current(&self) -> Arc<S> {
+        self.shared.lock().unwrap_or_else(|e| e.into_inner()).1.clone()
+    }
+
+    /// The settings, when they were replaced since the last call.
+    pub fn changed(&mut self) -> Option<Arc<S>> {
+        let shared = self.shared.lock().unwrap_or_else(|e| e.into_inner());
+        if shared.0 == self.seen_version {
+            return None;
+        }
+        self.seen_version = shared.0;
+        Some(shared.1.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_clone_sees_the_latest_replacement_once() {
+        let publisher = LiveSettings::new(1);
+        let mut first = publisher.clone();
+        let mut second = publisher.clone();
+        assert_eq!(first.changed(), None);
+
+        publisher.replace(2);
+        publisher.replace(3);
+        assert_eq!(first.changed().as_deref(), Some(&3));
+        assert_eq!(first.changed(), None);
+        assert_eq!(second.changed().as_deref(), Some(&3));
+        assert_eq!(*second.current(), 3);
+    }
+}
@@ -0,0 +1,368 @@
+use crate::RookLWResult;
+use super::{ConsumerTask, ErrorPolicy, Escalation};
+
+use crossbeam_channel::Receiver;
+
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// Upper bounds of the buckets of the time histograms, in seconds.
+const SECONDS_BUCKETS: [f64; 12] = [0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// Items whose way through the pipeline is timed.
+pub trait Timed {
+    /// When the item was last put in a queue.
+    fn queued_at(&self) -> Option<Instant>;
+
+    fn set_queued_at(&mut self, queued_at: Instant);
+
+    /// Time since the item was captured.
+    fn age(&self) -> Duration;
+}
+
+#[derive(Default)]
+struct Histogram {
+    // Cumulative count per bucket, as in the exposition format.
+    bucket_counts: [u64; SECONDS_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        for (bound, bucket_count) in SECONDS_BUCKETS.iter().zip(self.bucket_counts.iter_mut()) {
+            if value <= *bound {
+                *bucket_count += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+#[derive(Default)]
+struct StageCounters {
+    items_in: AtomicU64,
+    items_out: AtomicU64,
+    errors: AtomicU64,
+    dropped: AtomicU64,
+    filtered: AtomicU64,
+    processing_seconds: Mutex<Histogram>,
+    queue_wait_seconds: Mutex<Histogram>,
+    queue_latency_seconds: Mutex<Histogram>,
+}
+
+/// Counters of one pipeline stage. Cheap to clone; all clones update the same counters.
+#[derive(Clone, Default)]
+pub struct StageMetrics {
+    counters: Arc<StageCounters>,
+}
+
+impl StageMetrics {
+    pub fn record_in(&self) {
+        self.counters.items_in.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_out(&self) {
+        self.counters.items_out.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_error(&self) {
+        self.counters.errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count an item dropped from the queue in front of the stage.
+    pub fn record_drop(&self) {
+        self.counters.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count an item the stage chose not to pass on, like a near-duplicate.
+    pub fn record_filtered(&self) {
+        self.counters.filtered.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn dropped_count(&self) -> u64 {
+        self.counters.dropped.load(Ordering::Relaxed)
+    }
+
+    pub fn observe_processing(&self, duration: Duration) {
+        observe(&self.counters.processing_seconds, duration);
+    }
+
+    /// Time an item waited in the queue in front of the stage.
+    pub fn observe_queue_wait(&self, duration: Duration) {
+        observe(&self.counters.queue_wait_seconds, duration);
+    }
+
+    /// Time from the capture of an item until it was sent to the queue in front of the stage.
+    pub fn observe_queue_latency(&self, duration: Duration) {
+        observe(&self.counters.queue_latency_seconds, duration);
+    }
+}
+
+fn observe(histogram: &Mutex<Histogram>, duration: Duration) {
+    histogram
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .observe(duration.as_secs_f64());
+}
+
+/// Records how long items waited in the queue in front of a stage, as the
+/// stage takes them out. A retried item is counted once.
+pub struct QueueWaitConsumer<C> {
+    task: C,
+    metrics: StageMetrics,
+    last_queued_at: Option<Instant>,
+}
+
+impl<C> QueueWaitConsumer<C> {
+    pub fn new(task: C, metrics: StageMetrics) -> Self {
+        Self {
+            task,
+            metrics,
+            last_queued_at: None,
+        }
+    }
+}
+
+impl<T: Timed + Send + 'static, C: ConsumerTask<T>> ConsumerTask<T> for QueueWaitConsumer<C> {
+    fn consume(&mut self, item: T) -> RookLWResult<()> {
+        if let Some(queued_at) = item.queued_at()
+            && self.last_queued_at != Some(queued_at)
+        {
+            self.metrics.observe_queue_wait(queued_at.elapsed());
+            self.last_queued_at = Some(queued_at);
+        }
+        self.task.consume(item)
+    }
+
+    fn reset(&mut self) -> RookLWResult<()> {
+        self.task.reset()
+    }
+
+    fn take_error_policy(&mut self, policy: &ErrorPolicy, escalation: &Escalation) -> bool {
+        self.task.take_error_policy(policy, escalation)
+    }
+
+    fn finish(&mut self) {
+        self.task.finish()
+    }
+}
+
+struct QueueGauge {
+    name: String,
+    length: Box<dyn Fn() -> usize + Send>,
+    capacity: Option<usize>,
+}
+
+#[derive(Default)]
+struct Registry {
+    stages: Vec<(String, StageMetrics)>,
+    queues: Vec<QueueGauge>,
+}
+
+/// Metrics of all pipeline stages and the queues between them, rendered in
+/// the Prometheus text exposition format.
+#[derive(Clone, Default)]
+pub struct PipelineMetrics {
+    registry: Arc<Mutex<Registry>>,
+}
+
+impl PipelineMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Metrics of the stage with this name, registered on first use.
+    pub fn stage(&self, name: &str) -> StageMetrics {
+        let mut registry = self.registry.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((_, metrics)) = registry.stages.iter().find(|(stage, _)| stage == name) {
+            return metrics.clone();
+        }
+        let metrics = StageMetrics::default();
+        registry.stages.push((name.to_string(), metrics.clone()));
+        metrics
+    }
+
+    /// Report the length of the queue read by `receiver`.
+    ///
+    /// Keeps a clone of the receiver, so the queue is not disconnected for its
+    /// senders until the metrics are dropped. Supervised consumers drain their
+    /// queue until it is closed, so senders never block on it.
+    pub fn register_queue<T: Send + 'static>(&self, name: &str, receiver: &Receiver<T>) {
+        let receiver = receiver.clone();
+        let mut registry = self.registry.lock().unwrap_or_else(|e| e.into_inner());
+        registry.queues.retain(|queue| queue.name != name);
+        registry.queues.push(QueueGauge {
+            name: name.to_string(),
+            capacity: receiver.capacity(),
+            length: Box::new(move || receiver.len()),
+        });
+    }
+
+    pub fn render(&self) -> String {
+        let registry = self.registry.lock().unwrap_or_else(|e| e.into_inner());
+        let mut out = String::new();
+
+        let stages = &registry.stages;
+        write_counter(&mut out, stages, "rook_lw_stage_items_in_total", "Items received by a pipeline stage.", |c| &c.items_in);
+        write_counter(&mut out, stages, "rook_lw_stage_items_out_total", "Items sent on by a pipeline stage.", |c| &c.items_out);
+        write_counter(&mut out, stages, "rook_lw_stage_errors_total", "Failed items or runs of a pipeline stage.", |c| &c.errors);
+        write_counter(&mut out, stages, "rook_lw_stage_dropped_total", "Items dropped from the full queue in front of a pipeline stage.", |c| &c.dropped);
+        write_counter(&mut out, stages, "rook_lw_stage_filtered_total", "Items a pipeline stage chose not to pass on.", |c| &c.filtered);
+
+        write_histogram(&mut out, stages, "rook_lw_stage_processing_seconds", "Time a pipeline stage spent on one item.", "stage", |c| &c.processing_seconds);
+        // The queue in front of a stage has the name of the stage.
+        write_histogram(&mut out, stages, "rook_lw_queue_wait_seconds", "Time an item waited in a queue between pipeline stages.", "queue", |c| &c.queue_wait_seconds);
+        write_histogram(&mut out, stages, "rook_lw_queue_latency_seconds", "Time from the capture of an item until it was sent to a queue between pipeline stages.", "queue", |c| &c.queue_latency_seconds);
+
+        let _ = writeln!(out, "# HELP rook_lw_queue_length Items waiting in a queue between pipeline stages.");
+        let _ = writeln!(out, "# TYPE rook_lw_queue_length gauge");
+        for queue in &registry.queues {
+            let _ = writeln!(out, "rook_lw_queue_length{{queue=\"{}\"}} {}", queue.name, (queue.length)());
+        }
+
+        let _ = writeln!(out, "# HELP rook_lw_queue_capacity Capacity of a bounded queue between pipeline stages.");
+        let _ = writeln!(out, "# TYPE rook_lw_queue_capacity gauge");
+        for queue in &registry.queues {
+            if let Some(capacity) = queue.capacity {
+                let _ = writeln!(out, "rook_lw_queue_capacity{{queue=\"{}\"}} {}", queue.name, capacity);
+            }
+        }
+
+        out
+    }
+}
+
+fn write_counter(
+    out: &mut String,
+    stages: &[(String, StageMetrics)],
+    name: &str,
+    help: &str,
+    counter: impl Fn(&StageCounters) -> &AtomicU64,
+) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} counter", name);
+    for (stage, metrics) in stages {
+        let _ = writeln!(out, "{}{{stage=\"{}\"}} {}", name, stage, counter(&metrics.counters).load(Ordering::Relaxed));
+    }
+}
+
+fn write_histogram(
+    out: &mut String,
+    stages: &[(String, StageMetrics)],
+    name: &str,
+    help: &str,
+    label: &str,
+    histogram: impl Fn(&StageCounters) -> &Mutex<Histogram>,
+) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} histogram", name);
+    for (stage, metrics) in stages {
+        let histogram = histogram(&metrics.counters).lock().unwrap_or_else(|e| e.into_inner());
+        if histogram.count == 0 {
+            continue;
+        }
+        for (bound, bucket_count) in SECONDS_BUCKETS.iter().zip(histogram.bucket_counts) {
+            let _ = writeln!(out, "{}_bucket{{{}=\"{}\",le=\"{}\"}} {}", name, label, stage, bound, bucket_count);
+        }
+        let _ = writeln!(out, "{}_bucket{{{}=\"{}\",le=\"+Inf\"}} {}", name, label, stage, histogram.count);
+        let _ = writeln!(out, "{}_sum{{{}=\"{}\"}} {}", name, label, stage, histogram.sum);
+        let _ = writeln!(out, "{}_count{{{}=\"{}\"}} {}", name, label, stage, histogram.count);
+    }
+}
+
+impl std::fmt::Debug for PipelineMetrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let registry = self.registry.lock().unwrap_or_else(|e| e.into_inner());
+        f.debug_struct("PipelineMetrics")
+            .field("stages", &registry.stages.iter().map(|(name, _)| name).collect::<Vec<_>>())
+            .field("queues", &registry.queues.iter().map(|queue| &queue.name).collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_reports_counters_histograms_and_queues() {
+        let metrics = PipelineMetrics::new();
+        let detector = metrics.stage("image_detector");
+        detector.record_in();
+        detector.record_in();
+        detector.record_out();
+        detector.record_error();
+        metrics.stage("near_duplicate_filter").record_filtered();
+        detector.observe_processing(Duration::from_millis(30));
+        detector.observe_processing(Duration::from_millis(300));
+        metrics.stage("image_detector").record_in();
+        metrics.stage("object_tracker").observe_queue_latency(Duration::from_millis(700));
+
+        let (sender, receiver) = crossbeam_channel::bounded::<u32>(64);
+        sender.send(1).unwrap();
+        metrics.register_queue("motion_detected", &receiver);
+
+        let text = metrics.render();
+        assert!(text.contains("rook_lw_stage_items_in_total{stage=\"image_detector\"} 3\n"));
+        assert!(text.contains("rook_lw_stage_items_out_total{stage=\"image_detector\"} 1\n"));
+        assert!(text.contains("rook_lw_stage_errors_total{stage=\"image_detector\"} 1\n"));
+        assert!(text.contains("rook_lw_stage_filtered_total{stage=\"near_duplicate_filter\"} 1\n"));
+        assert!(text.contains("rook_lw_stage_processing_seconds_bucket{stage=\"image_detector\",le=\"0.025\"} 0\n"));
+        assert!(text.contains("rook_lw_stage_processing_seconds_bucket{stage=\"image_detector\",le=\"0.05\"} 1\n"));
+        assert!(text.contains("rook_lw_stage_processing_seconds_bucket{stage=\"image_detector\",le=\"+Inf\"} 2\n"));
+        assert!(text.contains("rook_lw_stage_processing_seconds_count{stage=\"image_detector\"} 2\n"));
+        assert!(text.contains("rook_lw_queue_latency_seconds_bucket{queue=\"object_tracker\",le=\"1\"} 1\n"));
+        assert!(!text.contains("rook_lw_queue_latency_seconds_count{queue=\"image_detector\"}"));
+        assert!(text.contains("rook_lw_queue_length{queue=\"motion_detected\"} 1\n"));
+        assert!(text.contains("rook_lw_queue_capacity{queue=\"motion_detected\"} 64\n"));
+    }
+
+    #[derive(Clone)]
+    struct Frame(Option<Instant>);
+
+    impl Timed for Frame {
+        fn queued_at(&self) -> Option<Instant> {
+            self.0
+        }
+
+        fn set_queued_at(&mut self, queued_at: Instant) {
+            self.0 = Some(queued_at);
+        }
+
+        fn age(&self) -> Duration {
+            Duration::ZERO
+        }
+    }
+
+    struct Failing(u32);
+
+    impl ConsumerTask<Frame> for Failing {
+        fn consume(&mut self, _item: Frame) -> RookLWResult<()> {
+            if self.0 > 0 {
+                self.0 -= 1;
+                return Err(crate::RookLWError::Other("corrupt frame".to_string()));
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn queue_wait_is_recorded_once_per_queued_item() {
+        let metrics = PipelineMetrics::new();
+        let mut consumer = QueueWaitConsumer::new(Failing(1), metrics.stage("image_detector"));
+
+        let frame = Frame(Some(Instant::now() - Duration::from_millis(200)));
+        assert!(consumer.consume(frame.clone()).is_err());
+        consumer.consume(frame).unwrap();
+        // Recovered items were never stamped.
+        consumer.consume(Frame(None)).unwrap();
+
+        let text = metrics.render();
+        assert!(text.contains("rook_lw_queue_wait_seconds_bucket{queue=\"image_detector\",le=\"0.1\"} 0\n"));
+        assert!(text.contains("rook_lw_queue_wait_seconds_count{queue=\"image_detector\"} 1\n"));
+    }
+}
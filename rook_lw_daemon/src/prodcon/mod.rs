@@ -1,10 +1,24 @@
 mod consumer_task;
+mod durable_queue;
+mod live_settings;
+mod metrics;
+mod overflow;
 mod producer_callbacks;
 mod producer_task;
+mod shutdown_signal;
+mod supervisor;
+mod worker_pool;
 
 pub use consumer_task::*;
+pub use durable_queue::*;
+pub use live_settings::*;
+pub use metrics::*;
+pub use overflow::*;
 pub use producer_callbacks::*;
 pub use producer_task::*;
+pub use shutdown_signal::*;
+pub use supervisor::*;
+pub use worker_pool::*;
 
 use crate::RookLWResult;
 
@@ -12,4 +26,14 @@ pub trait OnProduceCallback<T> : Fn(&T) -> RookLWResult<()> + Send + 'static {}
 
 impl<T, F> OnProduceCallback<T> for F where F: Fn(&T) -> RookLWResult<()> + Send + 'static {}
 
-pub trait ProducerConsumerTask<T: Send + Clone + 'static>: ProducerTask<T> + ConsumerTask<T> {}
\ No newline at end of file
+pub trait ProducerConsumerTask<T: Send + Clone + 'static>: ProducerTask<T> + ConsumerTask<T> {}
+
+impl<T, X> ProducerConsumerTask<T> for X
+    where T: Send + Clone + 'static, X: ProducerTask<T> + ConsumerTask<T> {}
+
+/// Consumes items of one type and produces items of another, like the storer
+/// that produces a `StorageEvent` for every image it stores.
+pub trait ConvertingTask<I: Send + 'static, O: Send + Clone + 'static>: ConsumerTask<I> + ProducerTask<O> {}
+
+impl<I, O, X> ConvertingTask<I, O> for X
+    where I: Send + 'static, O: Send + Clone + 'static, X: ConsumerTask<I> + ProducerTask<O> {}
\ No newline at end of file
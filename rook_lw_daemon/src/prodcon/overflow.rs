@@ -0,0 +1,251 @@
+use crate::RookLWResult;
+use super::{QueueJournal, StageMetrics, Timed};
+
+use crossbeam_channel::{Receiver, Sender, TrySendError};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use std::sync::Arc;
+use std::time::Instant;
+
+/// What a producer does when the queue it sends to is full.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    /// Wait for room, holding up the producer.
+    #[default]
+    Block,
+
+    /// Drop the item being sent.
+    DropNewest,
+
+    /// Drop the item that has been queued longest.
+    DropOldest,
+
+    /// Drop the item with the lowest score, queued or being sent.
+    KeepBest,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OverflowSettings {
+    pub policy: OverflowPolicy,
+
+    /// Items with a sequence index below this are never dropped. When the
+    /// queue is full of them, the producer waits for room instead.
+    pub priority_count: u32,
+}
+
+/// Items a queue can choose between when it sheds load.
+pub trait Sheddable {
+    /// Higher is more worth keeping.
+    fn shed_score(&self) -> f32;
+
+    /// Position of the item in its burst, such as the capture index in an event.
+    fn sequence_index(&self) -> u32;
+}
+
+/// Sends into a bounded queue, applying an [`OverflowPolicy`] when it is full.
+///
+/// Shedding a queued item takes the queued items out and sends back the ones
+/// that are kept, in order. Items from another producer sending to the same
+/// queue at that moment may end up between them.
+///
+/// With a journal, every item is recorded before it is queued and forgotten
+/// again when it is dropped.
+///
+/// Items are stamped with the time they are sent, and their age is recorded
+/// as the latency of the queue.
+pub struct OverflowSender<T> {
+    name: String,
+    sender: Sender<T>,
+    receiver: Receiver<T>,
+    settings: OverflowSettings,
+    metrics: StageMetrics,
+    journal: Option<Arc<dyn QueueJournal<T>>>,
+}
+
+impl<T> Clone for OverflowSender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            sender: self.sender.clone(),
+            receiver: self.receiver.clone(),
+            settings: self.settings.clone(),
+            metrics: self.metrics.clone(),
+            journal: self.journal.clone(),
+        }
+    }
+}
+
+impl<T: Sheddable + Timed + Send> OverflowSender<T> {
+    /// `receiver` must read the queue `sender` sends to; dropped items are
+    /// counted in `metrics`.
+    pub fn new(name: &str, sender: Sender<T>, receiver: Receiver<T>, settings: OverflowSettings, metrics: StageMetrics) -> Self {
+        Self {
+            name: name.to_string(),
+            sender,
+            receiver,
+            settings,
+            metrics,
+            journal: None,
+        }
+    }
+
+    pub fn set_journal(&mut self, journal: Arc<dyn QueueJournal<T>>) -> &mut Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    pub fn send(&self, mut item: T) -> RookLWResult<()> {
+        item.set_queued_at(Instant::now());
+        self.metrics.observe_queue_latency(item.age());
+
+        if let Some(journal) = &self.journal {
+            journal.record(&item)?;
+        }
+
+        if self.settings.policy == OverflowPolicy::Block {
+            return Ok(self.sender.send(item)?);
+        }
+
+        let item = match self.sender.try_send(item) {
+            Ok(()) => return Ok(()),
+            // Sending blocking reports the disconnect like connect() does.
+            Err(TrySendError::Disconnected(item)) => return Ok(self.sender.send(item)?),
+            Err(TrySendError::Full(item)) => item,
+        };
+
+        if self.settings.policy == OverflowPolicy::DropNewest && !self.is_priority(&item) {
+            self.record_drop(&item);
+            return Ok(());
+        }
+
+        let mut queued: Vec<T> = self.receiver.try_iter().collect();
+        queued.push(item);
+
+        if let Some(victim) = self.choose_victim(&queued) {
+            let dropped = queued.remove(victim);
+            self.record_drop(&dropped);
+        }
+
+        // Without a victim this waits until there is room for the new item.
+        for item in queued {
+            self.sender.send(item)?;
+        }
+        Ok(())
+    }
+
+    fn is_priority(&self, item: &T) -> bool {
+        item.sequence_index() < self.settings.priority_count
+    }
+
+    /// Index of the item to drop; the new item is last. None when all are priority items.
+    fn choose_victim(&self, items: &[T]) -> Option<usize> {
+        let mut candidates = items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| !self.is_priority(item));
+
+        match self.settings.policy {
+            OverflowPolicy::Block => None,
+            OverflowPolicy::DropNewest => candidates.next_back().map(|(index, _)| index),
+            OverflowPolicy::DropOldest => candidates.next().map(|(index, _)| index),
+            // The newest of the lowest scoring, so a burst of equal scores keeps its first frames.
+            OverflowPolicy::KeepBest => candidates
+                .min_by(|(a_index, a), (b_index, b)| {
+                    a.shed_score().total_cmp(&b.shed_score()).then(b_index.cmp(a_index))
+                })
+                .map(|(index, _)| index),
+        }
+    }
+
+    fn record_drop(&self, item: &T) {
+        self.metrics.record_drop();
+        if let Some(journal) = &self.journal
+            && let Err(e) = journal.forget(item)
+        {
+            warn!(queue = %self.name, error = %e, "Failed to remove dropped item from durable queue");
+        }
+        info!(
+            queue = %self.name,
+            policy = ?self.settings.policy,
+            sequence_index = item.sequence_index(),
+            score = item.shed_score(),
+            "Queue full, item dropped"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Frame {
+        score: f32,
+        index: u32,
+    }
+
+    impl Timed for Frame {
+        fn queued_at(&self) -> Option<Instant> {
+            None
+        }
+
+        fn set_queued_at(&mut self, _queued_at: Instant) {}
+
+        fn age(&self) -> std::time::Duration {
+            std::time::Duration::ZERO
+        }
+    }
+
+    impl Sheddable for Frame {
+        fn shed_score(&self) -> f32 {
+            self.score
+        }
+
+        fn sequence_index(&self) -> u32 {
+            self.index
+        }
+    }
+
+    /// Send frames with these (score, index) pairs into a queue of 3 and return what is queued.
+    fn send_all(policy: OverflowPolicy, priority_count: u32, frames: &[(f32, u32)]) -> (Vec<(f32, u32)>, StageMetrics) {
+        let (sender, receiver) = crossbeam_channel::bounded(3);
+        let metrics = StageMetrics::default();
+        let overflow = OverflowSender::new(
+            "test",
+            sender,
+            receiver.clone(),
+            OverflowSettings { policy, priority_count },
+            metrics.clone(),
+        );
+        for (score, index) in frames {
+            overflow.send(Frame { score: *score, index: *index }).unwrap();
+        }
+        let queued = receiver.try_iter().map(|frame| (frame.score, frame.index)).collect();
+        (queued, metrics)
+    }
+
+    #[test]
+    fn overflow_policies_choose_what_to_drop() {
+        let frames = [(0.5, 0), (0.9, 1), (0.2, 2), (0.7, 3)];
+
+        let (queued, metrics) = send_all(OverflowPolicy::DropNewest, 0, &frames);
+        assert_eq!(queued, vec![(0.5, 0), (0.9, 1), (0.2, 2)]);
+        assert_eq!(metrics.dropped_count(), 1);
+
+        let (queued, _) = send_all(OverflowPolicy::DropOldest, 0, &frames);
+        assert_eq!(queued, vec![(0.9, 1), (0.2, 2), (0.7, 3)]);
+
+        let (queued, _) = send_all(OverflowPolicy::KeepBest, 0, &frames);
+        assert_eq!(queued, vec![(0.5, 0), (0.9, 1), (0.7, 3)]);
+
+        // The first frame of an event is kept even though it is the oldest.
+        let (queued, _) = send_all(OverflowPolicy::DropOldest, 1, &frames);
+        assert_eq!(queued, vec![(0.5, 0), (0.2, 2), (0.7, 3)]);
+
+        // A priority frame pushes out a queued one instead of being dropped.
+        let (queued, _) = send_all(OverflowPolicy::DropNewest, 1, &[(0.5, 1), (0.9, 2), (0.2, 3), (0.7, 0)]);
+        assert_eq!(queued, vec![(0.5, 1), (0.9, 2), (0.7, 0)]);
+    }
+}
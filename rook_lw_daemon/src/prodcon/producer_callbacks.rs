@@ -1,14 +1,16 @@
 use crate::RookLWResult;
-use super::OnProduceCallback;
+use super::{OnProduceCallback, StageMetrics};
 
 pub struct ProducerCallbacks<T: Send + 'static> {
     callbacks: Vec<Box<dyn OnProduceCallback<T>>>,
+    metrics: Option<StageMetrics>,
 }
 
 impl<T: Send + 'static> ProducerCallbacks<T> {
     pub fn new() -> Self {
         Self {
             callbacks: Vec::new(),
+            metrics: None,
         }
     }
 
@@ -18,10 +20,19 @@ impl<T: Send + 'static> ProducerCallbacks<T> {
         self.callbacks.push(Box::new(callback));
     }
 
+    /// Count produced items in `metrics`.
+    pub fn set_metrics(&mut self, metrics: StageMetrics) -> &mut Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
     pub fn produce(&self, item: &T) -> RookLWResult<()> {
         for callback in &self.callbacks {
             callback(item)?;
         }
+        if let Some(metrics) = &self.metrics {
+            metrics.record_out();
+        }
         Ok(())
     }
 }
\ No newline at end of file
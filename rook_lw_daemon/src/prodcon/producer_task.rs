@@ -1,7 +1,7 @@
 
 use crate::RookLWResult;
 use crate::prodcon::OnProduceCallback;
-use super::ProducerCallbacks;
+use super::{OverflowSender, ProducerCallbacks, Sheddable, StageMetrics, Timed};
 
 use crossbeam_channel::Sender;
 
@@ -10,18 +10,42 @@ pub trait ProducerTask<T: Send + Clone + 'static>: Send {
     fn get_producer_callbacks(&mut self) -> &mut ProducerCallbacks<T>;
 
     fn on_produce<F>(&mut self, callback: F)
-        where F: OnProduceCallback<T>
+        where F: OnProduceCallback<T>, Self: Sized
     {
         self.get_producer_callbacks().on_produce(callback);
     }
 
     fn connect(&mut self, sender: Sender<T>) {
-        self.on_produce(move |item| {
+        self.get_producer_callbacks().on_produce(move |item| {
             Ok(sender.send(item.clone())?)
         });
     }
 
+    /// Like `connect`, but sheds load by the sender's overflow policy when its queue is full.
+    fn connect_overflow(&mut self, sender: OverflowSender<T>)
+        where T: Sheddable + Timed
+    {
+        self.get_producer_callbacks().on_produce(move |item| {
+            sender.send(item.clone())
+        });
+    }
+
+    fn set_metrics(&mut self, metrics: StageMetrics) {
+        self.get_producer_callbacks().set_metrics(metrics);
+    }
+
     fn produce(&mut self, item: T) -> RookLWResult<()> {
         self.get_producer_callbacks().produce(&item)
     }
-}
\ No newline at end of file
+}
+
+/// Lets boxed tasks be connected like the task they contain.
+impl<T: Send + Clone + 'static, P: ProducerTask<T> + ?Sized> ProducerTask<T> for Box<P> {
+    fn get_producer_callbacks(&mut self) -> &mut ProducerCallbacks<T> {
+        (**self).get_producer_callbacks()
+    }
+
+    fn set_metrics(&mut self, metrics: StageMetrics) {
+        (**self).set_metrics(metrics)
+    }
+}
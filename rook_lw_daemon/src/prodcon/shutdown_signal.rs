@@ -0,0 +1,47 @@
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+/// Shared flag that asks producers to stop.
+///
+/// Producers check it between units of work and return when it is set. When a
+/// producer returns, its senders are dropped and the consumers behind it drain
+/// their queues and exit in turn.
+#[derive(Clone, Default)]
+pub struct ShutdownSignal {
+    inner: Arc<(Mutex<bool>, Condvar)>,
+}
+
+impl ShutdownSignal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn request(&self) {
+        let (requested, condvar) = &*self.inner;
+        *requested.lock().unwrap_or_else(|e| e.into_inner()) = true;
+        condvar.notify_all();
+    }
+
+    pub fn is_requested(&self) -> bool {
+        *self.inner.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Sleep for `duration`, waking early when shutdown is requested.
+    /// Returns true when shutdown was requested.
+    pub fn sleep(&self, duration: Duration) -> bool {
+        let (requested, condvar) = &*self.inner;
+        let guard = requested.lock().unwrap_or_else(|e| e.into_inner());
+        let (guard, _) = condvar
+            .wait_timeout_while(guard, duration, |requested| !*requested)
+            .unwrap_or_else(|e| e.into_inner());
+        *guard
+    }
+}
+
+impl std::fmt::Debug for ShutdownSignal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShutdownSignal")
+            .field("requested", &self.is_requested())
+            .finish()
+    }
+}
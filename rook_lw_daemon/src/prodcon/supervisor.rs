@@ -0,0 +1,430 @@
+use crate::RookLWResult;
+use crate::error::RookLWError;
+use super::{ConsumerTask, PipelineMetrics, ShutdownSignal, StageMetrics};
+
+use rook_lw_models::process::{StageHealth, StageStatus};
+
+use crossbeam_channel::Receiver;
+use tracing::{error, info, warn};
+
+use std::sync::{Arc, Mutex};
+use std::thread::{JoinHandle, spawn};
+use std::time::{Duration, Instant};
+
+// A producer that ran at least this long before failing starts counting restarts afresh.
+const STABLE_RUN: Duration = Duration::from_secs(60);
+
+// Pause before a producer with the skip policy is run again.
+const SKIP_RESTART_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RestartPolicy {
+    /// Restarts in a row, without a successful item in between, before escalating to shutdown.
+    pub max_restarts: u32,
+
+    /// Backoff before the first restart, doubled for each following one.
+    pub initial_backoff: Duration,
+
+    pub max_backoff: Duration,
+}
+
+impl RestartPolicy {
+    pub(crate) fn backoff(&self, restart: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(restart))
+            .min(self.max_backoff)
+    }
+}
+
+/// What a supervised stage does when processing fails.
+///
+/// Producers have no items to skip or retry; for them `Skip` runs the
+/// producer again after a short pause and `Retry(n)` does so at most `n` times
+/// in a row.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErrorPolicy {
+    /// Log the error and continue with the next item.
+    Skip,
+
+    /// Try the item up to this many more times, then skip it.
+    Retry(u32),
+
+    /// Reset the stage after a backoff and continue with the next item.
+    Restart(RestartPolicy),
+
+    /// Stop the whole pipeline.
+    Shutdown,
+}
+
+/// Health of every supervised stage, shared with whoever wants to query it.
+#[derive(Clone, Default)]
+pub struct HealthRegistry {
+    stages: Arc<Mutex<Vec<StageStatus>>>,
+}
+
+impl HealthRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Status of all stages, in the order they were started.
+    pub fn snapshot(&self) -> Vec<StageStatus> {
+        self.stages.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn get(&self, name: &str) -> Option<StageStatus> {
+        self.snapshot().into_iter().find(|stage| stage.name == name)
+    }
+
+    /// True when no stage has failed or is restarting.
+    pub fn is_healthy(&self) -> bool {
+        self.snapshot()
+            .iter()
+            .all(|stage| !matches!(stage.health, StageHealth::Failed | StageHealth::Restarting))
+    }
+
+    fn register(&self, name: &str) {
+        let mut stages = self.stages.lock().unwrap_or_else(|e| e.into_inner());
+        stages.retain(|stage| stage.name != name);
+        stages.push(StageStatus {
+            name: name.to_string(),
+            updated: Some(chrono::Local::now().into()),
+            ..Default::default()
+        });
+    }
+
+    fn update(&self, name: &str, update: impl FnOnce(&mut StageStatus)) {
+        let mut stages = self.stages.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(stage) = stages.iter_mut().find(|stage| stage.name == name) {
+            update(stage);
+            stage.updated = Some(chrono::Local::now().into());
+        }
+    }
+
+    fn set_health(&self, name: &str, health: StageHealth) {
+        self.update(name, |stage| stage.health = health);
+    }
+
+    fn record_success(&self, name: &str) {
+        self.update(name, |stage| {
+            stage.processed_count += 1;
+            stage.health = StageHealth::Running;
+        });
+    }
+
+    fn record_error(&self, name: &str, error: &RookLWError) {
+        self.update(name, |stage| {
+            stage.error_count += 1;
+            stage.last_error = Some(error.to_string());
+            stage.health = StageHealth::Degraded;
+        });
+    }
+}
+
+/// Stops the pipeline because of a failed stage, as its supervisor would.
+/// Given to tasks that apply the error policy to their items themselves.
+#[derive(Clone)]
+pub struct Escalation {
+    name: String,
+    health: HealthRegistry,
+    shutdown: ShutdownSignal,
+}
+
+impl Escalation {
+    pub fn new(name: &str, health: HealthRegistry, shutdown: ShutdownSignal) -> Self {
+        Self {
+            name: name.to_string(),
+            health,
+            shutdown,
+        }
+    }
+
+    /// Mark the stage failed and request a shutdown.
+    pub fn escalate(&self, error: &RookLWError) {
+        error!(stage = %self.name, error = %error, "Stage failed, shutting down");
+        self.health.record_error(&self.name, error);
+        self.health.set_health(&self.name, StageHealth::Failed);
+        self.shutdown.request();
+    }
+}
+
+impl std::fmt::Debug for HealthRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.snapshot()).finish()
+    }
+}
+
+/// Runs pipeline stages on their own threads and applies an [`ErrorPolicy`]
+/// when they fail, instead of letting one error end the stage.
+pub struct Supervisor {
+    health: HealthRegistry,
+    shutdown: ShutdownSignal,
+    metrics: PipelineMetrics,
+}
+
+impl Supervisor {
+    pub fn new(health: HealthRegistry, shutdown: ShutdownSignal) -> Self {
+        Self {
+            health,
+            shutdown,
+            metrics: PipelineMetrics::new(),
+        }
+    }
+
+    /// Record items, errors and processing time of the supervised stages in `metrics`.
+    pub fn set_metrics(&mut self, metrics: PipelineMetrics) -> &mut Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Consume items from `receiver` until it is closed.
+    pub fn spawn_consumer<T, C>(
+        &self,
+        name: &str,
+        mut task: C,
+        receiver: Receiver<T>,
+        policy: ErrorPolicy,
+    ) -> JoinHandle<RookLWResult<()>>
+    where
+        T: Send + Clone + 'static,
+        C: ConsumerTask<T> + 'static,
+    {
+        let name = name.to_string();
+        let health = self.health.clone();
+        let shutdown = self.shutdown.clone();
+        let metrics = self.metrics.stage(&name);
+        health.register(&name);
+
+        spawn(move || {
+            info!(stage = %name, policy = ?policy, "Starting supervised consumer");
+            health.set_health(&name, StageHealth::Running);
+
+            let result = supervise_consumer(&name, &mut task, &receiver, &policy, &health, &metrics, &shutdown);
+            task.finish();
+
+            if let Err(e) = &result {
+                // Keep draining, so the stages before this one are not stuck on a full queue.
+                let dropped_count = receiver.iter().count();
+                error!(stage = %name, error = %e, dropped_count, "Supervised consumer failed");
+                health.set_health(&name, StageHealth::Failed);
+            } else {
+                info!(stage = %name, "Supervised consumer stopped");
+                // A task that escalated an error itself has marked the stage failed.
+                health.update(&name, |stage| if stage.health != StageHealth::Failed {
+                    stage.health = StageHealth::Stopped;
+                });
+            }
+            result
+        })
+    }
+
+    /// Run a producer until it returns normally, running it again according
+    /// to `policy` when it fails.
+    pub fn spawn_producer<F>(&self, name: &str, mut run: F, policy: ErrorPolicy) -> JoinHandle<RookLWResult<()>>
+    where
+        F: FnMut(&ShutdownSignal) -> RookLWResult<()> + Send + 'static,
+    {
+        let name = name.to_string();
+        let health = self.health.clone();
+        let shutdown = self.shutdown.clone();
+        let metrics = self.metrics.stage(&name);
+        health.register(&name);
+
+        spawn(move || {
+            info!(stage = %name, policy = ?policy, "Starting supervised producer");
+            let mut restarts_in_row = 0;
+
+            loop {
+                health.set_health(&name, StageHealth::Running);
+                let started = Instant::now();
+
+                let e = match run(&shutdown) {
+                    Ok(()) => {
+                        info!(stage = %name, "Supervised producer stopped");
+                        health.set_health(&name, StageHealth::Stopped);
+                        return Ok(());
+                    },
+                    Err(e) => e,
+                };
+                health.record_error(&name, &e);
+                metrics.record_error();
+
+                if started.elapsed() >= STABLE_RUN {
+                    restarts_in_row = 0;
+                }
+
+                let delay = match &policy {
+                    _ if shutdown.is_requested() => None,
+                    ErrorPolicy::Skip => Some(SKIP_RESTART_DELAY),
+                    ErrorPolicy::Retry(retries) if restarts_in_row < *retries => Some(Duration::ZERO),
+                    ErrorPolicy::Restart(restart) if restarts_in_row < restart.max_restarts => {
+                        Some(restart.backoff(restarts_in_row))
+                    },
+                    _ => None,
+                };
+
+                let Some(delay) = delay else {
+                    error!(stage = %name, error = %e, "Supervised producer failed, shutting down");
+                    health.set_health(&name, StageHealth::Failed);
+                    shutdown.request();
+                    return Err(e);
+                };
+
+                warn!(stage = %name, error = %e, delay_ms = delay.as_millis(), "Supervised producer failed, restarting");
+                health.set_health(&name, StageHealth::Restarting);
+                if shutdown.sleep(delay) {
+                    health.set_health(&name, StageHealth::Failed);
+                    return Err(e);
+                }
+                restarts_in_row += 1;
+                health.update(&name, |stage| stage.restart_count += 1);
+            }
+        })
+    }
+}
+
+fn supervise_consumer<T: Send + Clone + 'static, C: ConsumerTask<T>>(
+    name: &str,
+    task: &mut C,
+    receiver: &Receiver<T>,
+    policy: &ErrorPolicy,
+    health: &HealthRegistry,
+    metrics: &StageMetrics,
+    shutdown: &ShutdownSignal,
+) -> RookLWResult<()> {
+    // A task that applies the policy to its items itself only returns the
+    // errors it escalates, and times its items itself.
+    let escalation = Escalation::new(name, health.clone(), shutdown.clone());
+    let per_item = !task.take_error_policy(policy, &escalation);
+    let policy = if per_item { policy } else { &ErrorPolicy::Shutdown };
+    let mut restarts_in_row = 0;
+
+    for item in receiver.iter() {
+        metrics.record_in();
+        let mut attempt = 0;
+        loop {
+            let started = Instant::now();
+            let result = task.consume(item.clone());
+            if per_item {
+                metrics.observe_processing(started.elapsed());
+            }
+
+            let e = match result {
+                Ok(()) => {
+                    health.record_success(name);
+                    restarts_in_row = 0;
+                    break;
+                },
+                Err(e) => e,
+            };
+            health.record_error(name, &e);
+            if per_item {
+                metrics.record_error();
+            }
+
+            match policy {
+                ErrorPolicy::Skip => {
+                    warn!(stage = %name, error = %e, "Item failed, skipping");
+                    break;
+                },
+                ErrorPolicy::Retry(retries) if attempt < *retries => {
+                    attempt += 1;
+                    warn!(stage = %name, error = %e, attempt, "Item failed, retrying");
+                },
+                ErrorPolicy::Retry(_) => {
+                    warn!(stage = %name, error = %e, attempt, "Item failed after retries, skipping");
+                    break;
+                },
+                ErrorPolicy::Restart(restart) if restarts_in_row < restart.max_restarts => {
+                    let backoff = restart.backoff(restarts_in_row);
+                    warn!(stage = %name, error = %e, backoff_ms = backoff.as_millis(), "Item failed, restarting stage");
+                    health.set_health(name, StageHealth::Restarting);
+                    // Keep the backoff even during shutdown; the queue still has to be drained.
+                    std::thread::sleep(backoff);
+                    task.reset()?;
+                    restarts_in_row += 1;
+                    health.update(name, |stage| {
+                        stage.restart_count += 1;
+                        stage.health = StageHealth::Running;
+                    });
+                    break;
+                },
+                ErrorPolicy::Restart(_) | ErrorPolicy::Shutdown => {
+                    shutdown.request();
+                    return Err(e);
+                },
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FailingTask {
+        failures_left: u32,
+        consumed: Vec<u32>,
+        resets: u32,
+    }
+
+    impl ConsumerTask<u32> for FailingTask {
+        fn consume(&mut self, item: u32) -> RookLWResult<()> {
+            if item == 2 && self.failures_left > 0 {
+                self.failures_left -= 1;
+                return Err(RookLWError::Other("corrupt frame".to_string()));
+            }
+            self.consumed.push(item);
+            Ok(())
+        }
+
+        fn reset(&mut self) -> RookLWResult<()> {
+            self.resets += 1;
+            Ok(())
+        }
+    }
+
+    fn run(policy: ErrorPolicy, failures: u32) -> (RookLWResult<()>, FailingTask, HealthRegistry, ShutdownSignal) {
+        let health = HealthRegistry::new();
+        let shutdown = ShutdownSignal::new();
+        health.register("test");
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        for item in 1..=3 {
+            sender.send(item).unwrap();
+        }
+        drop(sender);
+
+        let mut task = FailingTask { failures_left: failures, consumed: Vec::new(), resets: 0 };
+        let result = supervise_consumer("test", &mut task, &receiver, &policy, &health, &StageMetrics::default(), &shutdown);
+        (result, task, health, shutdown)
+    }
+
+    #[test]
+    fn supervised_consumer_applies_policies() {
+        let (result, task, health, _) = run(ErrorPolicy::Skip, 1);
+        assert!(result.is_ok());
+        assert_eq!(task.consumed, vec![1, 3]);
+        assert_eq!(health.get("test").unwrap().error_count, 1);
+
+        let (result, task, _, _) = run(ErrorPolicy::Retry(2), 2);
+        assert!(result.is_ok());
+        assert_eq!(task.consumed, vec![1, 2, 3]);
+
+        let restart = RestartPolicy {
+            max_restarts: 1,
+            initial_backoff: Duration::ZERO,
+            max_backoff: Duration::ZERO,
+        };
+        let (result, task, health, _) = run(ErrorPolicy::Restart(restart), 1);
+        assert!(result.is_ok());
+        assert_eq!((task.consumed, task.resets), (vec![1, 3], 1));
+        assert_eq!(health.get("test").unwrap().restart_count, 1);
+
+        let (result, task, _, shutdown) = run(ErrorPolicy::Shutdown, 1);
+        assert!(result.is_err());
+        assert_eq!(task.consumed, vec![1]);
+        assert!(shutdown.is_requested());
+    }
+}
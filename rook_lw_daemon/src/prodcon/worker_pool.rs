@@ -0,0 +1,387 @@
+use crate::{RookLWError, RookLWResult};
+use super::{ConsumerTask, ErrorPolicy, Escalation, ProducerCallbacks, ProducerConsumerTask, ProducerTask, StageMetrics};
+
+use crossbeam_channel::{Receiver, Sender};
+use tracing::{error, info, warn};
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::thread::{JoinHandle, spawn};
+use std::time::Instant;
+
+struct WorkerResult<T> {
+    sequence: u64,
+    items: Vec<T>,
+}
+
+struct Running<T> {
+    work_sender: Sender<(u64, T)>,
+    workers: Vec<JoinHandle<()>>,
+    collector: JoinHandle<()>,
+    next_sequence: u64,
+}
+
+/// Runs several instances of a task in parallel, as one stage.
+///
+/// Items are handed to whichever worker is free, and what the workers produce
+/// is passed on in the order the items came in, so later stages see the frames
+/// of an event in order. Each worker applies the error policy of the stage
+/// to its items; a restart resets only the failed worker's task. When the
+/// policy calls for a shutdown the worker stops the pipeline right away, and
+/// the pool fails on its next item.
+///
+/// The workers start with the first item, so connect the pool before that.
+pub struct WorkerPool<T: Send + Clone + 'static> {
+    name: String,
+    tasks: Vec<Box<dyn ProducerConsumerTask<T>>>,
+    producer_callbacks: ProducerCallbacks<T>,
+    metrics: StageMetrics,
+    error_policy: ErrorPolicy,
+    escalation: Option<Escalation>,
+    failure: Arc<Mutex<Option<RookLWError>>>,
+    running: Option<Running<T>>,
+}
+
+impl<T: Send + Clone + 'static> ProducerTask<T> for WorkerPool<T> {
+    fn get_producer_callbacks(&mut self) -> &mut ProducerCallbacks<T> {
+        &mut self.producer_callbacks
+    }
+
+    fn set_metrics(&mut self, metrics: StageMetrics) {
+        self.metrics = metrics.clone();
+        self.producer_callbacks.set_metrics(metrics);
+    }
+}
+
+impl<T: Send + Clone + 'static> ConsumerTask<T> for WorkerPool<T> {
+    fn consume(&mut self, item: T) -> RookLWResult<()> {
+        if let Some(e) = self.failure.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            return Err(e);
+        }
+        if self.running.is_none() {
+            self.running = Some(self.start_workers());
+        }
+        let Some(running) = &mut self.running else {
+            unreachable!("workers were just started");
+        };
+
+        let sequence = running.next_sequence;
+        running.next_sequence += 1;
+        // Waits while all workers are busy.
+        running.work_sender
+            .send((sequence, item))
+            .map_err(|_| RookLWError::Concurrency(format!("Workers of {} stopped", self.name)))
+    }
+
+    fn take_error_policy(&mut self, policy: &ErrorPolicy, escalation: &Escalation) -> bool {
+        self.error_policy = policy.clone();
+        self.escalation = Some(escalation.clone());
+        true
+    }
+
+    fn finish(&mut self) {
+        let Some(running) = self.running.take() else {
+            for task in &mut self.tasks {
+                task.finish();
+            }
+            return;
+        };
+
+        drop(running.work_sender);
+        for worker in running.workers {
+            if worker.join().is_err() {
+                error!(stage = %self.name, "Pool worker panicked");
+            }
+        }
+        if running.collector.join().is_err() {
+            error!(stage = %self.name, "Pool collector panicked");
+        }
+    }
+}
+
+impl<T: Send + Clone + 'static> WorkerPool<T> {
+    pub fn new(name: &str, tasks: Vec<Box<dyn ProducerConsumerTask<T>>>) -> Self {
+        Self {
+            name: name.to_string(),
+            tasks,
+            producer_callbacks: ProducerCallbacks::new(),
+            metrics: StageMetrics::default(),
+            error_policy: ErrorPolicy::Skip,
+            escalation: None,
+            failure: Arc::default(),
+            running: None,
+        }
+    }
+
+    fn start_workers(&mut self) -> Running<T> {
+        info!(stage = %self.name, worker_count = self.tasks.len(), "Starting pool workers");
+
+        // At most one item waits per worker, the rest stay in the stage's queue.
+        let (work_sender, work_receiver) = crossbeam_channel::bounded::<(u64, T)>(self.tasks.len());
+        let (result_sender, result_receiver) = crossbeam_channel::unbounded::<WorkerResult<T>>();
+
+        let workers = self.tasks
+            .drain(..)
+            .enumerate()
+            .map(|(index, task)| {
+                let work_receiver = work_receiver.clone();
+                let result_sender = result_sender.clone();
+                let worker = Worker {
+                    name: format!("{}[{}]", self.name, index),
+                    error_policy: self.error_policy.clone(),
+                    escalation: self.escalation.clone(),
+                    metrics: self.metrics.clone(),
+                    failure: self.failure.clone(),
+                };
+                spawn(move || worker.run(task, work_receiver, result_sender))
+            })
+            .collect();
+
+        let name = self.name.clone();
+        let producer_callbacks = std::mem::replace(&mut self.producer_callbacks, ProducerCallbacks::new());
+        let collector = spawn(move || run_collector(&name, result_receiver, producer_callbacks));
+
+        Running {
+            work_sender,
+            workers,
+            collector,
+            next_sequence: 0,
+        }
+    }
+}
+
+struct Worker {
+    name: String,
+    error_policy: ErrorPolicy,
+    escalation: Option<Escalation>,
+    metrics: StageMetrics,
+    failure: Arc<Mutex<Option<RookLWError>>>,
+}
+
+impl Worker {
+    fn run<T: Send + Clone + 'static>(
+        &self,
+        mut task: Box<dyn ProducerConsumerTask<T>>,
+        work_receiver: Receiver<(u64, T)>,
+        result_sender: Sender<WorkerResult<T>>,
+    ) {
+        let produced: Arc<Mutex<Vec<T>>> = Arc::default();
+        let buffer = produced.clone();
+        task.get_producer_callbacks().on_produce(move |item: &T| {
+            buffer.lock().unwrap_or_else(|e| e.into_inner()).push(item.clone());
+            Ok(())
+        });
+
+        let mut restarts_in_row = 0;
+        for (sequence, item) in work_receiver.iter() {
+            let result = self.consume(task.as_mut(), item, &mut restarts_in_row);
+            let items = std::mem::take(&mut *produced.lock().unwrap_or_else(|e| e.into_inner()));
+            // Every sequence number is reported, also without items, so the collector never waits on a gap.
+            if result_sender.send(WorkerResult { sequence, items }).is_err() {
+                break;
+            }
+            if let Err(e) = result {
+                error!(worker = %self.name, error = %e, "Pooled item failed, stopping worker");
+                if let Some(escalation) = &self.escalation {
+                    escalation.escalate(&e);
+                }
+                *self.failure.lock().unwrap_or_else(|e| e.into_inner()) = Some(e);
+                break;
+            }
+        }
+        task.finish();
+    }
+
+    /// Consume an item under the error policy, as a supervised stage would.
+    /// Returns the error when the policy escalates it.
+    fn consume<T: Send + Clone + 'static>(
+        &self,
+        task: &mut dyn ProducerConsumerTask<T>,
+        item: T,
+        restarts_in_row: &mut u32,
+    ) -> RookLWResult<()> {
+        let name = &self.name;
+        let mut attempt = 0;
+        loop {
+            let started = Instant::now();
+            let result = task.consume(item.clone());
+            self.metrics.observe_processing(started.elapsed());
+
+            let e = match result {
+                Ok(()) => {
+                    *restarts_in_row = 0;
+                    return Ok(());
+                },
+                Err(e) => e,
+            };
+            self.metrics.record_error();
+
+            match &self.error_policy {
+                ErrorPolicy::Skip => {
+                    warn!(worker = %name, error = %e, "Pooled item failed, skipping");
+                    return Ok(());
+                },
+                ErrorPolicy::Retry(retries) if attempt < *retries => {
+                    attempt += 1;
+                    warn!(worker = %name, error = %e, attempt, "Pooled item failed, retrying");
+                },
+                ErrorPolicy::Retry(_) => {
+                    warn!(worker = %name, error = %e, attempt, "Pooled item failed after retries, skipping");
+                    return Ok(());
+                },
+                ErrorPolicy::Restart(restart) if *restarts_in_row < restart.max_restarts => {
+                    let backoff = restart.backoff(*restarts_in_row);
+                    warn!(worker = %name, error = %e, backoff_ms = backoff.as_millis(), "Pooled item failed, restarting worker");
+                    std::thread::sleep(backoff);
+                    *restarts_in_row += 1;
+                    return task.reset();
+                },
+                ErrorPolicy::Restart(_) | ErrorPolicy::Shutdown => return Err(e),
+            }
+        }
+    }
+}
+
+fn run_collector<T: Send + Clone + 'static>(
+    name: &str,
+    result_receiver: Receiver<WorkerResult<T>>,
+    producer_callbacks: ProducerCallbacks<T>,
+) {
+    let mut pending: BTreeMap<u64, Vec<T>> = BTreeMap::new();
+    let mut next_sequence = 0;
+
+    let produce = |items: Vec<T>| {
+        for item in items {
+            if let Err(e) = producer_callbacks.produce(&item) {
+                error!(stage = %name, error = %e, "Failed to pass on pooled result");
+            }
+        }
+    };
+
+    for result in result_receiver.iter() {
+        pending.insert(result.sequence, result.items);
+        while let Some(items) = pending.remove(&next_sequence) {
+            produce(items);
+            next_sequence += 1;
+        }
+    }
+
+    // Only a panicked worker leaves gaps; pass on what is left in order.
+    for (_, items) in pending {
+        produce(items);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prodcon::{HealthRegistry, PipelineMetrics, ShutdownSignal, Supervisor};
+
+    use rook_lw_models::process::StageHealth;
+
+    use std::time::Duration;
+
+    struct SlowDouble {
+        producer_callbacks: ProducerCallbacks<u64>,
+        // Fail item 7 only this many times.
+        failures_left: u32,
+    }
+
+    impl ProducerTask<u64> for SlowDouble {
+        fn get_producer_callbacks(&mut self) -> &mut ProducerCallbacks<u64> {
+            &mut self.producer_callbacks
+        }
+    }
+
+    impl ConsumerTask<u64> for SlowDouble {
+        fn consume(&mut self, item: u64) -> RookLWResult<()> {
+            // Early items take longest, so workers finish out of order.
+            std::thread::sleep(Duration::from_millis(20 - item));
+            if item == 7 && self.failures_left > 0 {
+                self.failures_left -= 1;
+                return Err(RookLWError::Other("corrupt frame".to_string()));
+            }
+            self.produce(item * 2)
+        }
+    }
+
+    fn pool(failures_left: u32) -> WorkerPool<u64> {
+        let tasks: Vec<Box<dyn ProducerConsumerTask<u64>>> = (0..4)
+            .map(|_| Box::new(SlowDouble { producer_callbacks: ProducerCallbacks::new(), failures_left }) as Box<dyn ProducerConsumerTask<u64>>)
+            .collect();
+        WorkerPool::new("test", tasks)
+    }
+
+    #[test]
+    fn worker_pool_passes_results_on_in_input_order() {
+        let mut pool = pool(u32::MAX);
+        let metrics = StageMetrics::default();
+        pool.set_metrics(metrics.clone());
+
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        pool.connect(sender);
+
+        for item in 0..12 {
+            pool.consume(item).unwrap();
+        }
+        pool.finish();
+
+        let expected: Vec<u64> = (0..12).filter(|item| *item != 7).map(|item| item * 2).collect();
+        assert_eq!(receiver.try_iter().collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn worker_pool_applies_the_error_policy_and_times_its_items() {
+        let mut pool = pool(1);
+        let escalation = Escalation::new("test", HealthRegistry::new(), ShutdownSignal::new());
+        assert!(pool.take_error_policy(&ErrorPolicy::Retry(1), &escalation));
+        let metrics = PipelineMetrics::new();
+        pool.set_metrics(metrics.stage("test"));
+
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        pool.connect(sender);
+
+        for item in 0..12 {
+            pool.consume(item).unwrap();
+        }
+        pool.finish();
+
+        // Item 7 succeeded when retried.
+        let expected: Vec<u64> = (0..12).map(|item| item * 2).collect();
+        assert_eq!(receiver.try_iter().collect::<Vec<_>>(), expected);
+
+        let rendered = metrics.render();
+        assert!(rendered.contains("rook_lw_stage_errors_total{stage=\"test\"} 1\n"));
+        // Twelve items and one retry, each taking milliseconds, not the handoff.
+        assert!(rendered.contains("rook_lw_stage_processing_seconds_count{stage=\"test\"} 13\n"));
+        assert!(rendered.contains("rook_lw_stage_processing_seconds_bucket{stage=\"test\",le=\"0.001\"} 0\n"));
+    }
+
+    #[test]
+    fn worker_pool_shuts_down_when_the_policy_escalates() {
+        let health = HealthRegistry::new();
+        let shutdown = ShutdownSignal::new();
+        let supervisor = Supervisor::new(health.clone(), shutdown.clone());
+
+        let mut pool = pool(u32::MAX);
+        let (sender, _receiver) = crossbeam_channel::unbounded();
+        pool.connect(sender);
+        let (item_sender, item_receiver) = crossbeam_channel::unbounded();
+        let handle = supervisor.spawn_consumer("test", pool, item_receiver, ErrorPolicy::Shutdown);
+
+        // The only item fails; no later item is needed to notice.
+        item_sender.send(7).unwrap();
+        for _ in 0..100 {
+            if shutdown.is_requested() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(shutdown.is_requested());
+        assert_eq!(health.get("test").unwrap().health, StageHealth::Failed);
+
+        drop(item_sender);
+        handle.join().unwrap().unwrap();
+        assert_eq!(health.get("test").unwrap().health, StageHealth::Failed);
+    }
+}
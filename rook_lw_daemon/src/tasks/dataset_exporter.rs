@@ -0,0 +1,365 @@
+use crate::{RookLWResult, RookLWError};
+
+use rook_lw_image_repo::image_info::ImageInfoRepository;
+use rook_lw_image_repo::image_store::ImageStoreRepository;
+use rook_lw_models::image::{Detection, ImageInfo, ImageInfoSearchOptions};
+
+use serde::Serialize;
+use serde_json::json;
+use tracing::{error, info};
+
+use std::collections::BTreeSet;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+// Number of records read from the repository at a time.
+const SEARCH_BATCH_SIZE: u32 = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DatasetFormat {
+    /// `images/`, `labels/` and `data.yaml`, as used by Ultralytics YOLO.
+    Yolo,
+
+    /// `images/` and one COCO annotation file per split in `annotations/`.
+    Coco,
+}
+
+/// Where the boxes of an exported image come from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LabelSource {
+    /// Only images with human-verified detections, using those.
+    Verified,
+
+    /// The detections of the model, for every image.
+    Model,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    pub output_dir: PathBuf,
+    pub format: DatasetFormat,
+    pub label_source: LabelSource,
+
+    /// Fraction of events put in the validation split.
+    pub val_fraction: f32,
+
+    /// Selects the exported images. Its limit and offset are ignored.
+    pub search_options: ImageInfoSearchOptions,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ExportReport {
+    pub train_image_count: u32,
+    pub val_image_count: u32,
+    pub box_count: u32,
+    pub skipped_count: u32,
+    pub failed_count: u32,
+    pub class_names: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Split {
+    Train,
+    Val,
+}
+
+impl Split {
+    fn name(&self) -> &'static str {
+        match self {
+            Split::Train => "train",
+            Split::Val => "val",
+        }
+    }
+}
+
+/// Split of an event. All images of an event go to the same split, so near
+/// identical frames never end up in both.
+fn event_split(event_id: &str, val_fraction: f32) -> Split {
+    // FNV-1a, stable across runs and platforms.
+    let hash = event_id.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    if ((hash % 10_000) as f32) < val_fraction * 10_000.0 {
+        Split::Val
+    } else {
+        Split::Train
+    }
+}
+
+/// YOLO label line: class index, then center and size relative to the image.
+fn yolo_label(class_index: usize, detection: &Detection, width: u32, height: u32) -> String {
+    let (width, height) = (width as f32, height as f32);
+    let x = (detection.x as f32).clamp(0.0, width);
+    let y = (detection.y as f32).clamp(0.0, height);
+    let right = ((detection.x + detection.width) as f32).clamp(0.0, width);
+    let bottom = ((detection.y + detection.height) as f32).clamp(0.0, height);
+    format!(
+        "{} {:.6} {:.6} {:.6} {:.6}",
+        class_index,
+        (x + right) / 2.0 / width,
+        (y + bottom) / 2.0 / height,
+        (right - x) / width,
+        (bottom - y) / height,
+    )
+}
+
+struct ExportedImage {
+    file_name: String,
+    width: u32,
+    height: u32,
+    detections: Vec<Detection>,
+}
+
+/// Writes stored images and their boxes as a training dataset.
+pub struct DatasetExporter {
+    image_info_repository: Box<dyn ImageInfoRepository>,
+    image_store_repository: Box<dyn ImageStoreRepository>,
+}
+
+impl DatasetExporter {
+    pub fn new(
+        image_info_repository: Box<dyn ImageInfoRepository>,
+        image_store_repository: Box<dyn ImageStoreRepository>,
+    ) -> Self {
+        Self {
+            image_info_repository,
+            image_store_repository,
+        }
+    }
+
+    pub fn run(&self, options: &ExportOptions) -> RookLWResult<ExportReport> {
+        info!(
+            output_dir = %options.output_dir.display(),
+            format = ?options.format,
+            label_source = ?options.label_source,
+            "Starting dataset export"
+        );
+
+        let mut report = ExportReport::default();
+
+        // Read all records first, the class list must be known before writing labels.
+        let mut selected: Vec<(ImageInfo, Vec<Detection>)> = Vec::new();
+        let mut offset = 0;
+        loop {
+            let search_options = ImageInfoSearchOptions {
+                limit: Some(SEARCH_BATCH_SIZE),
+                offset: Some(offset),
+                ..options.search_options.clone()
+            };
+            let image_infos = self.image_info_repository.search_image_info(&search_options)?;
+            if image_infos.is_empty() {
+                break;
+            }
+            offset += image_infos.len() as u32;
+
+            for image_info in image_infos {
+                let detections = match options.label_source {
+                    LabelSource::Verified => image_info.verified_detections.clone(),
+                    LabelSource::Model => image_info.detection.as_ref().map(|result| result.detections.clone()),
+                };
+                match detections {
+                    Some(detections) => selected.push((image_info, detections)),
+                    None => report.skipped_count += 1,
+                }
+            }
+        }
+
+        let class_names: Vec<String> = selected
+            .iter()
+            .flat_map(|(_, detections)| detections.iter().map(|detection| detection.class_name.clone()))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+
+        let mut exported: Vec<(Split, ExportedImage)> = Vec::new();
+        for (image_info, detections) in selected {
+            let split = event_split(&image_info.event_id, options.val_fraction);
+            match self.export_image(options, split, &image_info, detections, &class_names) {
+                Ok(image) => {
+                    report.box_count += image.detections.len() as u32;
+                    match split {
+                        Split::Train => report.train_image_count += 1,
+                        Split::Val => report.val_image_count += 1,
+                    }
+                    exported.push((split, image));
+                },
+                Err(e) => {
+                    report.failed_count += 1;
+                    error!(image_id = %image_info.image_id, error = %e, "Failed to export image");
+                },
+            }
+        }
+
+        match options.format {
+            DatasetFormat::Yolo => self.write_yolo_data_yaml(&options.output_dir, &class_names)?,
+            DatasetFormat::Coco => {
+                for split in [Split::Train, Split::Val] {
+                    let images = exported.iter().filter(|(s, _)| *s == split).map(|(_, image)| image);
+                    self.write_coco_annotations(&options.output_dir, split, images, &class_names)?;
+                }
+            },
+        }
+
+        report.class_names = class_names;
+
+        info!(
+            train_image_count = report.train_image_count,
+            val_image_count = report.val_image_count,
+            box_count = report.box_count,
+            skipped_count = report.skipped_count,
+            failed_count = report.failed_count,
+            "Dataset export completed"
+        );
+
+        Ok(report)
+    }
+
+    fn export_image(
+        &self,
+        options: &ExportOptions,
+        split: Split,
+        image_info: &ImageInfo,
+        detections: Vec<Detection>,
+        class_names: &[String],
+    ) -> RookLWResult<ExportedImage> {
+        let mut image_data = Vec::new();
+        self.image_store_repository
+            .read(&image_info.image_path)?
+            .read_to_end(&mut image_data)?;
+        let image = image::load_from_memory(&image_data)?;
+        let (width, height) = (image.width(), image.height());
+
+        let extension = Path::new(&image_info.image_path)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or("jpg");
+        let file_name = format!("{}.{}", image_info.image_id, extension);
+
+        let image_dir = options.output_dir.join("images").join(split.name());
+        create_dir(&image_dir)?;
+        write_file(&image_dir.join(&file_name), &image_data)?;
+
+        if options.format == DatasetFormat::Yolo {
+            let labels: Vec<String> = detections
+                .iter()
+                .filter_map(|detection| {
+                    let class_index = class_names.iter().position(|name| *name == detection.class_name)?;
+                    Some(yolo_label(class_index, detection, width, height))
+                })
+                .collect();
+
+            // Images without boxes get an empty label file, as background examples.
+            let label_dir = options.output_dir.join("labels").join(split.name());
+            create_dir(&label_dir)?;
+            let label_path = label_dir.join(format!("{}.txt", image_info.image_id));
+            let mut contents = labels.join("\n");
+            if !contents.is_empty() {
+                contents.push('\n');
+            }
+            write_file(&label_path, contents.as_bytes())?;
+        }
+
+        Ok(ExportedImage {
+            file_name,
+            width,
+            height,
+            detections,
+        })
+    }
+
+    fn write_yolo_data_yaml(&self, output_dir: &Path, class_names: &[String]) -> RookLWResult<()> {
+        let mut yaml = String::new();
+        yaml.push_str(&format!("path: {}\n", output_dir.display()));
+        yaml.push_str("train: images/train\n");
+        yaml.push_str("val: images/val\n");
+        yaml.push_str("names:\n");
+        for (index, name) in class_names.iter().enumerate() {
+            yaml.push_str(&format!("  {}: {}\n", index, name));
+        }
+        create_dir(output_dir)?;
+        write_file(&output_dir.join("data.yaml"), yaml.as_bytes())
+    }
+
+    fn write_coco_annotations<'a>(
+        &self,
+        output_dir: &Path,
+        split: Split,
+        images: impl Iterator<Item = &'a ExportedImage>,
+        class_names: &[String],
+    ) -> RookLWResult<()> {
+        let mut coco_images = Vec::new();
+        let mut coco_annotations = Vec::new();
+
+        for (image_index, image) in images.enumerate() {
+            let image_id = image_index + 1;
+            coco_images.push(json!({
+                "id": image_id,
+                "file_name": format!("{}/{}", split.name(), image.file_name),
+                "width": image.width,
+                "height": image.height,
+            }));
+            for detection in &image.detections {
+                let Some(class_index) = class_names.iter().position(|name| *name == detection.class_name) else {
+                    continue;
+                };
+                coco_annotations.push(json!({
+                    "id": coco_annotations.len() + 1,
+                    "image_id": image_id,
+                    "category_id": class_index + 1,
+                    "bbox": [detection.x, detection.y, detection.width, detection.height],
+                    "area": detection.width * detection.height,
+                    "iscrowd": 0,
+                }));
+            }
+        }
+
+        let categories: Vec<_> = class_names
+            .iter()
+            .enumerate()
+            .map(|(index, name)| json!({ "id": index + 1, "name": name }))
+            .collect();
+
+        let coco = json!({
+            "images": coco_images,
+            "annotations": coco_annotations,
+            "categories": categories,
+        });
+
+        let annotation_dir = output_dir.join("annotations");
+        create_dir(&annotation_dir)?;
+        write_file(
+            &annotation_dir.join(format!("instances_{}.json", split.name())),
+            serde_json::to_string_pretty(&coco)?.as_bytes(),
+        )
+    }
+}
+
+fn create_dir(path: &Path) -> RookLWResult<()> {
+    std::fs::create_dir_all(path)
+        .map_err(|e| RookLWError::Other(format!("Failed to create directory {}: {}", path.display(), e)))
+}
+
+fn write_file(path: &Path, contents: &[u8]) -> RookLWResult<()> {
+    std::fs::write(path, contents)
+        .map_err(|e| RookLWError::Other(format!("Failed to write {}: {}", path.display(), e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_split_is_stable_and_roughly_proportional() {
+        let val_count = (0..1000)
+            .filter(|i| event_split(&format!("event-{i}"), 0.2) == Split::Val)
+            .count();
+        assert!((150..250).contains(&val_count), "{val_count}");
+
+        assert_eq!(event_split("event-1", 0.2), event_split("event-1", 0.2));
+        assert_eq!(event_split("event-1", 0.0), Split::Train);
+        assert_eq!(event_split("event-1", 1.0), Split::Val);
+
+        let detection = Detection { x: 10, y: 20, width: 40, height: 20, ..Default::default() };
+        assert_eq!(yolo_label(2, &detection, 100, 50), "2 0.300000 0.600000 0.400000 0.400000");
+    }
+}
@@ -0,0 +1,124 @@
+use crate::RookLWResult;
+use crate::image::embedding_clustering::{cluster_embeddings, ClusteringSettings};
+
+use rook_lw_image_repo::image_info::ImageInfoRepository;
+use rook_lw_models::image::EmbeddingCluster;
+
+use chrono::{DateTime, FixedOffset};
+use serde::Serialize;
+use tracing::info;
+
+use std::collections::{BTreeMap, HashMap};
+
+// Number of embeddings read from the repository at a time.
+const SCAN_BATCH_SIZE: u32 = 1000;
+
+/// Outcome of a clustering run.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ClusteringReport {
+    pub dry_run: bool,
+    pub image_count: u32,
+    pub clustered_count: u32,
+    pub noise_count: u32,
+    pub cluster_count: u32,
+    pub new_cluster_count: u32,
+}
+
+/// Clusters the embeddings of all stored images and saves the clusters and
+/// the cluster of each image back to the repository.
+///
+/// The clusters of the previous run are used as the starting point, so
+/// recurring visitors keep their cluster id.
+pub struct EmbeddingClusterer {
+    image_info_repository: Box<dyn ImageInfoRepository>,
+    settings: ClusteringSettings,
+}
+
+impl EmbeddingClusterer {
+    pub fn new(image_info_repository: Box<dyn ImageInfoRepository>, settings: ClusteringSettings) -> Self {
+        Self {
+            image_info_repository,
+            settings,
+        }
+    }
+
+    pub fn run(&self, dry_run: bool) -> RookLWResult<ClusteringReport> {
+        info!(settings = ?self.settings, dry_run, "Starting embedding clustering");
+
+        let mut image_ids: Vec<String> = Vec::new();
+        let mut embeddings: Vec<Vec<f32>> = Vec::new();
+        let mut offset = 0;
+        loop {
+            let batch = self.image_info_repository.scan_image_embeddings(SCAN_BATCH_SIZE, offset)?;
+            if batch.is_empty() {
+                break;
+            }
+            offset += batch.len() as u32;
+            for (image_id, embedding) in batch {
+                image_ids.push(image_id);
+                embeddings.push(embedding);
+            }
+        }
+
+        let previous_clusters = self.image_info_repository.get_embedding_clusters()?;
+        let seeds: Vec<(u32, Vec<f32>)> = previous_clusters
+            .iter()
+            .map(|cluster| (cluster.cluster_id, cluster.centroid.clone()))
+            .collect();
+        let previous_clusters: HashMap<u32, EmbeddingCluster> = previous_clusters
+            .into_iter()
+            .map(|cluster| (cluster.cluster_id, cluster))
+            .collect();
+
+        let result = cluster_embeddings(&seeds, &embeddings, &self.settings);
+
+        let now: DateTime<FixedOffset> = chrono::Local::now().into();
+        let clusters: Vec<EmbeddingCluster> = result.clusters
+            .iter()
+            .map(|cluster| EmbeddingCluster {
+                cluster_id: cluster.cluster_id,
+                centroid: cluster.centroid.clone(),
+                member_count: cluster.members.len() as u32,
+                representative_image_id: Some(image_ids[cluster.representative].clone()),
+                created_timestamp: previous_clusters
+                    .get(&cluster.cluster_id)
+                    .map(|previous| previous.created_timestamp)
+                    .unwrap_or(now),
+                updated_timestamp: now,
+            })
+            .collect();
+        let assignments: BTreeMap<String, u32> = image_ids
+            .iter()
+            .zip(&result.assignments)
+            .filter_map(|(image_id, assignment)| assignment.map(|cluster_id| (image_id.clone(), cluster_id)))
+            .collect();
+
+        let report = ClusteringReport {
+            dry_run,
+            image_count: image_ids.len() as u32,
+            clustered_count: assignments.len() as u32,
+            noise_count: (image_ids.len() - assignments.len()) as u32,
+            cluster_count: clusters.len() as u32,
+            new_cluster_count: clusters
+                .iter()
+                .filter(|cluster| !previous_clusters.contains_key(&cluster.cluster_id))
+                .count() as u32,
+        };
+
+        if !dry_run {
+            self.image_info_repository.save_embedding_clusters(&clusters, &image_ids, &assignments)?;
+        }
+
+        info!(
+            dry_run = report.dry_run,
+            image_count = report.image_count,
+            clustered_count = report.clustered_count,
+            noise_count = report.noise_count,
+            cluster_count = report.cluster_count,
+            new_cluster_count = report.new_cluster_count,
+            "Embedding clustering completed"
+        );
+
+        Ok(report)
+    }
+}
@@ -0,0 +1,197 @@
+use crate::RookLWResult;
+use crate::image::hero_frame::{hero_frame_score, HeroFrameWeights};
+
+use rook_lw_image_repo::event::EventRepository;
+use rook_lw_image_repo::image_info::ImageInfoRepository;
+use rook_lw_models::event::{EventInfo, EventSummary, EventTrigger};
+use rook_lw_models::image::ImageInfo;
+
+use image::DynamicImage;
+use tracing::{info, warn};
+
+use std::collections::BTreeMap;
+
+struct OpenEvent {
+    trigger: EventTrigger,
+    images: Vec<ImageInfo>,
+
+    /// Image id and hero frame score of the best image so far.
+    hero: Option<(String, f32)>,
+}
+
+impl OpenEvent {
+    fn offer_hero(&mut self, image_id: &str, score: f32) {
+        let replace = match &self.hero {
+            None => true,
+            // A frame stored again takes its new score.
+            Some((hero_image_id, hero_score)) => score > *hero_score || hero_image_id == image_id,
+        };
+        if replace {
+            self.hero = Some((image_id.to_string(), score));
+        }
+    }
+}
+
+/// Keeps the stored events up to date as their images are stored.
+///
+/// The image capturer stores each event when it starts and ends it; the
+/// recorder adds what the stored images add up to, and keeps the trigger,
+/// start and end already stored. An event is open here while its images
+/// arrive, until an image of another event is stored or the storer finishes.
+/// An image arriving for an ended event opens it again, from the images
+/// already stored for it.
+///
+/// Each image is given a hero frame score as it arrives, and the best scoring
+/// image so far is the hero image of its event.
+pub struct EventRecorder {
+    event_repository: Box<dyn EventRepository>,
+    hero_weights: HeroFrameWeights,
+    open_events: BTreeMap<String, OpenEvent>,
+}
+
+impl EventRecorder {
+    pub fn new(event_repository: Box<dyn EventRepository>, hero_weights: HeroFrameWeights) -> Self {
+        Self {
+            event_repository,
+            hero_weights,
+            open_events: BTreeMap::new(),
+        }
+    }
+
+    /// Add a stored image to its event. `image_info_repository` must already hold the image.
+    pub fn record(
+        &mut self,
+        image_info: &ImageInfo,
+        image: &DynamicImage,
+        trigger: EventTrigger,
+        image_info_repository: &dyn ImageInfoRepository,
+    ) -> RookLWResult<()> {
+        let ended: Vec<String> = self.open_events
+            .keys()
+            .filter(|event_id| **event_id != image_info.event_id)
+            .cloned()
+            .collect();
+        for event_id in ended {
+            self.end_event(&event_id)?;
+        }
+
+        let mut open_event = match self.open_events.remove(&image_info.event_id) {
+            Some(mut open_event) => {
+                // A frame stored again, e.g. after recovery, replaces the earlier copy.
+                open_event.images.retain(|image| image.image_id != image_info.image_id);
+                open_event.images.push(image_info.clone());
+                open_event
+            },
+            None => {
+                let stored = self.event_repository.get_event(&image_info.event_id)?;
+                OpenEvent {
+                    trigger,
+                    images: image_info_repository.get_event_image_info(&image_info.event_id)?,
+                    hero: stored.and_then(|event| event.hero_image_id.zip(event.hero_score)),
+                }
+            },
+        };
+
+        let hero_score = hero_frame_score(image, image_info.detection.as_ref(), &self.hero_weights);
+        open_event.offer_hero(&image_info.image_id, hero_score);
+
+        self.event_repository.save_event_frames(&summarize_event(&image_info.event_id, &open_event, false))?;
+        self.open_events.insert(image_info.event_id.clone(), open_event);
+        Ok(())
+    }
+
+    /// Forget the open events without ending them. The next image of an event
+    /// opens it again from what is stored.
+    pub fn reset(&mut self) {
+        self.open_events.clear();
+    }
+
+    /// End all open events.
+    pub fn finish(&mut self) {
+        let event_ids: Vec<String> = self.open_events.keys().cloned().collect();
+        for event_id in event_ids {
+            if let Err(e) = self.end_event(&event_id) {
+                warn!(event_id = %event_id, error = %e, "Failed to end event");
+            }
+        }
+    }
+
+    fn end_event(&mut self, event_id: &str) -> RookLWResult<()> {
+        let Some(open_event) = self.open_events.remove(event_id) else {
+            return Ok(());
+        };
+        let event = summarize_event(event_id, &open_event, true);
+        info!(
+            event_id = %event_id,
+            frame_count = event.frame_count,
+            class_counts = ?event.class_counts,
+            "Event ended"
+        );
+        self.event_repository.save_event_frames(&event)?;
+        Ok(())
+    }
+}
+
+fn summarize_event(event_id: &str, open_event: &OpenEvent, ended: bool) -> EventInfo {
+    let images = &open_event.images;
+    let summary = EventSummary::from_image_infos(event_id, images);
+
+    EventInfo {
+        event_id: event_id.to_string(),
+        trigger: open_event.trigger,
+        start_timestamp: images.iter().map(|image| image.event_timestamp).min().unwrap_or_default(),
+        end_timestamp: if ended { summary.last_capture_timestamp } else { None },
+        peak_motion_score: images.iter().map(|image| image.motion_score.score).fold(0.0, f32::max),
+        frame_count: summary.image_count,
+        class_counts: summary.class_counts,
+        hero_image_id: open_event.hero.as_ref().map(|(image_id, _)| image_id.clone()),
+        hero_score: open_event.hero.as_ref().map(|(_, score)| *score),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rook_lw_models::image::{Detection, DetectionResult};
+
+    fn image(capture_index: u32, motion: f32, class_name: Option<&str>, confidence: f32) -> ImageInfo {
+        let mut image = ImageInfo {
+            image_id: format!("e_{}", capture_index),
+            event_id: "e".to_string(),
+            capture_index,
+            ..Default::default()
+        };
+        image.motion_score.score = motion;
+        image.capture_timestamp += chrono::Duration::seconds(capture_index as i64);
+        image.detection = class_name.map(|class_name| DetectionResult::new(vec![Detection {
+            class_name: class_name.to_string(),
+            confidence,
+            ..Default::default()
+        }]));
+        image
+    }
+
+    #[test]
+    fn summarize_event_aggregates_images_and_keeps_best_hero() {
+        let mut open_event = OpenEvent {
+            trigger: EventTrigger::Radar,
+            images: vec![image(0, 0.4, None, 0.0), image(1, 0.9, Some("fox"), 0.6), image(2, 0.2, Some("fox"), 0.8)],
+            hero: None,
+        };
+        open_event.offer_hero("e_0", 0.3);
+        open_event.offer_hero("e_1", 0.7);
+        open_event.offer_hero("e_2", 0.5);
+
+        let open = summarize_event("e", &open_event, false);
+        assert_eq!(open.frame_count, 3);
+        assert_eq!(open.peak_motion_score, 0.9);
+        assert_eq!(open.class_counts.get("fox"), Some(&1));
+        assert_eq!(open.hero_image_id.as_deref(), Some("e_1"));
+        assert_eq!(open.hero_score, Some(0.7));
+        assert_eq!(open.end_timestamp, None);
+
+        let ended = summarize_event("e", &open_event, true);
+        assert_eq!(ended.end_timestamp, Some(open_event.images[2].capture_timestamp));
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::thread::sleep;
 
@@ -5,13 +6,34 @@ use crate::RookLWResult;
 use crate::events::{CaptureEvent, ImageProcessingEvent, MotionDetectionEvent};
 use crate::image::conversions::frame_to_dynamic_image;
 use crate::image::frame::FrameSource;
-use crate::prodcon::ProducerCallbacks;
+use crate::prodcon::{LiveSettings, ProducerCallbacks};
+use crate::tasks::motion_watcher::WatcherCommand;
+
+use chrono::Duration;
+use image::DynamicImage;
+use rook_lw_image_repo::event::EventRepository;
+use rook_lw_models::event::{EventInfo, EventTrigger};
+use rook_lw_models::image::MotionDetectionScore;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+// Events still open this long after they started, e.g. after a crash, are ended.
+const OPEN_EVENT_TIMEOUT_MINUTES: i64 = 10;
+
+/// How many images are captured for an event, and how far apart.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaptureSettings {
+    pub capture_count: u32,
+    pub capture_interval: std::time::Duration,
+}
 
 pub struct ImageCapturer {
     frame_source: Arc<Box<dyn FrameSource + Send + Sync>>,
     producer_callbacks: ProducerCallbacks<ImageProcessingEvent>,
     capture_count: u32,
     capture_interval: std::time::Duration,
+    live_settings: Option<LiveSettings<CaptureSettings>>,
+    event_repository: Option<Box<dyn EventRepository>>,
 }
 
 impl ImageCapturer {
@@ -21,9 +43,24 @@ impl ImageCapturer {
             producer_callbacks: ProducerCallbacks::new(),
             capture_count,
             capture_interval,
+            live_settings: None,
+            event_repository: None,
         }
     }
 
+    /// Store each event as it starts and end it once its images are captured,
+    /// so events show up even when none of their images are stored.
+    pub fn set_event_repository(&mut self, event_repository: Box<dyn EventRepository>) -> &mut Self {
+        self.event_repository = Some(event_repository);
+        self
+    }
+
+    /// Take replaced capture settings into use from the next event on.
+    pub fn set_live_settings(&mut self, live_settings: LiveSettings<CaptureSettings>) -> &mut Self {
+        self.live_settings = Some(live_settings);
+        self
+    }
+
     pub fn get_producer_callbacks(&mut self) -> &mut ProducerCallbacks<ImageProcessingEvent> {
         &mut self.producer_callbacks
     }
@@ -32,7 +69,54 @@ impl ImageCapturer {
         self.producer_callbacks.produce(&event)
     }
 
+    /// Carry out a command sent to the motion watcher.
+    pub fn on_command(&mut self, command: WatcherCommand) -> RookLWResult<()> {
+        match command {
+            WatcherCommand::Trigger => {
+                let event_id = Uuid::new_v4();
+                info!(event_id = %event_id, "Manual trigger");
+                self.on_motion_detected(MotionDetectionEvent {
+                    event_id,
+                    event_timestamp: chrono::Local::now().into(),
+                    trigger: EventTrigger::Manual,
+                    motion_score: MotionDetectionScore {
+                        detected: true,
+                        score: 1.0,
+                        properties: HashMap::new(),
+                        regions: Vec::new(),
+                    },
+                    capture_events: Vec::new(),
+                })
+            },
+            WatcherCommand::Snapshot(reply) => {
+                // The requester may have given up waiting.
+                let _ = reply.send(self.snapshot());
+                Ok(())
+            },
+        }
+    }
+
+    /// Capture a single frame.
+    pub fn snapshot(&self) -> RookLWResult<DynamicImage> {
+        let frame = self.frame_source.next_frame()?;
+        frame_to_dynamic_image(&*frame)
+    }
+
     pub fn on_motion_detected(&mut self, result: MotionDetectionEvent) -> RookLWResult<()> {
+        if let Some(settings) = self.live_settings.as_mut().and_then(|live_settings| live_settings.changed()) {
+            info!(settings = ?settings, "Using new capture settings");
+            self.capture_count = settings.capture_count;
+            self.capture_interval = settings.capture_interval;
+        }
+
+        let event_id = result.event_id;
+        self.start_event(&result);
+        let captured = self.capture(result);
+        self.end_event(event_id);
+        captured
+    }
+
+    fn capture(&mut self, result: MotionDetectionEvent) -> RookLWResult<()> {
         let index_offset = result.capture_events.len() as u32;
 
         // Emit initial capture events
@@ -40,10 +124,16 @@ impl ImageCapturer {
             self.on_image_processing_event(ImageProcessingEvent {
                 capture_event: capture_event.clone(),
                 detection_result: None,
+                duplicate_of: None,
+                redacted: false,
+                queued_at: None,
             })?;
         }
 
-        for capture_index in 0..(self.capture_count-index_offset) {
+        // Later captures are taken after the subject may have moved, so they get a full-frame pass.
+        let motion_score = later_capture_motion_score(&result.motion_score);
+
+        for capture_index in 0..self.capture_count.saturating_sub(index_offset) {
 
             let image = {
                 let frame = self.frame_source.next_frame()?;
@@ -53,7 +143,8 @@ impl ImageCapturer {
             let capture_event = CaptureEvent {
                 event_id: result.event_id,
                 event_timestamp: result.event_timestamp,
-                motion_score: result.motion_score.clone(),
+                trigger: result.trigger,
+                motion_score: motion_score.clone(),
                 capture_index: capture_index + index_offset, // offset because first images were from motion detection
                 capture_timestamp: chrono::Local::now().into(),
                 image,
@@ -62,6 +153,9 @@ impl ImageCapturer {
             self.on_image_processing_event(ImageProcessingEvent {
                 capture_event: capture_event.clone(),
                 detection_result: None,
+                duplicate_of: None,
+                redacted: false,
+                queued_at: None,
             })?;
 
             sleep(self.capture_interval);
@@ -69,4 +163,69 @@ impl ImageCapturer {
 
         Ok(())
     }
-}
\ No newline at end of file
+
+    // A failed event update doesn't stop the captures.
+    fn start_event(&self, result: &MotionDetectionEvent) {
+        let Some(event_repository) = &self.event_repository else {
+            return;
+        };
+
+        let stale_before = result.event_timestamp - Duration::minutes(OPEN_EVENT_TIMEOUT_MINUTES);
+        match event_repository.end_stale_events(stale_before) {
+            Ok(0) => {},
+            Ok(count) => info!(count, "Ended events left open"),
+            Err(e) => warn!(error = %e, "Failed to end events left open"),
+        }
+
+        let event = EventInfo {
+            event_id: result.event_id.to_string(),
+            trigger: result.trigger,
+            start_timestamp: result.event_timestamp,
+            peak_motion_score: result.motion_score.score,
+            ..Default::default()
+        };
+        if let Err(e) = event_repository.save_event(&event) {
+            warn!(event_id = %result.event_id, error = %e, "Failed to store event");
+        }
+    }
+
+    fn end_event(&self, event_id: Uuid) {
+        let Some(event_repository) = &self.event_repository else {
+            return;
+        };
+        if let Err(e) = event_repository.end_event(&event_id.to_string(), chrono::Local::now().into()) {
+            warn!(event_id = %event_id, error = %e, "Failed to end event");
+        }
+    }
+}
+
+/// Motion score of the captures taken after motion was detected, without the
+/// motion regions of the frames it was detected in.
+fn later_capture_motion_score(motion_score: &MotionDetectionScore) -> MotionDetectionScore {
+    MotionDetectionScore {
+        regions: Vec::new(),
+        ..motion_score.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rook_lw_models::image::MotionRegion;
+
+    #[test]
+    fn later_captures_have_no_motion_regions() {
+        let motion_score = MotionDetectionScore {
+            detected: true,
+            score: 0.4,
+            properties: HashMap::from([("percentile".to_string(), "0.95".to_string())]),
+            regions: vec![MotionRegion { x: 10, y: 20, width: 30, height: 40, score: 0.6 }],
+        };
+
+        let later = later_capture_motion_score(&motion_score);
+        assert!(later.regions.is_empty());
+        assert_eq!(later.score, motion_score.score);
+        assert_eq!(later.properties, motion_score.properties);
+    }
+}
@@ -1,18 +1,101 @@
 use crate::RookLWResult;
 use crate::events::{ImageProcessingEvent, CaptureEvent};
-use crate::image::object_detection::ObjectDetector;
+use crate::image::object_detection::{ObjectDetector, RegionDetectionSettings, DetectionCrop, plan_detection_crops, StaticDetectionMemory};
 
 use crate::prodcon::{
     ProducerTask, ConsumerTask,
-    ProducerCallbacks
+    ProducerCallbacks, LiveSettings
 };
 
+use rook_lw_models::image::{Detection, DetectionResult};
+
+use image::{DynamicImage, GenericImageView};
 use tracing::info;
 
+use std::sync::{Arc, Mutex, MutexGuard};
 use std::time::Instant;
 
+/// Which captures without detections are passed on to be stored.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum StoragePolicy {
+    /// Every capture.
+    All,
+
+    /// Only captures with detections, including ones whose detections were all suppressed as static.
+    #[default]
+    DetectionsOnly,
+
+    /// This fraction of the captures without detections, spread evenly.
+    SampledNegatives(f32),
+
+    /// The first capture of each event, with or without detections.
+    FirstFrameNegatives,
+}
+
+/// Decides which negatives to keep under a [`StoragePolicy`].
+#[derive(Debug, Default)]
+struct NegativeSampler {
+    policy: StoragePolicy,
+
+    // Sampled negatives owed; one is kept each time this reaches 1.
+    credit: f32,
+}
+
+impl NegativeSampler {
+    fn keep(&mut self, capture_index: u32) -> bool {
+        match self.policy {
+            StoragePolicy::All => true,
+            StoragePolicy::DetectionsOnly => false,
+            StoragePolicy::SampledNegatives(rate) => {
+                self.credit += rate;
+                if self.credit >= 1.0 {
+                    self.credit -= 1.0;
+                    true
+                } else {
+                    false
+                }
+            },
+            StoragePolicy::FirstFrameNegatives => capture_index == 0,
+        }
+    }
+}
+
+/// What a detector remembers across captures: the static detections and the
+/// negatives owed by the storage policy. Cheap to clone; all clones share the
+/// same memory, so the workers of a pooled detector act as one detector.
+#[derive(Clone, Default)]
+pub struct DetectorMemory {
+    static_memory: Option<Arc<Mutex<StaticDetectionMemory>>>,
+    negative_sampler: Arc<Mutex<NegativeSampler>>,
+}
+
+impl DetectorMemory {
+    /// Suppress detections that stay in the same place across many events when
+    /// `static_memory` is given, and pass on negatives as `policy` allows.
+    pub fn new(static_memory: Option<StaticDetectionMemory>, policy: StoragePolicy) -> Self {
+        Self {
+            static_memory: static_memory.map(|static_memory| Arc::new(Mutex::new(static_memory))),
+            negative_sampler: Arc::new(Mutex::new(NegativeSampler { policy, credit: 0.0 })),
+        }
+    }
+
+    fn negative_sampler(&self) -> MutexGuard<'_, NegativeSampler> {
+        self.negative_sampler.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn clear(&self) {
+        if let Some(static_memory) = &self.static_memory {
+            static_memory.lock().unwrap_or_else(|e| e.into_inner()).clear();
+        }
+        self.negative_sampler().credit = 0.0;
+    }
+}
+
 pub struct ImageDetector {
     object_detector: Box<dyn ObjectDetector>,
+    region_detection: Option<RegionDetectionSettings>,
+    memory: DetectorMemory,
+    live_storage_policy: Option<LiveSettings<StoragePolicy>>,
     producer_callbacks: ProducerCallbacks<ImageProcessingEvent>,
 }
 
@@ -26,25 +109,79 @@ impl ConsumerTask<ImageProcessingEvent> for ImageDetector {
     fn consume(&mut self, item: ImageProcessingEvent) -> RookLWResult<()> {
         self.process_capture_event(&item.capture_event)
     }
+
+    fn reset(&mut self) -> RookLWResult<()> {
+        self.memory.clear();
+        Ok(())
+    }
 }
 
 impl ImageDetector {
     pub fn new(object_detector: Box<dyn ObjectDetector>) -> Self {
         Self {
             object_detector,
+            region_detection: None,
+            memory: DetectorMemory::default(),
+            live_storage_policy: None,
             producer_callbacks: ProducerCallbacks::new(),
         }
     }
 
+    /// Run detection on crops around the motion regions of a capture, when it has them.
+    pub fn set_region_detection(&mut self, settings: RegionDetectionSettings) -> &mut Self {
+        self.region_detection = Some(settings);
+        self
+    }
+
+    /// Remember static detections and owed negatives in `memory`, which may be
+    /// shared with other detectors; by default nothing is suppressed and no
+    /// negatives are passed on.
+    pub fn set_memory(&mut self, memory: DetectorMemory) -> &mut Self {
+        self.memory = memory;
+        self
+    }
+
+    /// Which captures without detections to pass on.
+    pub fn set_storage_policy(&mut self, policy: StoragePolicy) -> &mut Self {
+        *self.memory.negative_sampler() = NegativeSampler { policy, credit: 0.0 };
+        self
+    }
+
+    /// Take a replaced storage policy into use from the next capture on.
+    pub fn set_live_storage_policy(&mut self, live_storage_policy: LiveSettings<StoragePolicy>) -> &mut Self {
+        self.live_storage_policy = Some(live_storage_policy);
+        self
+    }
+
     fn process_capture_event(&mut self, capture_event: &CaptureEvent) -> RookLWResult<()> {
+        if let Some(policy) = self.live_storage_policy.as_mut().and_then(|live_storage_policy| live_storage_policy.changed()) {
+            info!(storage_policy = ?policy, "Using new storage policy");
+            self.set_storage_policy(*policy);
+        }
+
         info!(
             event_id = %capture_event.event_id,
             "Processing image for object detection"
         );
     
         let timer = Instant::now();
-        let detection_result = self.object_detector.detect(&capture_event.image)?;
+        let mut detection_result = self.detect(capture_event)?;
         let elapsed = timer.elapsed();
+
+        if let Some(static_memory) = &self.memory.static_memory {
+            let static_count = static_memory.lock().unwrap_or_else(|e| e.into_inner()).apply(
+                capture_event.event_id,
+                capture_event.capture_timestamp,
+                &mut detection_result,
+            );
+            if static_count > 0 {
+                info!(
+                    event_id = %capture_event.event_id,
+                    static_count,
+                    "Static detections found"
+                );
+            }
+        }
         
         info!(
             event_id = %capture_event.event_id,
@@ -66,15 +203,130 @@ impl ImageDetector {
             }
         }
 
-        // Only send event if there are detections
-        if detection_result.detections.len() > 0 {
+        // Captures without detections are only sent on as the storage policy allows.
+        if !detection_result.is_negative() || self.memory.negative_sampler().keep(capture_event.capture_index) {
             let image_processing_event = ImageProcessingEvent {
                 detection_result: Some(detection_result),
                 capture_event: capture_event.clone(),
+                duplicate_of: None,
+                redacted: false,
+                queued_at: None,
             };
             self.produce(image_processing_event)?;
         }
 
         Ok(())
     }
+
+    fn detect(&mut self, capture_event: &CaptureEvent) -> RookLWResult<DetectionResult> {
+        let image = &capture_event.image;
+        let (width, height) = image.dimensions();
+
+        let crops = self.region_detection.as_ref().and_then(|settings| {
+            plan_detection_crops(&capture_event.motion_score.regions, width, height, settings)
+        });
+
+        match crops {
+            Some(crops) => {
+                info!(
+                    event_id = %capture_event.event_id,
+                    region_count = capture_event.motion_score.regions.len(),
+                    crop_count = crops.len(),
+                    "Detecting objects in motion regions"
+                );
+                self.detect_in_crops(image, &crops)
+            },
+            None => self.object_detector.detect(image),
+        }
+    }
+
+    /// Detect in each crop and map the detections back to full-frame coordinates.
+    ///
+    /// Embeddings are taken from the largest crop, as the closest stand-in for the whole image.
+    fn detect_in_crops(&mut self, image: &DynamicImage, crops: &[DetectionCrop]) -> RookLWResult<DetectionResult> {
+        let mut detections = Vec::new();
+        let mut model_detections: Option<Vec<Detection>> = None;
+        let mut embeddings = None;
+        let mut embeddings_area = 0;
+        let mut inference_duration_ms = None;
+
+        for crop in crops {
+            let cropped = image.crop_imm(crop.x, crop.y, crop.width, crop.height);
+            let result = self.object_detector.detect(&cropped)?;
+
+            for mut detection in result.detections {
+                detection.x += crop.x as i32;
+                detection.y += crop.y as i32;
+                detections.push(detection);
+            }
+            for mut detection in result.model_detections.into_iter().flatten() {
+                detection.x += crop.x as i32;
+                detection.y += crop.y as i32;
+                model_detections.get_or_insert_with(Vec::new).push(detection);
+            }
+
+            if let Some(duration_ms) = result.inference_duration_ms {
+                *inference_duration_ms.get_or_insert(0) += duration_ms;
+            }
+
+            if result.embeddings.is_some() && crop.area() > embeddings_area {
+                embeddings = result.embeddings;
+                embeddings_area = crop.area();
+            }
+        }
+
+        Ok(DetectionResult {
+            detections,
+            embeddings,
+            suppressed: Vec::new(),
+            provenance: Some(self.object_detector.provenance()),
+            inference_duration_ms,
+            model_detections,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kept(policy: StoragePolicy, capture_indexes: &[u32]) -> Vec<u32> {
+        let mut sampler = NegativeSampler { policy, credit: 0.0 };
+        capture_indexes.iter().copied().filter(|index| sampler.keep(*index)).collect()
+    }
+
+    #[test]
+    fn negative_sampler_keeps_negatives_by_policy() {
+        let indexes: Vec<u32> = (0..10).chain(0..10).collect();
+
+        assert_eq!(kept(StoragePolicy::All, &indexes).len(), 20);
+        assert_eq!(kept(StoragePolicy::DetectionsOnly, &indexes), Vec::<u32>::new());
+        assert_eq!(kept(StoragePolicy::FirstFrameNegatives, &indexes), vec![0, 0]);
+        // Every fourth negative, across events.
+        assert_eq!(kept(StoragePolicy::SampledNegatives(0.25), &indexes), vec![3, 7, 1, 5, 9]);
+    }
+
+    #[test]
+    fn captures_with_only_suppressed_detections_are_not_negatives() {
+        let detection = Detection {
+            class_id: 0,
+            class_name: "person".to_string(),
+            confidence: 0.8,
+            x: 10,
+            y: 10,
+            width: 20,
+            height: 40,
+            track_id: None,
+        };
+
+        let mut detection_result = DetectionResult::default();
+        assert!(detection_result.is_negative());
+
+        detection_result.suppressed.push(detection.clone());
+        assert!(!detection_result.is_negative());
+
+        detection_result.suppressed.clear();
+        detection_result.detections.push(detection);
+        assert!(!detection_result.is_negative());
+    }
 }
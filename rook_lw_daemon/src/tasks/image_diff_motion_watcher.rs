@@ -1,24 +1,33 @@
 use crate::RookLWResult;
 use crate::image::conversions::frame_to_dynamic_image;
 use crate::image::frame::{FrameSource, FrameSlot};
-use crate::image::motion::YPlaneMotionDetector;
+use crate::image::motion::{MotionThresholds, YPlaneMotionDetector};
 use crate::events::{CaptureEvent, ImageProcessingEvent};
-use crate::prodcon::{ProducerTask, ProducerCallbacks};
+use crate::prodcon::{LiveSettings, ProducerTask, ProducerCallbacks, ShutdownSignal};
 use crate::tasks::image_capturer::ImageCapturer;
-use crate::tasks::motion_watcher::MotionWatcher;
+use crate::tasks::motion_watcher::{MotionWatcher, WatcherControl};
 
 use crate::events::MotionDetectionEvent;
 
 use std::sync::Arc;
 use std::time::Duration;
 use std::time::Instant;
-use std::thread::{JoinHandle, sleep, spawn};
 
 use chrono::{DateTime, FixedOffset};
 use tracing::{info, debug};
+use rook_lw_models::event::EventTrigger;
 
 use uuid::Uuid;
 
+/// Settings of the image diff motion watcher that can change while it runs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MotionWatchSettings {
+    pub motion_detect_interval: Duration,
+    pub motion_watch_count: u32,
+    pub round_interval: Duration,
+    pub thresholds: MotionThresholds,
+}
+
 pub struct ImageDiffMotionWatcher {
     frame_source: Arc<Box<dyn FrameSource + Send + Sync>>,
     motion_detect_interval: Duration,
@@ -26,6 +35,8 @@ pub struct ImageDiffMotionWatcher {
     motion_detector: Box<dyn YPlaneMotionDetector>,
     image_capturer: ImageCapturer,
     round_interval: Duration,
+    control: WatcherControl,
+    live_settings: Option<LiveSettings<MotionWatchSettings>>,
 }
 
 impl ProducerTask<ImageProcessingEvent> for ImageDiffMotionWatcher {
@@ -51,38 +62,59 @@ impl ImageDiffMotionWatcher {
             motion_detector,
             image_capturer,
             round_interval,
+            control: WatcherControl::new(),
+            live_settings: None,
         }
     }
 
-    pub fn start(mut self) -> JoinHandle<RookLWResult<()>> {
-        spawn(move || {
-            match self.run() {
-                Ok(_) => {
-                    info!("Motion watcher exiting normally");
-                    Ok(())
-                },
-                Err(e) => {
-                    info!(error = %e, "Motion watcher exiting with error");
-                    Err(e)
-                }
-            }
-        })
+    /// Take replaced settings into use from the next round on. The motion
+    /// detector keeps what it has learned, such as its rolling z statistics.
+    pub fn set_live_settings(&mut self, live_settings: LiveSettings<MotionWatchSettings>) -> &mut Self {
+        self.live_settings = Some(live_settings);
+        self
+    }
+
+    fn apply_live_settings(&mut self) {
+        let Some(settings) = self.live_settings.as_mut().and_then(|live_settings| live_settings.changed()) else {
+            return;
+        };
+        info!(settings = ?settings, "Using new motion watch settings");
+        self.motion_detect_interval = settings.motion_detect_interval;
+        self.motion_watch_count = settings.motion_watch_count;
+        self.round_interval = settings.round_interval;
+        self.motion_detector.set_thresholds(&settings.thresholds);
     }
 
-    pub fn run(&mut self) -> RookLWResult<()> {
+    pub fn run(&mut self, shutdown: &ShutdownSignal) -> RookLWResult<()> {
         info!("Starting motion watcher");
         self.frame_source.start()?;
-        loop {
-            self.run_round()?;
-            sleep(self.round_interval);
-        }
 
-        // self.frame_source.stop()?;
-        // Ok(())
+        let result = self.watch(shutdown);
+
+        info!("Stopping frame source");
+        let stopped = self.frame_source.stop();
+        result.and(stopped)
     }
 
-    fn run_round(&mut self) -> RookLWResult<()> {
-        match self.detect_motion()? {
+    fn watch(&mut self, shutdown: &ShutdownSignal) -> RookLWResult<()> {
+        while !shutdown.is_requested() {
+            while let Ok(command) = self.control.commands().try_recv() {
+                self.image_capturer.on_command(command)?;
+            }
+            self.apply_live_settings();
+            if !self.control.is_paused() {
+                self.run_round(shutdown)?;
+            }
+            if shutdown.sleep(self.round_interval) {
+                break;
+            }
+        }
+        info!("Shutdown requested, motion watcher stopping");
+        Ok(())
+    }
+
+    fn run_round(&mut self, shutdown: &ShutdownSignal) -> RookLWResult<()> {
+        match self.detect_motion(shutdown)? {
             Some(motion_detection_result) => {
                 self.on_motion_detected(motion_detection_result)?;
             },
@@ -97,14 +129,17 @@ impl ImageDiffMotionWatcher {
         self.image_capturer.on_motion_detected(result)
     }
 
-    fn detect_motion(&mut self) -> RookLWResult<Option<MotionDetectionEvent>> {
+    fn detect_motion(&mut self, shutdown: &ShutdownSignal) -> RookLWResult<Option<MotionDetectionEvent>> {
         // Keep a small 2-slot ring. Each slot owns its frame and caches a YPlane.
         // YU12: YPlane is a borrowed view (no copy). MJPG: YPlane owns decoded luma.
         let mut last = FrameSlot::from_frame(self.frame_source.next_frame()?)?;
         let mut last_timestamp: DateTime<FixedOffset> = chrono::Local::now().into();
 
         for _watch_index in 0..self.motion_watch_count {
-            sleep(self.motion_detect_interval);
+            // End the round early for a pause or command.
+            if shutdown.sleep(self.motion_detect_interval) || self.control.is_paused() || self.control.has_commands() {
+                return Ok(None);
+            }
 
             let current = FrameSlot::from_frame(self.frame_source.next_frame()?)?;
             let current_timestamp: DateTime<FixedOffset> = chrono::Local::now().into();
@@ -131,6 +166,7 @@ impl ImageDiffMotionWatcher {
                 let mut result = MotionDetectionEvent {
                     event_id,
                     event_timestamp: current_timestamp,
+                    trigger: EventTrigger::ImageDiff,
                     motion_score: motion_score.clone(),
                     capture_events: Vec::new(),
                 };
@@ -141,6 +177,7 @@ impl ImageDiffMotionWatcher {
                 result.capture_events.push(CaptureEvent {
                     event_id,
                     event_timestamp: last_timestamp,
+                    trigger: EventTrigger::ImageDiff,
                     motion_score: motion_score.clone(),
                     capture_index: 0,
                     capture_timestamp: last_timestamp,
@@ -151,6 +188,7 @@ impl ImageDiffMotionWatcher {
                 result.capture_events.push(CaptureEvent {
                     event_id,
                     event_timestamp: current_timestamp,
+                    trigger: EventTrigger::ImageDiff,
                     motion_score: motion_score.clone(),
                     capture_index: 1,
                     capture_timestamp: current_timestamp,
@@ -178,11 +216,11 @@ impl ImageDiffMotionWatcher {
 }
 
 impl MotionWatcher for ImageDiffMotionWatcher {
-    fn connect(&mut self, sender: crossbeam_channel::Sender<ImageProcessingEvent>) {
-        ProducerTask::connect(self, sender);
+    fn run(&mut self, shutdown: &ShutdownSignal) -> RookLWResult<()> {
+        ImageDiffMotionWatcher::run(self, shutdown)
     }
 
-    fn start(self: Box<Self>) -> JoinHandle<RookLWResult<()>> {
-        ImageDiffMotionWatcher::start(*self)
+    fn set_control(&mut self, control: WatcherControl) {
+        self.control = control;
     }
 }
@@ -0,0 +1,307 @@
+use crate::RookLWResult;
+use crate::image::object_detection::ObjectDetector;
+use crate::image::tracking::{SortTracker, TrackerSettings};
+
+use rook_lw_image_repo::image_info::ImageInfoRepository;
+use rook_lw_image_repo::image_store::ImageStoreRepository;
+use rook_lw_models::image::{DetectionResult, ImageInfo, ModelProvenance, UNKNOWN_MODEL_NAME};
+
+use chrono::{DateTime, FixedOffset};
+use serde::Serialize;
+use tracing::{error, info};
+
+use std::collections::{BTreeMap, HashSet};
+use std::io::Read;
+
+#[derive(Debug, Clone)]
+pub struct ReprocessOptions {
+    /// Only images captured at or after this time.
+    pub start_date: Option<DateTime<FixedOffset>>,
+
+    /// Only images captured at or before this time.
+    pub end_date: Option<DateTime<FixedOffset>>,
+
+    /// Only images whose stored result came from this model name.
+    /// [`UNKNOWN_MODEL_NAME`] selects results without provenance.
+    pub from_model: Option<String>,
+
+    /// Report what would change without writing anything back.
+    pub dry_run: bool,
+
+    /// Also reprocess images that already have a result from the current model.
+    pub force: bool,
+
+    /// Number of records read from the repository at a time.
+    pub batch_size: u32,
+}
+
+impl Default for ReprocessOptions {
+    fn default() -> Self {
+        Self {
+            start_date: None,
+            end_date: None,
+            from_model: None,
+            dry_run: false,
+            force: false,
+            batch_size: 100,
+        }
+    }
+}
+
+/// Per-class detection counts before and after reprocessing an image.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct DetectionDiff {
+    pub image_id: String,
+    pub previous_model: Option<String>,
+    /// class name -> (previous count, new count), only for classes whose count changed.
+    pub class_changes: BTreeMap<String, (u32, u32)>,
+}
+
+impl DetectionDiff {
+    pub fn new(image_id: &str, previous: Option<&DetectionResult>, current: &DetectionResult) -> Self {
+        let mut class_changes: BTreeMap<String, (u32, u32)> = BTreeMap::new();
+
+        for detection in previous.iter().flat_map(|result| result.detections.iter()) {
+            class_changes.entry(detection.class_name.clone()).or_default().0 += 1;
+        }
+        for detection in &current.detections {
+            class_changes.entry(detection.class_name.clone()).or_default().1 += 1;
+        }
+        class_changes.retain(|_, (previous, current)| previous != current);
+
+        Self {
+            image_id: image_id.to_string(),
+            previous_model: previous
+                .and_then(|result| result.provenance.as_ref())
+                .map(|provenance| provenance.model_name.clone()),
+            class_changes,
+        }
+    }
+
+    pub fn is_changed(&self) -> bool {
+        !self.class_changes.is_empty()
+    }
+}
+
+/// Outcome of a reprocessing run.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReprocessReport {
+    pub dry_run: bool,
+    pub scanned_count: u32,
+    pub skipped_count: u32,
+    pub reprocessed_count: u32,
+    pub failed_count: u32,
+    /// Images whose detections changed.
+    pub diffs: Vec<DetectionDiff>,
+}
+
+/// Runs an object detector again over images already in the repositories and
+/// writes the new results back, tagged with the model that produced them.
+///
+/// Images that already have a result from the same model are skipped, so an
+/// interrupted run can be started again and continues where it stopped.
+pub struct ImageReprocessor {
+    image_info_repository: Box<dyn ImageInfoRepository>,
+    image_store_repository: Box<dyn ImageStoreRepository>,
+    object_detector: Box<dyn ObjectDetector>,
+    provenance: ModelProvenance,
+    tracker_settings: TrackerSettings,
+}
+
+impl ImageReprocessor {
+    pub fn new(
+        image_info_repository: Box<dyn ImageInfoRepository>,
+        image_store_repository: Box<dyn ImageStoreRepository>,
+        object_detector: Box<dyn ObjectDetector>,
+        model_version: Option<String>,
+        tracker_settings: TrackerSettings,
+    ) -> Self {
+        let provenance = ModelProvenance {
+            model_version,
+            ..object_detector.provenance()
+        };
+        Self {
+            image_info_repository,
+            image_store_repository,
+            object_detector,
+            provenance,
+            tracker_settings,
+        }
+    }
+
+    pub fn run(&mut self, options: &ReprocessOptions) -> RookLWResult<ReprocessReport> {
+        info!(
+            model_name = %self.provenance.model_name,
+            model_version = ?self.provenance.model_version,
+            options = ?options,
+            "Starting image reprocessing"
+        );
+
+        let mut report = ReprocessReport {
+            dry_run: options.dry_run,
+            ..Default::default()
+        };
+        let batch_size = options.batch_size.max(1);
+        let mut offset = 0;
+        let mut processed_events = HashSet::new();
+
+        loop {
+            let image_infos = self.image_info_repository.scan_image_info(
+                options.start_date,
+                options.end_date,
+                batch_size,
+                offset,
+            )?;
+            if image_infos.is_empty() {
+                break;
+            }
+            offset += image_infos.len() as u32;
+
+            for image_info in image_infos {
+                // The tracker needs every frame of an event, so events are reprocessed whole.
+                if processed_events.insert(image_info.event_id.clone()) {
+                    self.reprocess_event(&image_info.event_id, options, &mut report)?;
+                }
+            }
+
+            info!(
+                scanned_count = report.scanned_count,
+                reprocessed_count = report.reprocessed_count,
+                "Reprocessing progress"
+            );
+        }
+
+        info!(
+            dry_run = report.dry_run,
+            scanned_count = report.scanned_count,
+            skipped_count = report.skipped_count,
+            reprocessed_count = report.reprocessed_count,
+            changed_count = report.diffs.len(),
+            failed_count = report.failed_count,
+            "Image reprocessing completed"
+        );
+
+        Ok(report)
+    }
+
+    /// Reprocess the frames of an event in capture order, tracking objects across them.
+    /// Frames that are not reprocessed are tracked with their stored detections, so the
+    /// track ids of the others still follow the whole event.
+    fn reprocess_event(&mut self, event_id: &str, options: &ReprocessOptions, report: &mut ReprocessReport) -> RookLWResult<()> {
+        let mut tracker = SortTracker::new(self.tracker_settings.clone());
+
+        for image_info in self.image_info_repository.get_event_image_info(event_id)? {
+            let in_range = options.start_date.is_none_or(|start_date| image_info.capture_timestamp >= start_date)
+                && options.end_date.is_none_or(|end_date| image_info.capture_timestamp <= end_date);
+            if in_range {
+                report.scanned_count += 1;
+            }
+
+            if !in_range || !self.should_reprocess(&image_info, options) {
+                if in_range {
+                    report.skipped_count += 1;
+                }
+                if let Some(detection_result) = &image_info.detection {
+                    tracker.update(image_info.capture_index, &mut detection_result.detections.clone());
+                }
+                continue;
+            }
+
+            let image_id = image_info.image_id.clone();
+            match self.reprocess_image(image_info, &mut tracker, options.dry_run) {
+                Ok(diff) => {
+                    report.reprocessed_count += 1;
+                    if diff.is_changed() {
+                        info!(
+                            image_id = %diff.image_id,
+                            class_changes = ?diff.class_changes,
+                            "Detections changed"
+                        );
+                        report.diffs.push(diff);
+                    }
+                },
+                Err(e) => {
+                    report.failed_count += 1;
+                    error!(image_id = %image_id, error = %e, "Failed to reprocess image");
+                },
+            }
+        }
+
+        Ok(())
+    }
+
+    fn should_reprocess(&self, image_info: &ImageInfo, options: &ReprocessOptions) -> bool {
+        let provenance = image_info.detection
+            .as_ref()
+            .and_then(|result| result.provenance.as_ref());
+
+        if let Some(from_model) = &options.from_model {
+            let model_name = provenance
+                .map(|provenance| provenance.model_name.as_str())
+                .unwrap_or(UNKNOWN_MODEL_NAME);
+            if model_name != from_model {
+                return false;
+            }
+        }
+
+        // Already done by this model, e.g. in an earlier, interrupted run.
+        options.force || !provenance.is_some_and(|provenance| provenance.same_model(&self.provenance))
+    }
+
+    fn reprocess_image(&mut self, mut image_info: ImageInfo, tracker: &mut SortTracker, dry_run: bool) -> RookLWResult<DetectionDiff> {
+        let mut image_data = Vec::new();
+        self.image_store_repository
+            .read(&image_info.image_path)?
+            .read_to_end(&mut image_data)?;
+        let image = image::load_from_memory(&image_data)?;
+
+        let mut detection_result = self.object_detector.detect(&image)?;
+        if let Some(provenance) = &mut detection_result.provenance {
+            provenance.model_version = self.provenance.model_version.clone();
+        }
+
+        tracker.update(image_info.capture_index, &mut detection_result.detections);
+
+        let diff = DetectionDiff::new(&image_info.image_id, image_info.detection.as_ref(), &detection_result);
+
+        if !dry_run {
+            image_info.negative = detection_result.is_negative();
+            image_info.detection = Some(detection_result);
+            self.image_info_repository.save_image_info(&image_info)?;
+        }
+
+        Ok(diff)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rook_lw_models::image::Detection;
+
+    fn detection(class_name: &str) -> Detection {
+        Detection {
+            class_name: class_name.to_string(),
+            confidence: 0.8,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn detection_diff_reports_changed_classes() {
+        let previous = DetectionResult::new(vec![detection("sheep"), detection("deer")]);
+        let current = DetectionResult::new(vec![detection("deer"), detection("fox"), detection("fox")]);
+
+        let diff = DetectionDiff::new("event_0", Some(&previous), &current);
+
+        assert!(diff.is_changed());
+        assert_eq!(diff.previous_model, None);
+        assert_eq!(diff.class_changes, BTreeMap::from([
+            ("fox".to_string(), (0, 2)),
+            ("sheep".to_string(), (1, 0)),
+        ]));
+
+        let unchanged = DetectionDiff::new("event_0", Some(&previous), &previous);
+        assert!(!unchanged.is_changed());
+    }
+}
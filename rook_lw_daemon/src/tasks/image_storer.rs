@@ -1,22 +1,51 @@
 use crate::RookLWResult;
 use crate::image::conversions::dynamic_image_to_jpeg;
 use crate::events::{CaptureEvent, StorageEvent, ImageProcessingEvent};
+use crate::image::interestingness::InterestingnessScorer;
+use crate::image::embedding_clustering::nearest_cluster;
+use crate::tasks::event_recorder::EventRecorder;
 use crate::prodcon::{
     ProducerTask, ConsumerTask,
     OnProduceCallback, ProducerCallbacks
 };
 
-use rook_lw_models::image::ImageInfo;
+use rook_lw_models::image::{DetectionResult, EmbeddingCluster, ImageInfo};
 use rook_lw_image_repo::image_info::ImageInfoRepository;
 use rook_lw_image_repo::image_store::ImageStoreRepository;
 
+use chrono::{DateTime, Duration, FixedOffset};
+use tracing::warn;
+
 use std::time::Instant;
 
+// How often class counts for the rarity component are read from the repository.
+const CLASS_COUNT_REFRESH_MINUTES: i64 = 10;
+
+// How often embedding clusters are read from the repository.
+const CLUSTER_REFRESH_MINUTES: i64 = 10;
+
+struct ClusterAssignment {
+    min_similarity: f32,
+    clusters: Vec<EmbeddingCluster>,
+    clusters_updated: Option<DateTime<FixedOffset>>,
+}
+
+struct Interestingness {
+    scorer: InterestingnessScorer,
+    rarity_period: Duration,
+    class_counts_updated: Option<DateTime<FixedOffset>>,
+}
+
 pub trait OnImageStoredCallback : OnProduceCallback<StorageEvent> {}
 
 pub struct ImageStorer {
     image_store_repository: Box<dyn ImageStoreRepository>,
     image_info_repository: Box<dyn ImageInfoRepository>,
+    interestingness: Option<Interestingness>,
+    cluster_assignment: Option<ClusterAssignment>,
+    jpeg_quality: u8,
+    negative_jpeg_quality: u8,
+    event_recorder: Option<EventRecorder>,
     producer_callbacks: ProducerCallbacks<StorageEvent>,
 }
 
@@ -30,6 +59,26 @@ impl ConsumerTask<ImageProcessingEvent> for ImageStorer {
     fn consume(&mut self, item: ImageProcessingEvent) -> RookLWResult<()> {
         self.process_capture_event(item)
     }
+
+    fn reset(&mut self) -> RookLWResult<()> {
+        // Read class counts and clusters again on the next image.
+        if let Some(interestingness) = &mut self.interestingness {
+            interestingness.class_counts_updated = None;
+        }
+        if let Some(cluster_assignment) = &mut self.cluster_assignment {
+            cluster_assignment.clusters_updated = None;
+        }
+        if let Some(event_recorder) = &mut self.event_recorder {
+            event_recorder.reset();
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) {
+        if let Some(event_recorder) = &mut self.event_recorder {
+            event_recorder.finish();
+        }
+    }
 }
 
 impl ImageStorer {
@@ -37,10 +86,95 @@ impl ImageStorer {
         Self { 
             image_store_repository,
             image_info_repository,
+            interestingness: None,
+            cluster_assignment: None,
+            jpeg_quality: 85,
+            negative_jpeg_quality: 85,
+            event_recorder: None,
             producer_callbacks: ProducerCallbacks::new(),
         }
     }
 
+    /// Keep an event record up to date for the events of the stored images.
+    pub fn set_event_recorder(&mut self, event_recorder: EventRecorder) -> &mut Self {
+        self.event_recorder = Some(event_recorder);
+        self
+    }
+
+    /// JPEG quality of stored images, and of images without detections.
+    pub fn set_jpeg_quality(&mut self, jpeg_quality: u8, negative_jpeg_quality: u8) -> &mut Self {
+        self.jpeg_quality = jpeg_quality;
+        self.negative_jpeg_quality = negative_jpeg_quality;
+        self
+    }
+
+    /// Score how interesting each image is, with class rarity taken over `rarity_period`.
+    pub fn set_interestingness_scorer(&mut self, scorer: InterestingnessScorer, rarity_period: Duration) -> &mut Self {
+        self.interestingness = Some(Interestingness {
+            scorer,
+            rarity_period,
+            class_counts_updated: None,
+        });
+        self
+    }
+
+    fn score_interestingness(&mut self, image_processing_event: &ImageProcessingEvent) -> Option<f32> {
+        let interestingness = self.interestingness.as_mut()?;
+        let capture_event = &image_processing_event.capture_event;
+        let now = capture_event.capture_timestamp;
+
+        let refresh = match interestingness.class_counts_updated {
+            Some(updated) => now - updated >= Duration::minutes(CLASS_COUNT_REFRESH_MINUTES),
+            None => true,
+        };
+        if refresh {
+            match self.image_info_repository.get_class_counts(now - interestingness.rarity_period) {
+                Ok(class_counts) => interestingness.scorer.set_class_counts(class_counts),
+                Err(e) => warn!(error = %e, "Failed to read class counts for interestingness"),
+            }
+            interestingness.class_counts_updated = Some(now);
+        }
+
+        let score = interestingness.scorer.score(
+            &capture_event.image,
+            image_processing_event.detection_result.as_ref(),
+        );
+        Some(score)
+    }
+
+    /// Assign images with an embedding to the nearest stored embedding cluster,
+    /// when its centroid is at least `min_similarity` similar.
+    pub fn set_cluster_assignment(&mut self, min_similarity: f32) -> &mut Self {
+        self.cluster_assignment = Some(ClusterAssignment {
+            min_similarity,
+            clusters: Vec::new(),
+            clusters_updated: None,
+        });
+        self
+    }
+
+    fn assign_cluster(&mut self, image_processing_event: &ImageProcessingEvent) -> Option<u32> {
+        let cluster_assignment = self.cluster_assignment.as_mut()?;
+        let embedding = image_processing_event.detection_result
+            .as_ref()
+            .and_then(|result| result.embeddings.as_ref())?;
+        let now = image_processing_event.capture_event.capture_timestamp;
+
+        let refresh = match cluster_assignment.clusters_updated {
+            Some(updated) => now - updated >= Duration::minutes(CLUSTER_REFRESH_MINUTES),
+            None => true,
+        };
+        if refresh {
+            match self.image_info_repository.get_embedding_clusters() {
+                Ok(clusters) => cluster_assignment.clusters = clusters,
+                Err(e) => warn!(error = %e, "Failed to read embedding clusters"),
+            }
+            cluster_assignment.clusters_updated = Some(now);
+        }
+
+        nearest_cluster(&cluster_assignment.clusters, embedding, cluster_assignment.min_similarity)
+    }
+
     fn process_capture_event(&mut self, image_processing_event: ImageProcessingEvent) -> RookLWResult<()> {
 
         let capture_event = &image_processing_event.capture_event;
@@ -52,8 +186,13 @@ impl ImageStorer {
             "Processing capture event"
         );
 
+        let negative = image_processing_event.detection_result
+            .as_ref()
+            .is_none_or(DetectionResult::is_negative);
+        let jpeg_quality = if negative { self.negative_jpeg_quality } else { self.jpeg_quality };
+
         let timer = Instant::now();
-        let jpeg_data = dynamic_image_to_jpeg(&capture_event.image, Some(85))?;
+        let jpeg_data = dynamic_image_to_jpeg(&capture_event.image, Some(jpeg_quality))?;
         let elapsed = timer.elapsed();
 
         tracing::info!(
@@ -80,10 +219,10 @@ impl ImageStorer {
         );
 
         // Save image info to repository
-        let image_id = format!(
-            "{}_{}",
-            capture_event.event_id, capture_event.capture_index
-        );
+        let image_id = capture_event.image_id();
+
+        let interestingness = self.score_interestingness(&image_processing_event);
+        let cluster_id = self.assign_cluster(&image_processing_event);
 
         let timer = Instant::now();
 
@@ -96,6 +235,12 @@ impl ImageStorer {
             capture_timestamp: capture_event.capture_timestamp,
             detection: image_processing_event.detection_result.clone(),
             image_path: image_path_rel.to_string(),
+            verified_detections: None,
+            duplicate_of: image_processing_event.duplicate_of.clone(),
+            redacted: image_processing_event.redacted,
+            negative,
+            interestingness,
+            cluster_id,
         };
 
         self.image_info_repository.save_image_info(&image_info)?;
@@ -108,9 +253,22 @@ impl ImageStorer {
             "Saved image info"
         );
 
+        // The image is stored either way, so a failed event update doesn't fail the image.
+        if let Some(event_recorder) = &mut self.event_recorder
+            && let Err(e) = event_recorder.record(
+                &image_info,
+                &capture_event.image,
+                capture_event.trigger,
+                self.image_info_repository.as_ref(),
+            )
+        {
+            warn!(event_id = %capture_event.event_id, error = %e, "Failed to update event");
+        }
+
         let storage_event = StorageEvent {
             capture_event: capture_event.clone(),
             image_path: image_path_rel,
+            queued_at: None,
         };
 
         self.produce(storage_event)?;
@@ -139,4 +297,68 @@ impl ImageStorer {
 
         filename
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use image::{DynamicImage, RgbImage};
+    use rook_lw_image_repo::image_info::ImageInfoRepositorySqlite;
+    use rook_lw_image_repo::image_store::ImageStoreRepositoryFile;
+    use rook_lw_image_repo::sqlite::create_pool;
+    use rook_lw_models::event::EventTrigger;
+    use rook_lw_models::image::{Detection, MotionDetectionScore};
+    use uuid::Uuid;
+
+    use std::sync::Arc;
+
+    fn item(capture_index: u32, detection_result: DetectionResult) -> ImageProcessingEvent {
+        let timestamp: DateTime<FixedOffset> = chrono::Local::now().into();
+        ImageProcessingEvent {
+            capture_event: CaptureEvent {
+                event_id: Uuid::new_v4(),
+                event_timestamp: timestamp,
+                trigger: EventTrigger::ImageDiff,
+                motion_score: MotionDetectionScore::default(),
+                capture_index,
+                capture_timestamp: timestamp,
+                image: Arc::new(DynamicImage::ImageRgb8(RgbImage::new(16, 16))),
+            },
+            detection_result: Some(detection_result),
+            duplicate_of: None,
+            redacted: false,
+            queued_at: None,
+        }
+    }
+
+    #[test]
+    fn captures_with_only_suppressed_detections_are_not_stored_as_negatives() {
+        let dir = std::env::temp_dir().join(format!("rook_lw_image_storer_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let pool = create_pool(&dir.join("image_info.db").to_string_lossy()).unwrap();
+        let image_info_repository = ImageInfoRepositorySqlite::new(pool.clone()).unwrap();
+        let mut storer = ImageStorer::new(
+            Box::new(ImageStoreRepositoryFile::new(dir.join("images")).unwrap()),
+            Box::new(ImageInfoRepositorySqlite::new(pool).unwrap()),
+        );
+
+        let suppressed = DetectionResult {
+            suppressed: vec![Detection { class_name: "sheep".to_string(), ..Default::default() }],
+            ..Default::default()
+        };
+        let suppressed_item = item(0, suppressed);
+        let negative_item = item(1, DetectionResult::default());
+        storer.consume(suppressed_item.clone()).unwrap();
+        storer.consume(negative_item.clone()).unwrap();
+
+        let negative = |item: &ImageProcessingEvent| image_info_repository
+            .get_image_info(&item.capture_event.image_id())
+            .unwrap()
+            .unwrap()
+            .negative;
+        assert!(!negative(&suppressed_item));
+        assert!(negative(&negative_item));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,28 @@
+use crate::RookLWResult;
+use crate::events::StorageEvent;
+use crate::prodcon::ConsumerTask;
+
+use tracing::info;
+
+/// Notifier that logs every stored image with its path, so stored images can
+/// be followed in the log without looking up the repository.
+#[derive(Default)]
+pub struct LogNotifier {}
+
+impl LogNotifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ConsumerTask<StorageEvent> for LogNotifier {
+    fn consume(&mut self, item: StorageEvent) -> RookLWResult<()> {
+        info!(
+            event_id = %item.capture_event.event_id,
+            capture_index = item.capture_event.capture_index,
+            image_path = %item.image_path,
+            "Image stored"
+        );
+        Ok(())
+    }
+}
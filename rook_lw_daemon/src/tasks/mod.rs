@@ -4,5 +4,12 @@ pub mod image_capturer;
 pub mod image_diff_motion_watcher;
 pub mod radar_motion_watcher;
 pub mod image_storer;
-pub mod batch_image_object_detector;
-pub mod image_detector;
\ No newline at end of file
+pub mod event_recorder;
+pub mod image_detector;
+pub mod object_tracker;
+pub mod image_reprocessor;
+pub mod near_duplicate_filter;
+pub mod embedding_clusterer;
+pub mod privacy_redactor;
+pub mod dataset_exporter;
+pub mod log_notifier;
\ No newline at end of file
@@ -1,10 +1,98 @@
-use crate::RookLWResult;
+use crate::{RookLWError, RookLWResult};
 use crate::events::ImageProcessingEvent;
+use crate::prodcon::{ProducerTask, ShutdownSignal};
 
-use std::thread::JoinHandle;
-use crossbeam_channel::Sender;
+use crossbeam_channel::{Receiver, Sender, TrySendError};
+use image::DynamicImage;
 
-pub trait MotionWatcher: Send {
-    fn connect(&mut self, sender: Sender<ImageProcessingEvent>);
-    fn start(self: Box<Self>) -> JoinHandle<RookLWResult<()>>;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+// Commands not picked up by a watcher yet; more are refused.
+const COMMAND_CAPACITY: usize = 16;
+
+pub trait MotionWatcher: ProducerTask<ImageProcessingEvent> {
+    /// Watch for motion until `shutdown` is requested. Can be called again
+    /// after it returns an error, to restart watching.
+    fn run(&mut self, shutdown: &ShutdownSignal) -> RookLWResult<()>;
+
+    /// Take pause requests and commands from `control` while running.
+    fn set_control(&mut self, control: WatcherControl);
+}
+
+/// Command for the motion watchers, carried out between watching rounds.
+pub enum WatcherCommand {
+    /// Capture an event as if motion was detected.
+    Trigger,
+
+    /// Capture a single frame and send it back, without starting an event.
+    Snapshot(Sender<RookLWResult<DynamicImage>>),
+}
+
+/// Shared handle to pause the motion watchers and send them commands.
+///
+/// All watchers of a pipeline share one handle; a command is carried out by
+/// whichever watcher takes it first.
+#[derive(Clone)]
+pub struct WatcherControl {
+    paused: Arc<AtomicBool>,
+    sender: Sender<WatcherCommand>,
+    receiver: Receiver<WatcherCommand>,
+}
+
+impl Default for WatcherControl {
+    fn default() -> Self {
+        let (sender, receiver) = crossbeam_channel::bounded(COMMAND_CAPACITY);
+        Self {
+            paused: Arc::new(AtomicBool::new(false)),
+            sender,
+            receiver,
+        }
+    }
+}
+
+impl WatcherControl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    pub fn trigger(&self) -> RookLWResult<()> {
+        self.send(WatcherCommand::Trigger)
+    }
+
+    /// Wait up to `timeout` for a watcher to capture a frame.
+    pub fn snapshot(&self, timeout: Duration) -> RookLWResult<DynamicImage> {
+        let (sender, receiver) = crossbeam_channel::bounded(1);
+        self.send(WatcherCommand::Snapshot(sender))?;
+        receiver
+            .recv_timeout(timeout)
+            .map_err(|_| RookLWError::Concurrency("No motion watcher took the snapshot in time".to_string()))?
+    }
+
+    /// True when a command is waiting, so a watcher can cut a round short.
+    pub fn has_commands(&self) -> bool {
+        !self.receiver.is_empty()
+    }
+
+    /// Commands are taken from here; watchers that block can select on it.
+    pub fn commands(&self) -> &Receiver<WatcherCommand> {
+        &self.receiver
+    }
+
+    fn send(&self, command: WatcherCommand) -> RookLWResult<()> {
+        match self.sender.try_send(command) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(_)) => Err(RookLWError::Concurrency("Too many motion watcher commands waiting".to_string())),
+            Err(TrySendError::Disconnected(_)) => Err(RookLWError::Concurrency("Motion watcher commands closed".to_string())),
+        }
+    }
 }
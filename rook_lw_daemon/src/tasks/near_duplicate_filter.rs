@@ -0,0 +1,300 @@
+use crate::RookLWResult;
+use crate::events::ImageProcessingEvent;
+use crate::image::perceptual_hash::{dhash, hamming_distance};
+
+use crate::prodcon::{
+    ProducerTask, ConsumerTask,
+    ProducerCallbacks, StageMetrics
+};
+
+use rook_lw_image_repo::image_info::cosine_similarity;
+
+use chrono::{DateTime, Duration, FixedOffset};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info};
+use uuid::Uuid;
+
+use std::collections::VecDeque;
+
+/// What to do with an image that is a near-duplicate of a recent one.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum NearDuplicateMode {
+    /// Do not store the image.
+    Drop,
+
+    /// Store the image with `duplicate_of` set.
+    Mark,
+}
+
+#[derive(Debug, Clone)]
+pub struct NearDuplicateSettings {
+    /// Minimum cosine similarity of embeddings for a near-duplicate.
+    pub embedding_threshold: f32,
+
+    /// Maximum number of differing perceptual hash bits for a near-duplicate,
+    /// used when either image has no embedding.
+    pub hash_max_distance: u32,
+
+    /// How long a stored image is compared against.
+    pub window: Duration,
+
+    pub mode: NearDuplicateMode,
+}
+
+impl Default for NearDuplicateSettings {
+    fn default() -> Self {
+        Self {
+            embedding_threshold: 0.97,
+            hash_max_distance: 4,
+            window: Duration::minutes(10),
+            mode: NearDuplicateMode::Drop,
+        }
+    }
+}
+
+/// Number of images checked, and what happened to them.
+#[derive(Debug, Clone, Default)]
+struct NearDuplicateCounters {
+    checked: u64,
+    kept: u64,
+    dropped: u64,
+    marked: u64,
+}
+
+// Upper bound on remembered images, the oldest are dropped first.
+const MAX_RECENT_IMAGES: usize = 256;
+
+#[derive(Debug, Clone)]
+struct RecentImage {
+    image_id: String,
+    event_id: Uuid,
+    timestamp: DateTime<FixedOffset>,
+    embedding: Option<Vec<f32>>,
+    hash: u64,
+}
+
+/// Images recently kept by the filter.
+struct RecentImages {
+    settings: NearDuplicateSettings,
+    images: VecDeque<RecentImage>,
+}
+
+impl RecentImages {
+    fn new(settings: NearDuplicateSettings) -> Self {
+        Self {
+            settings,
+            images: VecDeque::new(),
+        }
+    }
+
+    fn prune(&mut self, timestamp: DateTime<FixedOffset>) {
+        let window = self.settings.window;
+        self.images.retain(|image| timestamp - image.timestamp <= window);
+    }
+
+    /// Image id of a recent image from another event that `image` is a near-duplicate of.
+    ///
+    /// Frames of the same event are not compared with each other, they are
+    /// expected to be similar.
+    fn find_duplicate(&self, image: &RecentImage) -> Option<&str> {
+        self.images
+            .iter()
+            .filter(|recent| recent.event_id != image.event_id)
+            .find(|recent| match (&recent.embedding, &image.embedding) {
+                (Some(a), Some(b)) if a.len() == b.len() => {
+                    cosine_similarity(a, b) >= self.settings.embedding_threshold
+                },
+                _ => hamming_distance(recent.hash, image.hash) <= self.settings.hash_max_distance,
+            })
+            .map(|recent| recent.image_id.as_str())
+    }
+
+    fn push(&mut self, image: RecentImage) {
+        self.images.push_back(image);
+        while self.images.len() > MAX_RECENT_IMAGES {
+            self.images.pop_front();
+        }
+    }
+}
+
+/// Drops or marks images that are near-duplicates of images stored shortly
+/// before, such as an animal standing in front of the camera for hours.
+///
+/// Images are compared by embedding when both have one, otherwise by
+/// perceptual hash.
+pub struct NearDuplicateFilter {
+    mode: NearDuplicateMode,
+    recent_images: RecentImages,
+    counters: NearDuplicateCounters,
+    metrics: StageMetrics,
+    producer_callbacks: ProducerCallbacks<ImageProcessingEvent>,
+}
+
+impl ProducerTask<ImageProcessingEvent> for NearDuplicateFilter {
+    fn get_producer_callbacks(&mut self) -> &mut ProducerCallbacks<ImageProcessingEvent> {
+        &mut self.producer_callbacks
+    }
+
+    fn set_metrics(&mut self, metrics: StageMetrics) {
+        self.metrics = metrics.clone();
+        self.producer_callbacks.set_metrics(metrics);
+    }
+}
+
+impl ConsumerTask<ImageProcessingEvent> for NearDuplicateFilter {
+    fn consume(&mut self, item: ImageProcessingEvent) -> RookLWResult<()> {
+        self.process_image_processing_event(item)
+    }
+
+    fn reset(&mut self) -> RookLWResult<()> {
+        self.recent_images.images.clear();
+        Ok(())
+    }
+}
+
+impl NearDuplicateFilter {
+    pub fn new(settings: NearDuplicateSettings) -> Self {
+        Self {
+            mode: settings.mode,
+            recent_images: RecentImages::new(settings),
+            counters: NearDuplicateCounters::default(),
+            metrics: StageMetrics::default(),
+            producer_callbacks: ProducerCallbacks::new(),
+        }
+    }
+
+    fn process_image_processing_event(&mut self, mut item: ImageProcessingEvent) -> RookLWResult<()> {
+        let capture_event = &item.capture_event;
+
+        let image = RecentImage {
+            image_id: capture_event.image_id(),
+            event_id: capture_event.event_id,
+            timestamp: capture_event.capture_timestamp,
+            embedding: item.detection_result.as_ref().and_then(|result| result.embeddings.clone()),
+            hash: dhash(&capture_event.image),
+        };
+
+        self.counters.checked += 1;
+        self.recent_images.prune(image.timestamp);
+
+        let duplicate_of = self.recent_images.find_duplicate(&image).map(|image_id| image_id.to_string());
+
+        let Some(duplicate_of) = duplicate_of else {
+            debug!(image_id = %image.image_id, hash = %format!("{:016x}", image.hash), "Image kept");
+            self.counters.kept += 1;
+            self.recent_images.push(image);
+            return self.produce(item);
+        };
+
+        match self.mode {
+            NearDuplicateMode::Drop => {
+                self.counters.dropped += 1;
+                self.metrics.record_filtered();
+            },
+            NearDuplicateMode::Mark => self.counters.marked += 1,
+        }
+
+        info!(
+            image_id = %image.image_id,
+            duplicate_of = %duplicate_of,
+            mode = ?self.mode,
+            checked_count = self.counters.checked,
+            dropped_count = self.counters.dropped,
+            marked_count = self.counters.marked,
+            "Near-duplicate image"
+        );
+
+        if self.mode == NearDuplicateMode::Mark {
+            item.duplicate_of = Some(duplicate_of);
+            self.produce(item)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::CaptureEvent;
+    use crate::prodcon::PipelineMetrics;
+
+    use image::{DynamicImage, RgbImage};
+    use rook_lw_models::event::EventTrigger;
+    use rook_lw_models::image::MotionDetectionScore;
+
+    use std::sync::{Arc, Mutex};
+
+    fn recent_image(event_id: Uuid, minutes: i64, embedding: Option<Vec<f32>>, hash: u64) -> RecentImage {
+        RecentImage {
+            image_id: format!("{}_{}", event_id, minutes),
+            event_id,
+            timestamp: DateTime::parse_from_rfc3339("2024-06-01T12:00:00+00:00").unwrap() + Duration::minutes(minutes),
+            embedding,
+            hash,
+        }
+    }
+
+    #[test]
+    fn recent_images_find_duplicates_from_other_events() {
+        let mut recent_images = RecentImages::new(NearDuplicateSettings::default());
+        let first_event = Uuid::new_v4();
+        recent_images.push(recent_image(first_event, 0, Some(vec![1.0, 0.0, 0.2]), 0xff00));
+
+        // Same event is never a duplicate.
+        assert!(recent_images.find_duplicate(&recent_image(first_event, 1, Some(vec![1.0, 0.0, 0.2]), 0xff00)).is_none());
+
+        let other_event = Uuid::new_v4();
+        assert!(recent_images.find_duplicate(&recent_image(other_event, 2, Some(vec![1.0, 0.01, 0.2]), 0)).is_some());
+        assert!(recent_images.find_duplicate(&recent_image(other_event, 2, Some(vec![0.0, 1.0, 0.0]), 0xff00)).is_none());
+
+        // Without an embedding the hash is used.
+        assert!(recent_images.find_duplicate(&recent_image(other_event, 2, None, 0xff01)).is_some());
+
+        // Outside the window.
+        recent_images.prune(recent_image(other_event, 11, None, 0).timestamp);
+        assert!(recent_images.find_duplicate(&recent_image(other_event, 11, None, 0xff00)).is_none());
+    }
+
+    fn item(event_id: Uuid) -> ImageProcessingEvent {
+        let timestamp: DateTime<FixedOffset> = chrono::Local::now().into();
+        ImageProcessingEvent {
+            capture_event: CaptureEvent {
+                event_id,
+                event_timestamp: timestamp,
+                trigger: EventTrigger::ImageDiff,
+                motion_score: MotionDetectionScore::default(),
+                capture_index: 0,
+                capture_timestamp: timestamp,
+                image: Arc::new(DynamicImage::ImageRgb8(RgbImage::new(32, 32))),
+            },
+            detection_result: None,
+            duplicate_of: None,
+            redacted: false,
+            queued_at: None,
+        }
+    }
+
+    #[test]
+    fn reset_forgets_recent_images() {
+        let kept = Arc::new(Mutex::new(0));
+        let metrics = PipelineMetrics::new();
+        let mut filter = NearDuplicateFilter::new(NearDuplicateSettings::default());
+        filter.set_metrics(metrics.stage("near_duplicate_filter"));
+        let counter = kept.clone();
+        filter.get_producer_callbacks().on_produce(move |_: &ImageProcessingEvent| {
+            *counter.lock().unwrap() += 1;
+            Ok(())
+        });
+
+        filter.consume(item(Uuid::new_v4())).unwrap();
+        filter.consume(item(Uuid::new_v4())).unwrap();
+        assert_eq!(*kept.lock().unwrap(), 1);
+        assert!(metrics.render().contains("rook_lw_stage_filtered_total{stage=\"near_duplicate_filter\"} 1\n"));
+
+        filter.reset().unwrap();
+        filter.consume(item(Uuid::new_v4())).unwrap();
+        assert_eq!(*kept.lock().unwrap(), 2);
+    }
+}
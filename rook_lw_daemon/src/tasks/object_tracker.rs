@@ -0,0 +1,104 @@
+use crate::RookLWResult;
+use crate::events::ImageProcessingEvent;
+use crate::image::tracking::{SortTracker, TrackerSettings};
+
+use crate::prodcon::{
+    ProducerTask, ConsumerTask,
+    ProducerCallbacks, LiveSettings
+};
+
+use rook_lw_models::event::describe_class_counts;
+
+use tracing::{debug, info};
+use uuid::Uuid;
+
+/// Assigns track ids to the detections of an event, so the same animal across
+/// frames can be told apart from several animals.
+///
+/// Expects the frames of an event in capture order, which is how the detector
+/// produces them.
+pub struct ObjectTracker {
+    settings: TrackerSettings,
+    live_settings: Option<LiveSettings<TrackerSettings>>,
+    current_event: Option<(Uuid, SortTracker)>,
+    producer_callbacks: ProducerCallbacks<ImageProcessingEvent>,
+}
+
+impl ProducerTask<ImageProcessingEvent> for ObjectTracker {
+    fn get_producer_callbacks(&mut self) -> &mut ProducerCallbacks<ImageProcessingEvent> {
+        &mut self.producer_callbacks
+    }
+}
+
+impl ConsumerTask<ImageProcessingEvent> for ObjectTracker {
+    fn consume(&mut self, item: ImageProcessingEvent) -> RookLWResult<()> {
+        self.process_image_processing_event(item)
+    }
+
+    fn reset(&mut self) -> RookLWResult<()> {
+        self.finish_event();
+        Ok(())
+    }
+
+    fn finish(&mut self) {
+        self.finish_event();
+    }
+}
+
+impl ObjectTracker {
+    pub fn new(settings: TrackerSettings) -> Self {
+        Self {
+            settings,
+            live_settings: None,
+            current_event: None,
+            producer_callbacks: ProducerCallbacks::new(),
+        }
+    }
+
+    /// Take replaced settings into use from the next event on.
+    pub fn set_live_settings(&mut self, live_settings: LiveSettings<TrackerSettings>) -> &mut Self {
+        self.live_settings = Some(live_settings);
+        self
+    }
+
+    fn process_image_processing_event(&mut self, mut item: ImageProcessingEvent) -> RookLWResult<()> {
+        let event_id = item.capture_event.event_id;
+
+        let is_new_event = match &self.current_event {
+            Some((current_event_id, _)) => *current_event_id != event_id,
+            None => true,
+        };
+
+        if is_new_event {
+            self.finish_event();
+            if let Some(settings) = self.live_settings.as_mut().and_then(|live_settings| live_settings.changed()) {
+                info!(settings = ?settings, "Using new tracker settings");
+                self.settings = (*settings).clone();
+            }
+            self.current_event = Some((event_id, SortTracker::new(self.settings.clone())));
+        }
+
+        if let (Some((_, tracker)), Some(detection_result)) = (&mut self.current_event, &mut item.detection_result) {
+            tracker.update(item.capture_event.capture_index, &mut detection_result.detections);
+
+            debug!(
+                event_id = %event_id,
+                capture_index = item.capture_event.capture_index,
+                track_ids = ?detection_result.detections.iter().map(|d| d.track_id).collect::<Vec<_>>(),
+                "Tracked detections"
+            );
+        }
+
+        self.produce(item)
+    }
+
+    fn finish_event(&mut self) {
+        if let Some((event_id, tracker)) = self.current_event.take() {
+            info!(
+                event_id = %event_id,
+                distinct_objects = %describe_class_counts(tracker.class_counts()),
+                "Event tracking summary"
+            );
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use crate::RookLWResult;
+use crate::events::ImageProcessingEvent;
+use crate::image::redaction::{padded_region, redact_region, RedactionMethod};
+
+use crate::prodcon::{
+    ProducerTask, ConsumerTask,
+    ProducerCallbacks
+};
+
+use image::GenericImageView;
+use tracing::info;
+
+use std::sync::Arc;
+
+/// What to do with an image that contains a redacted class.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RedactionMode {
+    /// Obscure the detection boxes and store the image.
+    Redact(RedactionMethod),
+
+    /// Do not store the image.
+    Drop,
+}
+
+#[derive(Debug, Clone)]
+pub struct RedactionSettings {
+    /// Class names whose detections are redacted.
+    pub classes: Vec<String>,
+
+    /// Fraction of the box width and height added on each side before redacting.
+    pub padding: f32,
+
+    pub mode: RedactionMode,
+}
+
+impl Default for RedactionSettings {
+    fn default() -> Self {
+        Self {
+            classes: vec!["person".to_string()],
+            padding: 0.1,
+            mode: RedactionMode::Redact(RedactionMethod::Pixelate(16)),
+        }
+    }
+}
+
+/// Obscures people, or other configured classes, before an image is stored.
+///
+/// Sits directly before the storer, which is the only stage that writes
+/// images, so no unredacted copy reaches the disk. Suppressed detections are
+/// redacted too, and so are the detections of the model that a class policy
+/// dropped or relabeled. Images that were never run through the detector are
+/// dropped, since they cannot be checked.
+pub struct PrivacyRedactor {
+    settings: RedactionSettings,
+    producer_callbacks: ProducerCallbacks<ImageProcessingEvent>,
+}
+
+impl ProducerTask<ImageProcessingEvent> for PrivacyRedactor {
+    fn get_producer_callbacks(&mut self) -> &mut ProducerCallbacks<ImageProcessingEvent> {
+        &mut self.producer_callbacks
+    }
+}
+
+impl ConsumerTask<ImageProcessingEvent> for PrivacyRedactor {
+    fn consume(&mut self, item: ImageProcessingEvent) -> RookLWResult<()> {
+        self.process_image_processing_event(item)
+    }
+}
+
+impl PrivacyRedactor {
+    pub fn new(settings: RedactionSettings) -> Self {
+        Self {
+            settings,
+            producer_callbacks: ProducerCallbacks::new(),
+        }
+    }
+
+    fn process_image_processing_event(&mut self, mut item: ImageProcessingEvent) -> RookLWResult<()> {
+        let image_id = item.capture_event.image_id();
+
+        let Some(detection_result) = &item.detection_result else {
+            info!(image_id = %image_id, "Image without detections dropped before redaction");
+            return Ok(());
+        };
+
+        let boxes: Vec<(i32, i32, i32, i32)> = detection_result.detections
+            .iter()
+            .chain(detection_result.suppressed.iter())
+            .chain(detection_result.model_detections.iter().flatten())
+            .filter(|detection| self.settings.classes.contains(&detection.class_name))
+            .map(|detection| (detection.x, detection.y, detection.width, detection.height))
+            .collect();
+
+        if boxes.is_empty() {
+            return self.produce(item);
+        }
+
+        let method = match self.settings.mode {
+            RedactionMode::Drop => {
+                info!(image_id = %image_id, region_count = boxes.len(), "Image with redacted classes dropped");
+                return Ok(());
+            },
+            RedactionMode::Redact(method) => method,
+        };
+
+        let mut image = item.capture_event.image.as_ref().clone();
+        let (width, height) = image.dimensions();
+        for bbox in &boxes {
+            if let Some(region) = padded_region(*bbox, self.settings.padding, width, height) {
+                redact_region(&mut image, region, method);
+            }
+        }
+        item.capture_event.image = Arc::new(image);
+        item.redacted = true;
+
+        info!(image_id = %image_id, region_count = boxes.len(), method = ?method, "Image redacted");
+
+        self.produce(item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::CaptureEvent;
+
+    use image::{DynamicImage, Rgb, RgbImage};
+    use rook_lw_models::event::EventTrigger;
+    use rook_lw_models::image::{Detection, DetectionResult, MotionDetectionScore};
+    use uuid::Uuid;
+
+    use std::sync::Mutex;
+
+    fn item(detection_result: DetectionResult) -> ImageProcessingEvent {
+        // Stripes, so pixelating changes the pixels.
+        let image = RgbImage::from_fn(64, 64, |x, _| if x % 2 == 0 { Rgb([255, 255, 255]) } else { Rgb([0, 0, 0]) });
+        ImageProcessingEvent {
+            capture_event: CaptureEvent {
+                event_id: Uuid::new_v4(),
+                event_timestamp: chrono::Local::now().into(),
+                trigger: EventTrigger::ImageDiff,
+                motion_score: MotionDetectionScore::default(),
+                capture_index: 0,
+                capture_timestamp: chrono::Local::now().into(),
+                image: Arc::new(DynamicImage::ImageRgb8(image)),
+            },
+            detection_result: Some(detection_result),
+            duplicate_of: None,
+            redacted: false,
+            queued_at: None,
+        }
+    }
+
+    #[test]
+    fn redacts_people_a_class_policy_dropped() {
+        let person = Detection {
+            class_name: "person".to_string(),
+            confidence: 0.9,
+            x: 8,
+            y: 8,
+            width: 16,
+            height: 32,
+            ..Default::default()
+        };
+        // As left by a class policy that ignores people.
+        let dropped_by_policy = DetectionResult {
+            model_detections: Some(vec![person]),
+            ..Default::default()
+        };
+
+        let produced = Arc::new(Mutex::new(Vec::new()));
+        let mut redactor = PrivacyRedactor::new(RedactionSettings::default());
+        let sink = produced.clone();
+        redactor.get_producer_callbacks().on_produce(move |item: &ImageProcessingEvent| {
+            sink.lock().unwrap().push(item.clone());
+            Ok(())
+        });
+
+        let original = item(dropped_by_policy);
+        redactor.consume(original.clone()).unwrap();
+        redactor.consume(item(DetectionResult::default())).unwrap();
+
+        let produced = produced.lock().unwrap();
+        assert_eq!(produced.len(), 2);
+        assert!(produced[0].redacted);
+        assert_ne!(*produced[0].capture_event.image, *original.capture_event.image);
+        assert!(!produced[1].redacted);
+    }
+}
@@ -1,29 +1,35 @@
 use crate::RookLWResult;
 use crate::events::{ImageProcessingEvent, MotionDetectionEvent};
-use crate::prodcon::{ProducerTask, ProducerCallbacks};
+use crate::prodcon::{ProducerTask, ProducerCallbacks, ShutdownSignal};
 use crate::tasks::image_capturer::ImageCapturer;
-use crate::tasks::motion_watcher::MotionWatcher;
+use crate::tasks::motion_watcher::{MotionWatcher, WatcherControl};
 use crate::error::RookLWError;
 
+use rook_lw_models::event::EventTrigger;
 use rook_lw_models::image::MotionDetectionScore;
 
-use std::thread::{JoinHandle, spawn};
 use std::collections::HashMap;
+use std::thread::spawn;
+use std::time::Duration;
 
 use chrono::{DateTime, FixedOffset};
+use crossbeam_channel::Receiver;
 use tracing::info;
 
 use uuid::Uuid;
 
-use gpiod::{Chip, Options, EdgeDetect, Input};
+use gpiod::{Chip, Options, EdgeDetect, Event};
+
+// How often shutdown is checked for while waiting for radar edges and commands.
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(100);
 
 impl MotionWatcher for RadarMotionWatcher {
-    fn connect(&mut self, sender: crossbeam_channel::Sender<ImageProcessingEvent>) {
-        ProducerTask::connect(self, sender);
+    fn run(&mut self, shutdown: &ShutdownSignal) -> RookLWResult<()> {
+        RadarMotionWatcher::run(self, shutdown)
     }
 
-    fn start(self: Box<Self>) -> JoinHandle<RookLWResult<()>> {
-        RadarMotionWatcher::start(*self)
+    fn set_control(&mut self, control: WatcherControl) {
+        self.control = control;
     }
 }
 
@@ -31,6 +37,11 @@ pub struct RadarMotionWatcher {
     gpio_chip_path: Option<String>,
     gpio_pin: u32,
     image_capturer: ImageCapturer,
+    control: WatcherControl,
+
+    /// Edges read by the GPIO reader thread. Kept across restarts, as the
+    /// reader holds the GPIO line until it reads its next edge.
+    radar_edges: Option<Receiver<std::io::Result<Event>>>,
 }
 
 impl ProducerTask<ImageProcessingEvent> for RadarMotionWatcher {
@@ -50,28 +61,59 @@ impl RadarMotionWatcher {
             gpio_chip_path,
             gpio_pin,
             image_capturer,
+            control: WatcherControl::new(),
+            radar_edges: None,
         }
     }
 
-    pub fn start(mut self) -> JoinHandle<RookLWResult<()>> {
-        spawn(move || {
-            match self.run() {
-                Ok(_) => {
-                    info!("Radar motion watcher exiting normally");
-                    Ok(())
+    /// Reading GPIO events blocks, so they are read on a thread of their own
+    /// and the watcher can notice shutdown and commands in between.
+    pub fn run(&mut self, shutdown: &ShutdownSignal) -> RookLWResult<()> {
+        info!("Starting radar motion watcher");
+
+        let radar_edges = match &self.radar_edges {
+            Some(radar_edges) => radar_edges.clone(),
+            None => {
+                let radar_edges = self.start_gpio_reader()?;
+                self.radar_edges = Some(radar_edges.clone());
+                radar_edges
+            },
+        };
+        let commands = self.control.commands().clone();
+
+        while !shutdown.is_requested() {
+            crossbeam_channel::select! {
+                recv(radar_edges) -> edge => match edge {
+                    Ok(Ok(edge)) => {
+                        if !shutdown.is_requested() && !self.control.is_paused() {
+                            self.on_radar_detected(radar_detection_event(edge))?;
+                        }
+                    },
+                    Ok(Err(e)) => {
+                        self.radar_edges = None;
+                        return Err(RookLWError::Initialization(format!("Failed to read GPIO event: {}", e)));
+                    },
+                    Err(_) => {
+                        self.radar_edges = None;
+                        return Err(RookLWError::Concurrency("GPIO reader stopped".to_string()));
+                    },
                 },
-                Err(e) => {
-                    info!(error = %e, "Radar motion watcher exiting with error");
-                    Err(e)
-                }
+                recv(commands) -> command => {
+                    if let Ok(command) = command {
+                        self.image_capturer.on_command(command)?;
+                    }
+                },
+                default(SHUTDOWN_POLL_INTERVAL) => {},
             }
-        })
-    }
+        }
 
-    pub fn run(&mut self) -> RookLWResult<()> {
-        info!("Starting radar motion watcher");
+        info!("Shutdown requested, radar motion watcher stopping");
+        Ok(())
+    }
 
-        // Initialize GPIO chip and line for radar input
+    /// Open the GPIO line and read its edges on a new thread, until reading
+    /// fails or the watcher is gone.
+    fn start_gpio_reader(&self) -> RookLWResult<Receiver<std::io::Result<Event>>> {
         let chip_path = self.gpio_chip_path.as_deref().unwrap_or("/dev/gpiochip0");
         let chip = Chip::new(chip_path)
             .map_err(|e| RookLWError::Initialization(format!("Failed to open GPIO chip {}: {}", chip_path, e)))?;
@@ -89,57 +131,47 @@ impl RadarMotionWatcher {
             "GPIO radar input configured"
         );
 
-        loop {
-            match self.wait_for_radar_trigger(&mut lines)? {
-                Some(radar_detection_result) => {
-                    self.on_radar_detected(radar_detection_result)?;
-                },
-                None => {
-                    // Timeout or no event, continue waiting
+        let (sender, receiver) = crossbeam_channel::bounded(1);
+        spawn(move || {
+            // The GPIO line is closed when `lines` is dropped.
+            loop {
+                let edge = lines.read_event();
+                let failed = edge.is_err();
+                if sender.send(edge).is_err() || failed {
+                    break;
                 }
             }
-        }
-
-        // Note: GPIO lines and chip will be closed when dropped
-        // Ok(())
+        });
+        Ok(receiver)
     }
 
     fn on_radar_detected(&mut self, result: MotionDetectionEvent) -> RookLWResult<()> {
         self.image_capturer.on_motion_detected(result)
     }
+}
 
-    fn wait_for_radar_trigger(&mut self, lines: &mut gpiod::Lines<Input>) -> RookLWResult<Option<MotionDetectionEvent>> {
-        // Try to read an event from the GPIO line - this will block until an event occurs
-        match lines.read_event() {
-            Ok(event) => {
-                let event_id = Uuid::new_v4();
-                let event_timestamp: DateTime<FixedOffset> = chrono::Local::now().into();
-
-                info!(
-                    event_id = %event_id,
-                    line = event.line,
-                    edge = ?event.edge,
-                    "Radar trigger detected"
-                );
-
-                // Return trigger event without capturing images
-                // Image capture happens later in on_radar_detected()
-                let result = MotionDetectionEvent {
-                    event_id,
-                    event_timestamp,
-                    motion_score: MotionDetectionScore {
-                        detected: true,
-                        score: 1.0,
-                        properties: HashMap::new(),
-                    },
-                    capture_events: Vec::new(),
-                };
-
-                return Ok(Some(result));
-            },
-            Err(e) => {
-                Err(RookLWError::Initialization(format!("Failed to read GPIO event: {}", e)))
-            }
-        }
+fn radar_detection_event(edge: Event) -> MotionDetectionEvent {
+    let event_id = Uuid::new_v4();
+    let event_timestamp: DateTime<FixedOffset> = chrono::Local::now().into();
+
+    info!(
+        event_id = %event_id,
+        line = edge.line,
+        edge = ?edge.edge,
+        "Radar trigger detected"
+    );
+
+    // Image capture happens later in on_radar_detected()
+    MotionDetectionEvent {
+        event_id,
+        event_timestamp,
+        trigger: EventTrigger::Radar,
+        motion_score: MotionDetectionScore {
+            detected: true,
+            score: 1.0,
+            properties: HashMap::new(),
+            regions: Vec::new(),
+        },
+        capture_events: Vec::new(),
     }
 }
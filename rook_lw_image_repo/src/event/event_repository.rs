@@ -0,0 +1,27 @@
+use rook_lw_models::event::{EventInfo, EventSearchOptions};
+
+use crate::ImageRepoResult;
+
+use chrono::{DateTime, FixedOffset};
+
+pub trait EventRepository: Send + Sync {
+    /// Save an event, replacing the stored one with the same id.
+    fn save_event(&self, event: &EventInfo) -> ImageRepoResult<()>;
+
+    /// Save what the stored images of an event add up to: frame count, class
+    /// counts, hero image and peak motion score. The trigger, start and end of
+    /// an event already stored are kept.
+    fn save_event_frames(&self, event: &EventInfo) -> ImageRepoResult<()>;
+
+    /// Set the end of a stored event.
+    fn end_event(&self, event_id: &str, end_timestamp: DateTime<FixedOffset>) -> ImageRepoResult<()>;
+
+    /// End the events started before `before` that have not ended, at their
+    /// start. Returns the number of events ended.
+    fn end_stale_events(&self, before: DateTime<FixedOffset>) -> ImageRepoResult<usize>;
+
+    fn get_event(&self, event_id: &str) -> ImageRepoResult<Option<EventInfo>>;
+
+    /// Events matching the options, newest first.
+    fn search_events(&self, options: &EventSearchOptions) -> ImageRepoResult<Vec<EventInfo>>;
+}
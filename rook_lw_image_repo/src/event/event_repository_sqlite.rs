@@ -0,0 +1,283 @@
+use super::EventRepository;
+use crate::{ImageRepoError, ImageRepoResult};
+
+use rook_lw_models::event::{EventInfo, EventSearchOptions, EventTrigger};
+
+use chrono::{DateTime, FixedOffset};
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{params, Row, ToSql};
+use tracing::{debug, info};
+
+use std::collections::BTreeMap;
+
+// Columns read by row_to_event_info, in order.
+const EVENT_INFO_COLUMNS: &str =
+    "event_id, trigger, start_timestamp, end_timestamp, peak_motion_score, frame_count, class_counts, hero_image_id, hero_score";
+
+pub struct EventRepositorySqlite {
+    pool: Pool<SqliteConnectionManager>,
+}
+
+impl EventRepositorySqlite {
+
+    pub fn new(pool: Pool<SqliteConnectionManager>) -> ImageRepoResult<Self> {
+        let repo = Self { pool };
+        repo.initialize()?;
+        Ok(repo)
+    }
+
+    fn initialize(&self) -> ImageRepoResult<()> {
+        let conn = self.pool.get()?;
+        info!("Initializing event_repository database");
+        conn.execute_batch(r#"
+            CREATE TABLE IF NOT EXISTS event_info (
+                event_id TEXT PRIMARY KEY,
+                trigger TEXT NOT NULL,
+                start_timestamp TEXT NOT NULL,
+                end_timestamp TEXT,
+                peak_motion_score REAL NOT NULL,
+                frame_count INTEGER NOT NULL,
+                class_counts TEXT NOT NULL,
+                hero_image_id TEXT,
+                hero_score REAL
+            );
+            CREATE INDEX IF NOT EXISTS idx_event_start_timestamp_dt ON event_info(datetime(start_timestamp));
+        "#)?;
+        Ok(())
+    }
+
+    fn row_to_event_info(row: &Row) -> ImageRepoResult<EventInfo> {
+        let trigger: String = row.get(1)?;
+        let start_timestamp: String = row.get(2)?;
+        let end_timestamp: Option<String> = row.get(3)?;
+        let class_counts_json: String = row.get(6)?;
+
+        let class_counts: BTreeMap<String, u32> = serde_json::from_str(&class_counts_json)?;
+
+        Ok(EventInfo {
+            event_id: row.get(0)?,
+            trigger: EventTrigger::parse(&trigger)
+                .ok_or_else(|| ImageRepoError::Parse(format!("Unknown event trigger: {trigger}")))?,
+            start_timestamp: chrono::DateTime::parse_from_rfc3339(&start_timestamp)?,
+            end_timestamp: match end_timestamp {
+                Some(end_timestamp) => Some(chrono::DateTime::parse_from_rfc3339(&end_timestamp)?),
+                None => None,
+            },
+            peak_motion_score: row.get(4)?,
+            frame_count: row.get(5)?,
+            class_counts,
+            hero_image_id: row.get(7)?,
+            hero_score: row.get(8)?,
+        })
+    }
+}
+
+impl EventRepository for EventRepositorySqlite {
+
+    fn save_event(&self, event: &EventInfo) -> ImageRepoResult<()> {
+        let class_counts_json = serde_json::to_string(&event.class_counts)?;
+
+        let conn = self.pool.get()?;
+        conn.execute(
+            r#"INSERT INTO event_info (
+                event_id, trigger, start_timestamp, end_timestamp, peak_motion_score, frame_count, class_counts, hero_image_id, hero_score
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            ON CONFLICT(event_id) DO UPDATE SET
+                trigger=excluded.trigger,
+                start_timestamp=excluded.start_timestamp,
+                end_timestamp=excluded.end_timestamp,
+                peak_motion_score=excluded.peak_motion_score,
+                frame_count=excluded.frame_count,
+                class_counts=excluded.class_counts,
+                hero_image_id=excluded.hero_image_id,
+                hero_score=excluded.hero_score
+            "#,
+            params![
+                &event.event_id,
+                event.trigger.as_str(),
+                event.start_timestamp.to_rfc3339(),
+                event.end_timestamp.map(|end_timestamp| end_timestamp.to_rfc3339()),
+                event.peak_motion_score,
+                event.frame_count,
+                class_counts_json,
+                &event.hero_image_id,
+                event.hero_score,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn save_event_frames(&self, event: &EventInfo) -> ImageRepoResult<()> {
+        let class_counts_json = serde_json::to_string(&event.class_counts)?;
+
+        let conn = self.pool.get()?;
+        conn.execute(
+            r#"INSERT INTO event_info (
+                event_id, trigger, start_timestamp, end_timestamp, peak_motion_score, frame_count, class_counts, hero_image_id, hero_score
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            ON CONFLICT(event_id) DO UPDATE SET
+                end_timestamp=COALESCE(event_info.end_timestamp, excluded.end_timestamp),
+                peak_motion_score=MAX(event_info.peak_motion_score, excluded.peak_motion_score),
+                frame_count=excluded.frame_count,
+                class_counts=excluded.class_counts,
+                hero_image_id=excluded.hero_image_id,
+                hero_score=excluded.hero_score
+            "#,
+            params![
+                &event.event_id,
+                event.trigger.as_str(),
+                event.start_timestamp.to_rfc3339(),
+                event.end_timestamp.map(|end_timestamp| end_timestamp.to_rfc3339()),
+                event.peak_motion_score,
+                event.frame_count,
+                class_counts_json,
+                &event.hero_image_id,
+                event.hero_score,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn end_event(&self, event_id: &str, end_timestamp: DateTime<FixedOffset>) -> ImageRepoResult<()> {
+        let conn = self.pool.get()?;
+        conn.execute(
+            "UPDATE event_info SET end_timestamp = ?2 WHERE event_id = ?1",
+            params![event_id, end_timestamp.to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    fn end_stale_events(&self, before: DateTime<FixedOffset>) -> ImageRepoResult<usize> {
+        let conn = self.pool.get()?;
+        let count = conn.execute(
+            r#"UPDATE event_info SET end_timestamp = start_timestamp
+            WHERE end_timestamp IS NULL AND datetime(start_timestamp) < datetime(?1)"#,
+            params![before.to_rfc3339()],
+        )?;
+        Ok(count)
+    }
+
+    fn get_event(&self, event_id: &str) -> ImageRepoResult<Option<EventInfo>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {EVENT_INFO_COLUMNS} FROM event_info WHERE event_id = ?1"
+        ))?;
+        let mut rows = stmt.query(params![event_id])?;
+        if let Some(row_result) = rows.next()? {
+            Ok(Some(Self::row_to_event_info(row_result)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn search_events(&self, options: &EventSearchOptions) -> ImageRepoResult<Vec<EventInfo>> {
+        let conn = self.pool.get()?;
+
+        let mut query = format!("SELECT {EVENT_INFO_COLUMNS}\nFROM event_info\nWHERE 1=1\n");
+        let mut params_vec: Vec<Box<dyn ToSql>> = Vec::new();
+
+        if let Some(start_dt) = &options.start_date {
+            query.push_str("  AND datetime(start_timestamp) >= datetime(?)\n");
+            params_vec.push(Box::new(start_dt.to_rfc3339()));
+        }
+
+        if let Some(end_dt) = &options.end_date {
+            query.push_str("  AND datetime(start_timestamp) <= datetime(?)\n");
+            params_vec.push(Box::new(end_dt.to_rfc3339()));
+        }
+
+        if let Some(trigger) = options.trigger {
+            query.push_str("  AND trigger = ?\n");
+            params_vec.push(Box::new(trigger.as_str()));
+        }
+
+        // Any of the classes seen in the event
+        if !options.detection_classes.is_empty() {
+            let placeholders = vec!["?"; options.detection_classes.len()].join(",");
+            query.push_str(&format!(
+                "  AND EXISTS (SELECT 1 FROM json_each(class_counts) WHERE json_each.key IN ({placeholders}))\n"
+            ));
+            for class_name in &options.detection_classes {
+                params_vec.push(Box::new(class_name.clone()));
+            }
+        }
+
+        query.push_str("ORDER BY datetime(start_timestamp) DESC\n");
+        query.push_str("LIMIT ?\nOFFSET ?\n");
+        params_vec.push(Box::new(options.limit.unwrap_or(500)));
+        params_vec.push(Box::new(options.offset.unwrap_or(0)));
+
+        debug!(
+            query = query.replace("\n", " "),
+            "Built sql query"
+        );
+
+        let mut stmt = conn.prepare(&query)?;
+        let mut rows = stmt.query(rusqlite::params_from_iter(params_vec.iter()))?;
+
+        let mut results = Vec::new();
+        while let Some(row_result) = rows.next()? {
+            results.push(Self::row_to_event_info(row_result)?);
+        }
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sqlite::TestDb;
+
+    fn at_minutes(minutes: i64) -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339("2024-06-01T12:00:00+00:00").unwrap() + chrono::Duration::minutes(minutes)
+    }
+
+    #[test]
+    fn saved_frames_keep_the_event_lifecycle() {
+        let test_db = TestDb::new("event_frames");
+        let repo = EventRepositorySqlite::new(test_db.pool()).unwrap();
+        repo.save_event(&EventInfo {
+            event_id: "e".to_string(),
+            trigger: EventTrigger::Radar,
+            start_timestamp: at_minutes(0),
+            peak_motion_score: 0.8,
+            ..Default::default()
+        }).unwrap();
+        repo.end_event("e", at_minutes(1)).unwrap();
+
+        repo.save_event_frames(&EventInfo {
+            event_id: "e".to_string(),
+            trigger: EventTrigger::ImageDiff,
+            start_timestamp: at_minutes(0),
+            peak_motion_score: 0.3,
+            frame_count: 2,
+            class_counts: BTreeMap::from([("fox".to_string(), 1)]),
+            ..Default::default()
+        }).unwrap();
+
+        let event = repo.get_event("e").unwrap().unwrap();
+        assert_eq!(event.trigger, EventTrigger::Radar);
+        assert_eq!(event.end_timestamp, Some(at_minutes(1)));
+        assert_eq!(event.peak_motion_score, 0.8);
+        assert_eq!(event.frame_count, 2);
+        assert_eq!(event.class_counts.get("fox"), Some(&1));
+    }
+
+    #[test]
+    fn stale_events_are_ended_at_their_start() {
+        let test_db = TestDb::new("event_stale");
+        let repo = EventRepositorySqlite::new(test_db.pool()).unwrap();
+        for (event_id, minutes) in [("old", 0), ("new", 30)] {
+            repo.save_event(&EventInfo {
+                event_id: event_id.to_string(),
+                start_timestamp: at_minutes(minutes),
+                ..Default::default()
+            }).unwrap();
+        }
+
+        assert_eq!(repo.end_stale_events(at_minutes(20)).unwrap(), 1);
+        assert_eq!(repo.get_event("old").unwrap().unwrap().end_timestamp, Some(at_minutes(0)));
+        assert_eq!(repo.get_event("new").unwrap().unwrap().end_timestamp, None);
+    }
+}
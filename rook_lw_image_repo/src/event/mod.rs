@@ -0,0 +1,5 @@
+mod event_repository;
+mod event_repository_sqlite;
+
+pub use event_repository::*;
+pub use event_repository_sqlite::*;
@@ -0,0 +1,77 @@
+//! Packing and comparison of image embeddings.
+//!
+//! Embeddings are stored as packed little-endian `f32` blobs. Next to each one
+//! an 8-bit quantized copy of the normalized vector is kept, which is a quarter
+//! of the size and is enough to pick candidates before an exact comparison.
+
+/// Pack an embedding into a little-endian `f32` blob.
+pub fn pack_embedding(embedding: &[f32]) -> Vec<u8> {
+    embedding.iter().flat_map(|value| value.to_le_bytes()).collect()
+}
+
+/// Unpack a blob written by [`pack_embedding`].
+pub fn unpack_embedding(blob: &[u8]) -> Vec<f32> {
+    blob.chunks_exact(4)
+        .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .collect()
+}
+
+/// Quantize the normalized embedding to one signed byte per value.
+pub fn quantize_embedding(embedding: &[f32]) -> Vec<u8> {
+    let norm = embedding.iter().map(|value| value * value).sum::<f32>().sqrt();
+    if norm == 0.0 {
+        return vec![0; embedding.len()];
+    }
+    embedding
+        .iter()
+        .map(|value| ((value / norm) * 127.0).round().clamp(-127.0, 127.0) as i8 as u8)
+        .collect()
+}
+
+/// Cosine similarity of two embeddings, 0 when either is all zeros or the sizes differ.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let mut dot = 0.0;
+    let mut norm_a = 0.0;
+    let mut norm_b = 0.0;
+    for (x, y) in a.iter().zip(b) {
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a.sqrt() * norm_b.sqrt())
+    }
+}
+
+/// Approximate cosine similarity of two embeddings quantized by [`quantize_embedding`].
+pub fn quantized_similarity(a: &[u8], b: &[u8]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: i32 = a.iter().zip(b).map(|(x, y)| (*x as i8 as i32) * (*y as i8 as i32)).sum();
+    dot as f32 / (127.0 * 127.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn embedding_roundtrip_and_similarity() {
+        let a = vec![0.5, -1.25, 3.0, 0.0];
+        assert_eq!(unpack_embedding(&pack_embedding(&a)), a);
+
+        let b = vec![1.0, -2.5, 6.0, 0.0];
+        assert!((cosine_similarity(&a, &b) - 1.0).abs() < 1e-6);
+        assert!((quantized_similarity(&quantize_embedding(&a), &quantize_embedding(&b)) - 1.0).abs() < 0.02);
+
+        let c = vec![-0.5, 1.25, -3.0, 0.0];
+        assert!((cosine_similarity(&a, &c) + 1.0).abs() < 1e-6);
+        assert_eq!(cosine_similarity(&a, &[0.0; 4]), 0.0);
+    }
+}
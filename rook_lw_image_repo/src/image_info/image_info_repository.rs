@@ -1,14 +1,77 @@
-use rook_lw_models::image::{ImageInfo, ImageInfoSearchOptions};
+use rook_lw_models::image::{Detection, EmbeddingCluster, ImageInfo, ImageInfoSearchOptions, SimilarImage, SimilaritySearchOptions};
+
+use chrono::{DateTime, FixedOffset};
+
+use std::collections::BTreeMap;
 
 use crate::ImageRepoResult;
 
 pub trait ImageInfoRepository: Send + Sync {
+    /// Save an image. Embeddings are stored separately from the detection
+    /// result and are not returned when reading image info back; use
+    /// [`get_image_embedding`](Self::get_image_embedding) for them. Saving an
+    /// image without embeddings keeps any embedding already stored.
     fn save_image_info(&self, info: &ImageInfo) -> ImageRepoResult<()>;
 
+    /// Set or clear the human-verified detections of an image.
+    /// Returns false when there is no such image.
+    fn set_verified_detections(&self, image_id: &str, detections: Option<&[Detection]>) -> ImageRepoResult<bool>;
+
     fn get_image_info(&self, image_id: &str) -> ImageRepoResult<Option<ImageInfo>>;
 
+    /// All images of an event, in capture order.
+    fn get_event_image_info(&self, event_id: &str) -> ImageRepoResult<Vec<ImageInfo>>;
+
+    /// Images captured in a date range, oldest first, with or without detections.
+    ///
+    /// Meant for batch jobs that page through the whole repository; the order is
+    /// stable, so pages can be walked with `offset` while records are updated.
+    fn scan_image_info(
+        &self,
+        start_date: Option<DateTime<FixedOffset>>,
+        end_date: Option<DateTime<FixedOffset>>,
+        limit: u32,
+        offset: u32,
+        ) -> ImageRepoResult<Vec<ImageInfo>>;
+
     fn search_image_info(
         &self,
         options: &ImageInfoSearchOptions,
         ) -> ImageRepoResult<Vec<ImageInfo>>;
+
+    /// Number of images per detected class captured since the given time.
+    fn get_class_counts(&self, since: DateTime<FixedOffset>) -> ImageRepoResult<BTreeMap<String, u32>>;
+
+    fn get_image_embedding(&self, image_id: &str) -> ImageRepoResult<Option<Vec<f32>>>;
+
+    /// Image ids and embeddings of all images with one, oldest first.
+    fn scan_image_embeddings(&self, limit: u32, offset: u32) -> ImageRepoResult<Vec<(String, Vec<f32>)>>;
+
+    /// All embedding clusters with their current member counts.
+    fn get_embedding_clusters(&self) -> ImageRepoResult<Vec<EmbeddingCluster>>;
+
+    /// Replace all clusters, and the cluster assignments of the `image_ids` they were
+    /// computed from, in one transaction. Of those, images not in `assignments` are left
+    /// without a cluster; other images, e.g. stored since, keep theirs.
+    fn save_embedding_clusters(
+        &self,
+        clusters: &[EmbeddingCluster],
+        image_ids: &[String],
+        assignments: &BTreeMap<String, u32>,
+        ) -> ImageRepoResult<()>;
+
+    /// Images most similar to the given image by cosine similarity of their
+    /// embeddings, most similar first. Empty when the image has no embedding.
+    fn find_similar_image_info(
+        &self,
+        image_id: &str,
+        options: &SimilaritySearchOptions,
+        ) -> ImageRepoResult<Vec<SimilarImage>>;
+
+    /// Stored images most similar to an embedding, most similar first.
+    fn find_similar_to_embedding(
+        &self,
+        embedding: &[f32],
+        options: &SimilaritySearchOptions,
+        ) -> ImageRepoResult<Vec<SimilarImage>>;
 }
@@ -1,12 +1,20 @@
 use super::ImageInfoRepository;
+use super::{pack_embedding, unpack_embedding, quantize_embedding, cosine_similarity, quantized_similarity};
 use crate::ImageRepoResult;
 
 use rook_lw_models::image::{
+    UNKNOWN_MODEL_NAME,
+    Detection,
     DetectionResult,
+    EmbeddingCluster,
     MotionDetectionScore,
     ImageInfo,
-    ImageInfoSearchOptions};
+    ImageInfoSearchOptions,
+    ImageInfoSort,
+    SimilarImage,
+    SimilaritySearchOptions};
 
+use chrono::{DateTime, FixedOffset};
 use rusqlite::Row;
 use tracing::{debug, info};
 use rusqlite::{params, ToSql};
@@ -14,6 +22,21 @@ use r2d2::Pool;
 use r2d2_sqlite::SqliteConnectionManager;
 use serde_json;
 
+use std::collections::BTreeMap;
+
+// Columns read by row_to_image_info, in order.
+const IMAGE_INFO_COLUMNS: &str =
+    "image_id, event_id, event_timestamp, motion_score, detection, capture_index, capture_timestamp, image_path, duplicate_of, interestingness, cluster_id, redacted, verified_detections, negative";
+
+// Default number of images returned by a similarity search.
+const DEFAULT_SIMILAR_LIMIT: u32 = 10;
+
+// Most images a similarity search returns; larger requested limits are clamped.
+const MAX_SIMILAR_LIMIT: u32 = 1000;
+
+// Candidates kept per requested image when searching the quantized embeddings.
+const QUANTIZED_CANDIDATE_FACTOR: usize = 4;
+
 pub struct ImageInfoRepositorySqlite {
     pool: Pool<SqliteConnectionManager>,
 }
@@ -35,6 +58,12 @@ impl ImageInfoRepositorySqlite {
         let capture_index: u32 = row.get(5)?;
         let capture_timestamp: String = row.get(6)?;
         let image_path: String = row.get(7)?;
+        let duplicate_of: Option<String> = row.get(8)?;
+        let interestingness: Option<f32> = row.get(9)?;
+        let cluster_id: Option<u32> = row.get(10)?;
+        let redacted: bool = row.get(11)?;
+        let verified_detections_json: Option<String> = row.get(12)?;
+        let negative: bool = row.get(13)?;
 
         let motion_score: MotionDetectionScore = serde_json::from_str(&motion_score_json)?;
         
@@ -44,6 +73,11 @@ impl ImageInfoRepositorySqlite {
             serde_json::from_str(&detection_json)?
         };
         
+        let verified_detections: Option<Vec<Detection>> = match verified_detections_json {
+            Some(json) => Some(serde_json::from_str(&json)?),
+            None => None,
+        };
+
         let event_timestamp = chrono::DateTime::parse_from_rfc3339(&event_timestamp)?;
         let capture_timestamp = chrono::DateTime::parse_from_rfc3339(&capture_timestamp)?;
 
@@ -56,6 +90,12 @@ impl ImageInfoRepositorySqlite {
             capture_timestamp,
             detection,
             image_path,
+            verified_detections,
+            duplicate_of,
+            redacted,
+            negative,
+            interestingness,
+            cluster_id,
         })
     }
 
@@ -71,22 +111,134 @@ impl ImageInfoRepositorySqlite {
                 detection TEXT NOT NULL,
                 capture_index INTEGER NOT NULL,
                 capture_timestamp TEXT NOT NULL,
-                image_path TEXT NOT NULL
+                image_path TEXT NOT NULL,
+                duplicate_of TEXT,
+                interestingness REAL,
+                cluster_id INTEGER,
+                redacted INTEGER NOT NULL DEFAULT 0,
+                verified_detections TEXT,
+                negative INTEGER NOT NULL DEFAULT 0
             );
             CREATE INDEX IF NOT EXISTS idx_event_timestamp_dt ON image_info(datetime(event_timestamp));
             CREATE INDEX IF NOT EXISTS idx_capture_timestamp_dt ON image_info(datetime(capture_timestamp));
+            CREATE INDEX IF NOT EXISTS idx_event_id ON image_info(event_id);
+            CREATE TABLE IF NOT EXISTS image_embedding (
+                image_id TEXT PRIMARY KEY,
+                dimension INTEGER NOT NULL,
+                embedding BLOB NOT NULL,
+                embedding_q8 BLOB NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_image_embedding_dimension ON image_embedding(dimension);
+            CREATE TABLE IF NOT EXISTS embedding_cluster (
+                cluster_id INTEGER PRIMARY KEY,
+                dimension INTEGER NOT NULL,
+                centroid BLOB NOT NULL,
+                representative_image_id TEXT,
+                created_timestamp TEXT NOT NULL,
+                updated_timestamp TEXT NOT NULL
+            );
         "#)?;
+        Self::add_column_if_missing(&conn, "image_info", "duplicate_of", "TEXT")?;
+        Self::add_column_if_missing(&conn, "image_info", "interestingness", "REAL")?;
+        Self::add_column_if_missing(&conn, "image_info", "cluster_id", "INTEGER")?;
+        Self::add_column_if_missing(&conn, "image_info", "redacted", "INTEGER NOT NULL DEFAULT 0")?;
+        Self::add_column_if_missing(&conn, "image_info", "verified_detections", "TEXT")?;
+        Self::add_column_if_missing(&conn, "image_info", "negative", "INTEGER NOT NULL DEFAULT 0")?;
+        conn.execute_batch("CREATE INDEX IF NOT EXISTS idx_cluster_id ON image_info(cluster_id);")?;
+        drop(conn);
+        self.migrate_embeddings()?;
+        Ok(())
+    }
+
+    /// Add a column to a table created by an older version.
+    fn add_column_if_missing(conn: &rusqlite::Connection, table: &str, column: &str, definition: &str) -> ImageRepoResult<()> {
+        let mut stmt = conn.prepare(&format!("PRAGMA table_info({table})"))?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let name: String = row.get(1)?;
+            if name == column {
+                return Ok(());
+            }
+        }
+        info!(table, column, "Adding column");
+        conn.execute_batch(&format!("ALTER TABLE {table} ADD COLUMN {column} {definition}"))?;
+        Ok(())
+    }
+
+    /// Move embeddings stored in the detection JSON by older versions into the
+    /// embedding table.
+    fn migrate_embeddings(&self) -> ImageRepoResult<()> {
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction()?;
+
+        let embeddings = {
+            let mut stmt = tx.prepare(
+                r#"SELECT image_id, json_extract(detection, '$.embeddings')
+                   FROM image_info WHERE json_type(detection, '$.embeddings') = 'array'"#
+            )?;
+            let mut rows = stmt.query([])?;
+            let mut embeddings: Vec<(String, Vec<f32>)> = Vec::new();
+            while let Some(row) = rows.next()? {
+                let image_id: String = row.get(0)?;
+                let embedding_json: String = row.get(1)?;
+                embeddings.push((image_id, serde_json::from_str(&embedding_json)?));
+            }
+            embeddings
+        };
+
+        if embeddings.is_empty() {
+            return Ok(());
+        }
+
+        info!(image_count = embeddings.len(), "Moving embeddings to image_embedding table");
+        for (image_id, embedding) in &embeddings {
+            Self::save_embedding(&tx, image_id, embedding)?;
+            tx.execute(
+                "UPDATE image_info SET detection = json_remove(detection, '$.embeddings') WHERE image_id = ?1",
+                params![image_id],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn save_embedding(conn: &rusqlite::Connection, image_id: &str, embedding: &[f32]) -> ImageRepoResult<()> {
+        conn.execute(
+            r#"INSERT INTO image_embedding (image_id, dimension, embedding, embedding_q8)
+               VALUES (?1, ?2, ?3, ?4)
+               ON CONFLICT(image_id) DO UPDATE SET
+                   dimension=excluded.dimension,
+                   embedding=excluded.embedding,
+                   embedding_q8=excluded.embedding_q8
+            "#,
+            params![
+                image_id,
+                embedding.len() as u32,
+                pack_embedding(embedding),
+                quantize_embedding(embedding),
+            ],
+        )?;
         Ok(())
     }
 
     fn save_image_info(&self, info: &ImageInfo) -> ImageRepoResult<()> {
         let motion_score_json = serde_json::to_string(&info.motion_score)?;
-        let detection_json = serde_json::to_string(&info.detection)?;
-        let conn = self.pool.get()?;
-        conn.execute(
+
+        // Embeddings go to their own table rather than the detection JSON.
+        let mut detection = info.detection.clone();
+        let embeddings = detection.as_mut().and_then(|result| result.embeddings.take());
+        let detection_json = serde_json::to_string(&detection)?;
+        let verified_detections_json = match &info.verified_detections {
+            Some(detections) => Some(serde_json::to_string(detections)?),
+            None => None,
+        };
+
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction()?;
+        tx.execute(
             r#"INSERT INTO image_info (
-                image_id, event_id, event_timestamp, motion_score, detection, capture_index, capture_timestamp, image_path
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+                image_id, event_id, event_timestamp, motion_score, detection, capture_index, capture_timestamp, image_path, duplicate_of, interestingness, cluster_id, redacted, verified_detections, negative
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
             ON CONFLICT(image_id) DO UPDATE SET
                 event_id=excluded.event_id,
                 event_timestamp=excluded.event_timestamp,
@@ -94,7 +246,13 @@ impl ImageInfoRepositorySqlite {
                 detection=excluded.detection,
                 capture_index=excluded.capture_index,
                 capture_timestamp=excluded.capture_timestamp,
-                image_path=excluded.image_path
+                image_path=excluded.image_path,
+                duplicate_of=excluded.duplicate_of,
+                interestingness=excluded.interestingness,
+                cluster_id=excluded.cluster_id,
+                redacted=excluded.redacted,
+                verified_detections=excluded.verified_detections,
+                negative=excluded.negative
             "#,
             params![
                 &info.image_id,
@@ -105,17 +263,39 @@ impl ImageInfoRepositorySqlite {
                 info.capture_index,
                 info.capture_timestamp.to_rfc3339(),
                 &info.image_path,
+                &info.duplicate_of,
+                info.interestingness,
+                info.cluster_id,
+                info.redacted,
+                verified_detections_json,
+                info.negative,
             ],
         )?;
+        if let Some(embeddings) = &embeddings {
+            Self::save_embedding(&tx, &info.image_id, embeddings)?;
+        }
+        tx.commit()?;
         Ok(())
     }
 
-    fn get_image_info(&self, image_id: &str) -> ImageRepoResult<Option<ImageInfo>> {
+    fn set_verified_detections(&self, image_id: &str, detections: Option<&[Detection]>) -> ImageRepoResult<bool> {
+        let detections_json = match detections {
+            Some(detections) => Some(serde_json::to_string(detections)?),
+            None => None,
+        };
         let conn = self.pool.get()?;
-        let mut stmt = conn.prepare(
-            r#"SELECT image_id, event_id, event_timestamp, motion_score, detection, capture_index, capture_timestamp, image_path
-               FROM image_info WHERE image_id = ?1"#
+        let updated = conn.execute(
+            "UPDATE image_info SET verified_detections = ?2 WHERE image_id = ?1",
+            params![image_id, detections_json],
         )?;
+        Ok(updated > 0)
+    }
+
+    fn get_image_info(&self, image_id: &str) -> ImageRepoResult<Option<ImageInfo>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {IMAGE_INFO_COLUMNS} FROM image_info WHERE image_id = ?1"
+        ))?;
         let mut rows = stmt.query(params![image_id])?;
         if let Some(row_result) = rows.next()? {
             Ok(Some(Self::row_to_image_info(&row_result)?))
@@ -124,6 +304,255 @@ impl ImageInfoRepositorySqlite {
         }
     }
 
+    fn get_event_image_info(&self, event_id: &str) -> ImageRepoResult<Vec<ImageInfo>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {IMAGE_INFO_COLUMNS} FROM image_info WHERE event_id = ?1 ORDER BY capture_index"
+        ))?;
+        let mut rows = stmt.query(params![event_id])?;
+
+        let mut results: Vec<ImageInfo> = Vec::new();
+        while let Some(row_result) = rows.next()? {
+            results.push(Self::row_to_image_info(row_result)?);
+        }
+        Ok(results)
+    }
+
+    fn get_class_counts(&self, since: DateTime<FixedOffset>) -> ImageRepoResult<BTreeMap<String, u32>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(
+            r#"SELECT json_extract(detection.value, '$.class_name'), COUNT(DISTINCT image_info.image_id)
+               FROM image_info, json_each(image_info.detection, '$.detections') AS detection
+               WHERE datetime(capture_timestamp) >= datetime(?1)
+               GROUP BY 1"#
+        )?;
+        let mut rows = stmt.query(params![since.to_rfc3339()])?;
+
+        let mut class_counts = BTreeMap::new();
+        while let Some(row) = rows.next()? {
+            let class_name: Option<String> = row.get(0)?;
+            let count: u32 = row.get(1)?;
+            if let Some(class_name) = class_name {
+                class_counts.insert(class_name, count);
+            }
+        }
+        Ok(class_counts)
+    }
+
+    fn get_image_embedding(&self, image_id: &str) -> ImageRepoResult<Option<Vec<f32>>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare("SELECT embedding FROM image_embedding WHERE image_id = ?1")?;
+        let mut rows = stmt.query(params![image_id])?;
+        if let Some(row) = rows.next()? {
+            let blob: Vec<u8> = row.get(0)?;
+            Ok(Some(unpack_embedding(&blob)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn scan_image_embeddings(&self, limit: u32, offset: u32) -> ImageRepoResult<Vec<(String, Vec<f32>)>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(
+            r#"SELECT image_embedding.image_id, image_embedding.embedding
+               FROM image_embedding
+               JOIN image_info ON image_info.image_id = image_embedding.image_id
+               ORDER BY datetime(image_info.capture_timestamp), image_embedding.image_id
+               LIMIT ?1 OFFSET ?2"#
+        )?;
+        let mut rows = stmt.query(params![limit, offset])?;
+
+        let mut results = Vec::new();
+        while let Some(row) = rows.next()? {
+            let image_id: String = row.get(0)?;
+            let blob: Vec<u8> = row.get(1)?;
+            results.push((image_id, unpack_embedding(&blob)));
+        }
+        Ok(results)
+    }
+
+    fn get_embedding_clusters(&self) -> ImageRepoResult<Vec<EmbeddingCluster>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(
+            r#"SELECT cluster_id, centroid, representative_image_id, created_timestamp, updated_timestamp,
+                   (SELECT COUNT(*) FROM image_info WHERE image_info.cluster_id = embedding_cluster.cluster_id)
+               FROM embedding_cluster
+               ORDER BY cluster_id"#
+        )?;
+        let mut rows = stmt.query([])?;
+
+        let mut clusters = Vec::new();
+        while let Some(row) = rows.next()? {
+            let centroid: Vec<u8> = row.get(1)?;
+            let created_timestamp: String = row.get(3)?;
+            let updated_timestamp: String = row.get(4)?;
+            clusters.push(EmbeddingCluster {
+                cluster_id: row.get(0)?,
+                centroid: unpack_embedding(&centroid),
+                representative_image_id: row.get(2)?,
+                created_timestamp: DateTime::parse_from_rfc3339(&created_timestamp)?,
+                updated_timestamp: DateTime::parse_from_rfc3339(&updated_timestamp)?,
+                member_count: row.get(5)?,
+            });
+        }
+        Ok(clusters)
+    }
+
+    fn save_embedding_clusters(
+        &self,
+        clusters: &[EmbeddingCluster],
+        image_ids: &[String],
+        assignments: &BTreeMap<String, u32>,
+        ) -> ImageRepoResult<()>
+    {
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM embedding_cluster", [])?;
+        for cluster in clusters {
+            tx.execute(
+                r#"INSERT INTO embedding_cluster (
+                    cluster_id, dimension, centroid, representative_image_id, created_timestamp, updated_timestamp
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6)"#,
+                params![
+                    cluster.cluster_id,
+                    cluster.centroid.len() as u32,
+                    pack_embedding(&cluster.centroid),
+                    &cluster.representative_image_id,
+                    cluster.created_timestamp.to_rfc3339(),
+                    cluster.updated_timestamp.to_rfc3339(),
+                ],
+            )?;
+        }
+        {
+            let mut stmt = tx.prepare("UPDATE image_info SET cluster_id = ?2 WHERE image_id = ?1")?;
+            for image_id in image_ids {
+                stmt.execute(params![image_id, assignments.get(image_id)])?;
+            }
+        }
+        tx.commit()?;
+        info!(cluster_count = clusters.len(), image_count = assignments.len(), "Saved embedding clusters");
+        Ok(())
+    }
+
+    fn find_similar_image_info(
+        &self,
+        image_id: &str,
+        options: &SimilaritySearchOptions,
+        ) -> ImageRepoResult<Vec<SimilarImage>>
+    {
+        match self.get_image_embedding(image_id)? {
+            Some(embedding) => self.find_similar(&embedding, options, Some(image_id)),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn find_similar_to_embedding(
+        &self,
+        embedding: &[f32],
+        options: &SimilaritySearchOptions,
+        ) -> ImageRepoResult<Vec<SimilarImage>>
+    {
+        self.find_similar(embedding, options, None)
+    }
+
+    /// Brute-force k-nearest-neighbour search over all embeddings of the same size.
+    fn find_similar(
+        &self,
+        embedding: &[f32],
+        options: &SimilaritySearchOptions,
+        exclude_image_id: Option<&str>,
+        ) -> ImageRepoResult<Vec<SimilarImage>>
+    {
+        let limit = options.limit.unwrap_or(DEFAULT_SIMILAR_LIMIT).min(MAX_SIMILAR_LIMIT) as usize;
+        let dimension = embedding.len() as u32;
+
+        let mut scored: Vec<(f32, String)> = {
+            let conn = self.pool.get()?;
+            let column = if options.quantized { "embedding_q8" } else { "embedding" };
+            let mut stmt = conn.prepare(&format!(
+                "SELECT image_id, {column} FROM image_embedding WHERE dimension = ?1"
+            ))?;
+            let mut rows = stmt.query(params![dimension])?;
+
+            let query_q8 = quantize_embedding(embedding);
+            let mut scored = Vec::new();
+            while let Some(row) = rows.next()? {
+                let image_id: String = row.get(0)?;
+                if exclude_image_id == Some(image_id.as_str()) {
+                    continue;
+                }
+                let blob: Vec<u8> = row.get(1)?;
+                let similarity = if options.quantized {
+                    quantized_similarity(&query_q8, &blob)
+                } else {
+                    cosine_similarity(embedding, &unpack_embedding(&blob))
+                };
+                scored.push((similarity, image_id));
+            }
+            scored
+        };
+
+        sort_by_similarity(&mut scored);
+
+        if options.quantized {
+            // Rank the best quantized candidates exactly.
+            scored.truncate(limit.saturating_mul(QUANTIZED_CANDIDATE_FACTOR));
+            for (similarity, image_id) in scored.iter_mut() {
+                if let Some(candidate) = self.get_image_embedding(image_id)? {
+                    *similarity = cosine_similarity(embedding, &candidate);
+                }
+            }
+            sort_by_similarity(&mut scored);
+        }
+        scored.truncate(limit);
+
+        debug!(
+            dimension,
+            quantized = options.quantized,
+            result_count = scored.len(),
+            "Similarity search"
+        );
+
+        let mut results = Vec::with_capacity(scored.len());
+        for (similarity, image_id) in scored {
+            if let Some(image_info) = self.get_image_info(&image_id)? {
+                results.push(SimilarImage { similarity, image_info });
+            }
+        }
+        Ok(results)
+    }
+
+    fn scan_image_info(
+        &self,
+        start_date: Option<DateTime<FixedOffset>>,
+        end_date: Option<DateTime<FixedOffset>>,
+        limit: u32,
+        offset: u32,
+        ) -> ImageRepoResult<Vec<ImageInfo>>
+    {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(&format!(
+            r#"SELECT {IMAGE_INFO_COLUMNS}
+               FROM image_info
+               WHERE (?1 IS NULL OR datetime(capture_timestamp) >= datetime(?1))
+                 AND (?2 IS NULL OR datetime(capture_timestamp) <= datetime(?2))
+               ORDER BY datetime(capture_timestamp), image_id
+               LIMIT ?3 OFFSET ?4"#
+        ))?;
+        let mut rows = stmt.query(params![
+            start_date.map(|dt| dt.to_rfc3339()),
+            end_date.map(|dt| dt.to_rfc3339()),
+            limit,
+            offset,
+        ])?;
+
+        let mut results: Vec<ImageInfo> = Vec::new();
+        while let Some(row_result) = rows.next()? {
+            results.push(Self::row_to_image_info(row_result)?);
+        }
+        Ok(results)
+    }
+
     fn search_image_info(
         &self,
         options: &ImageInfoSearchOptions,
@@ -136,8 +565,7 @@ impl ImageInfoRepositorySqlite {
         // Base query selection
         let mut query = String::new();
         query.push_str("SELECT\n");
-        query.push_str("  image_id, event_id, event_timestamp, motion_score,\n");
-        query.push_str("  detection, capture_index, capture_timestamp, image_path\n");
+        query.push_str(&format!("  {IMAGE_INFO_COLUMNS}\n"));
         query.push_str("FROM image_info AS ii_outer\n");
         query.push_str("WHERE 1=1\n");
 
@@ -156,6 +584,28 @@ impl ImageInfoRepositorySqlite {
             params_vec.push(Box::new(end_dt.to_rfc3339()));
         }
 
+        // Model that produced the detections
+        if let Some(model_name) = &options.model_name {
+            if model_name == UNKNOWN_MODEL_NAME {
+                query.push_str("  AND json_extract(detection, '$.provenance.model_name') IS NULL\n");
+            } else {
+                query.push_str("  AND json_extract(detection, '$.provenance.model_name') = ?\n");
+                params_vec.push(Box::new(model_name));
+            }
+        }
+
+        // Embedding cluster
+        if let Some(cluster_id) = options.cluster_id {
+            query.push_str("  AND cluster_id = ?\n");
+            params_vec.push(Box::new(cluster_id));
+        }
+
+        // Images kept without detections
+        if let Some(negative) = options.negative {
+            query.push_str("  AND negative = ?\n");
+            params_vec.push(Box::new(negative));
+        }
+
         // Critera on detections
         query.push_str("  AND EXISTS (\n");
         query.push_str("    SELECT image_id\n");
@@ -184,8 +634,15 @@ impl ImageInfoRepositorySqlite {
         // End the exists check.
         query.push_str("  )\n");
 
-        // Reverse order by capture timestamp.
-        query.push_str("ORDER BY datetime(capture_timestamp) DESC\n");
+        match options.sort {
+            // Reverse order by capture timestamp.
+            ImageInfoSort::CaptureTimestamp => {
+                query.push_str("ORDER BY datetime(capture_timestamp) DESC\n");
+            },
+            ImageInfoSort::Interestingness => {
+                query.push_str("ORDER BY interestingness IS NULL, interestingness DESC, datetime(capture_timestamp) DESC\n");
+            },
+        }
 
         // Add limit and offset
         let limit = options.limit.unwrap_or(500);
@@ -217,16 +674,39 @@ impl ImageInfoRepositorySqlite {
 
 }
 
+fn sort_by_similarity(scored: &mut [(f32, String)]) {
+    scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+}
+
 impl ImageInfoRepository for ImageInfoRepositorySqlite {
     
     fn save_image_info(&self, info: &ImageInfo) -> ImageRepoResult<()> {
         self.save_image_info(info)
     }
 
+    fn set_verified_detections(&self, image_id: &str, detections: Option<&[Detection]>) -> ImageRepoResult<bool> {
+        self.set_verified_detections(image_id, detections)
+    }
+
     fn get_image_info(&self, image_id: &str) -> ImageRepoResult<Option<ImageInfo>> {
         self.get_image_info(image_id)
     }
 
+    fn get_event_image_info(&self, event_id: &str) -> ImageRepoResult<Vec<ImageInfo>> {
+        self.get_event_image_info(event_id)
+    }
+
+    fn scan_image_info(
+        &self,
+        start_date: Option<DateTime<FixedOffset>>,
+        end_date: Option<DateTime<FixedOffset>>,
+        limit: u32,
+        offset: u32)
+        -> ImageRepoResult<Vec<ImageInfo>>
+    {
+        self.scan_image_info(start_date, end_date, limit, offset)
+    }
+
     fn search_image_info(
         &self,
         options: &ImageInfoSearchOptions)
@@ -234,4 +714,142 @@ impl ImageInfoRepository for ImageInfoRepositorySqlite {
     {
         self.search_image_info(options)
     }
-}
\ No newline at end of file
+
+    fn get_class_counts(&self, since: DateTime<FixedOffset>) -> ImageRepoResult<BTreeMap<String, u32>> {
+        self.get_class_counts(since)
+    }
+
+    fn get_image_embedding(&self, image_id: &str) -> ImageRepoResult<Option<Vec<f32>>> {
+        self.get_image_embedding(image_id)
+    }
+
+    fn scan_image_embeddings(&self, limit: u32, offset: u32) -> ImageRepoResult<Vec<(String, Vec<f32>)>> {
+        self.scan_image_embeddings(limit, offset)
+    }
+
+    fn get_embedding_clusters(&self) -> ImageRepoResult<Vec<EmbeddingCluster>> {
+        self.get_embedding_clusters()
+    }
+
+    fn save_embedding_clusters(
+        &self,
+        clusters: &[EmbeddingCluster],
+        image_ids: &[String],
+        assignments: &BTreeMap<String, u32>)
+        -> ImageRepoResult<()>
+    {
+        self.save_embedding_clusters(clusters, image_ids, assignments)
+    }
+
+    fn find_similar_image_info(
+        &self,
+        image_id: &str,
+        options: &SimilaritySearchOptions)
+        -> ImageRepoResult<Vec<SimilarImage>>
+    {
+        self.find_similar_image_info(image_id, options)
+    }
+
+    fn find_similar_to_embedding(
+        &self,
+        embedding: &[f32],
+        options: &SimilaritySearchOptions)
+        -> ImageRepoResult<Vec<SimilarImage>>
+    {
+        self.find_similar_to_embedding(embedding, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sqlite::TestDb;
+
+    fn image_info(image_id: &str, embeddings: Vec<f32>) -> ImageInfo {
+        let timestamp = chrono::DateTime::parse_from_rfc3339("2024-06-01T12:00:00+00:00").unwrap();
+        ImageInfo {
+            image_id: image_id.to_string(),
+            event_id: "event".to_string(),
+            event_timestamp: timestamp,
+            motion_score: MotionDetectionScore::default(),
+            capture_index: 0,
+            capture_timestamp: timestamp,
+            detection: Some(DetectionResult::with_embeddings(Vec::new(), embeddings)),
+            image_path: format!("{}.jpg", image_id),
+            verified_detections: None,
+            duplicate_of: None,
+            redacted: false,
+            negative: true,
+            interestingness: None,
+            cluster_id: None,
+        }
+    }
+
+    #[test]
+    fn find_similar_ranks_by_similarity_with_any_limit() {
+        let test_db = TestDb::new("similar");
+        let repository = ImageInfoRepositorySqlite::new(test_db.pool()).unwrap();
+        repository.save_image_info(&image_info("query", vec![1.0, 0.0, 0.0])).unwrap();
+        repository.save_image_info(&image_info("close", vec![0.9, 0.1, 0.0])).unwrap();
+        repository.save_image_info(&image_info("far", vec![0.0, 0.0, 1.0])).unwrap();
+
+        for quantized in [false, true] {
+            let options = SimilaritySearchOptions { limit: Some(u32::MAX), quantized };
+            let similar = repository.find_similar_image_info("query", &options).unwrap();
+            let image_ids: Vec<&str> = similar.iter().map(|similar| similar.image_info.image_id.as_str()).collect();
+            assert_eq!(image_ids, vec!["close", "far"]);
+        }
+
+        let options = SimilaritySearchOptions { limit: Some(1), quantized: false };
+        assert_eq!(repository.find_similar_image_info("query", &options).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn migrate_embeddings_moves_them_out_of_the_detection() {
+        let test_db = TestDb::new("migrate");
+        let repository = ImageInfoRepositorySqlite::new(test_db.pool()).unwrap();
+        repository.save_image_info(&image_info("old", Vec::new())).unwrap();
+        // As stored by older versions, with the embeddings in the detection JSON.
+        repository.pool.get().unwrap().execute(
+            r#"UPDATE image_info SET detection = '{"detections":[],"embeddings":[0.5,-1.0]}' WHERE image_id = 'old'"#,
+            [],
+        ).unwrap();
+
+        repository.migrate_embeddings().unwrap();
+
+        assert_eq!(repository.get_image_embedding("old").unwrap(), Some(vec![0.5, -1.0]));
+        let image_info = repository.get_image_info("old").unwrap().unwrap();
+        assert_eq!(image_info.detection.unwrap().embeddings, None);
+    }
+
+    #[test]
+    fn save_embedding_clusters_keeps_assignments_of_images_not_scanned() {
+        let test_db = TestDb::new("clusters");
+        let repository = ImageInfoRepositorySqlite::new(test_db.pool()).unwrap();
+        for image_id in ["scanned", "noise", "stored_since"] {
+            let mut info = image_info(image_id, vec![1.0, 0.0]);
+            info.cluster_id = Some(7);
+            repository.save_image_info(&info).unwrap();
+        }
+
+        let timestamp = chrono::DateTime::parse_from_rfc3339("2024-06-01T12:00:00+00:00").unwrap();
+        let cluster = EmbeddingCluster {
+            cluster_id: 3,
+            centroid: vec![1.0, 0.0],
+            member_count: 1,
+            representative_image_id: Some("scanned".to_string()),
+            created_timestamp: timestamp,
+            updated_timestamp: timestamp,
+        };
+        repository.save_embedding_clusters(
+            &[cluster],
+            &["scanned".to_string(), "noise".to_string()],
+            &BTreeMap::from([("scanned".to_string(), 3)]),
+        ).unwrap();
+
+        let cluster_id = |image_id: &str| repository.get_image_info(image_id).unwrap().unwrap().cluster_id;
+        assert_eq!(cluster_id("scanned"), Some(3));
+        assert_eq!(cluster_id("noise"), None);
+        assert_eq!(cluster_id("stored_since"), Some(7));
+    }
+}
@@ -1,5 +1,7 @@
+mod embedding;
 mod image_info_repository;
 mod image_info_repository_sqlite;
 
 pub use image_info_repository_sqlite::*;
-pub use image_info_repository::*;
\ No newline at end of file
+pub use image_info_repository::*;
+pub use embedding::*;
\ No newline at end of file
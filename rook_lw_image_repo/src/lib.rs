@@ -1,3 +1,4 @@
+pub mod event;
 pub mod image_info;
 pub mod image_store;
 pub mod sqlite;
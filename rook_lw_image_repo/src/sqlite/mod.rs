@@ -1,3 +1,7 @@
 mod r2d2;
+#[cfg(test)]
+mod test_db;
 
-pub use r2d2::*;
\ No newline at end of file
+pub use r2d2::*;
+#[cfg(test)]
+pub(crate) use test_db::*;
\ No newline at end of file
@@ -0,0 +1,40 @@
+use super::create_pool;
+
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+
+/// Database file of a test in the temp directory, removed when dropped.
+///
+/// Create it before the repository using it, so the repository is dropped first.
+pub struct TestDb {
+    db_path: String,
+}
+
+impl TestDb {
+    pub fn new(name: &str) -> Self {
+        let db_path = std::env::temp_dir()
+            .join(format!("rook_lw_image_repo_{}_{}.db", name, std::process::id()))
+            .to_string_lossy()
+            .to_string();
+        let test_db = Self { db_path };
+        test_db.remove_files();
+        test_db
+    }
+
+    pub fn pool(&self) -> Pool<SqliteConnectionManager> {
+        create_pool(&self.db_path).unwrap()
+    }
+
+    fn remove_files(&self) {
+        // WAL journal mode leaves two files next to the database.
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", self.db_path, suffix));
+        }
+    }
+}
+
+impl Drop for TestDb {
+    fn drop(&mut self) {
+        self.remove_files();
+    }
+}
@@ -0,0 +1,74 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+
+/// What started an event.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum EventTrigger {
+    /// Motion found by comparing frames.
+    #[default]
+    ImageDiff,
+
+    /// Motion reported by a radar sensor.
+    Radar,
+
+    /// Requested by a person.
+    Manual,
+
+    /// Captured on a schedule.
+    Schedule,
+}
+
+impl EventTrigger {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventTrigger::ImageDiff => "image_diff",
+            EventTrigger::Radar => "radar",
+            EventTrigger::Manual => "manual",
+            EventTrigger::Schedule => "schedule",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "image_diff" => Some(EventTrigger::ImageDiff),
+            "radar" => Some(EventTrigger::Radar),
+            "manual" => Some(EventTrigger::Manual),
+            "schedule" => Some(EventTrigger::Schedule),
+            _ => None,
+        }
+    }
+}
+
+/// A stored event, stored by the daemon when it starts and kept up to date
+/// while its images are stored.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct EventInfo {
+    pub event_id: String,
+    pub trigger: EventTrigger,
+    pub start_timestamp: DateTime<FixedOffset>,
+
+    /// Time the last image was captured, set once the event has ended.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_timestamp: Option<DateTime<FixedOffset>>,
+
+    /// Highest motion score of the event and its stored images.
+    pub peak_motion_score: f32,
+
+    /// Number of stored images, 0 when none of the captures were stored.
+    pub frame_count: u32,
+
+    /// Number of distinct individuals seen per class.
+    pub class_counts: BTreeMap<String, u32>,
+
+    /// The image that best shows the event: sharp, with a confident, large
+    /// and centred detection.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hero_image_id: Option<String>,
+
+    /// Hero frame score of the hero image, from 0 to 1.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hero_score: Option<f32>,
+}
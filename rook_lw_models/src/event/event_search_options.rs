@@ -0,0 +1,23 @@
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+
+use super::EventTrigger;
+
+#[derive(Debug, Deserialize, Serialize, Default, PartialEq, Clone)]
+pub struct EventSearchOptions {
+    /// Only events starting at or after this time.
+    pub start_date: Option<DateTime<FixedOffset>>,
+
+    /// Only events starting at or before this time.
+    pub end_date: Option<DateTime<FixedOffset>>,
+
+    pub trigger: Option<EventTrigger>,
+
+    /// Only events in which any of these classes was seen.
+    #[serde(default)]
+    pub detection_classes: Vec<String>,
+
+    pub limit: Option<u32>,
+
+    pub offset: Option<u32>,
+}
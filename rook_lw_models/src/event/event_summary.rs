@@ -0,0 +1,85 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+
+use crate::image::ImageInfo;
+
+/// Summary of what was seen during one event.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct EventSummary {
+    pub event_id: String,
+    pub image_count: u32,
+    pub first_capture_timestamp: Option<DateTime<FixedOffset>>,
+    pub last_capture_timestamp: Option<DateTime<FixedOffset>>,
+
+    /// Number of distinct individuals seen per class.
+    pub class_counts: BTreeMap<String, u32>,
+
+    /// Interestingness of the most interesting image of the event.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interestingness: Option<f32>,
+}
+
+impl EventSummary {
+
+    /// Summarize the stored images of an event.
+    ///
+    /// Tracked detections are counted once per track. Detections without a track id
+    /// (stored before tracking existed) count as the most seen together in one image.
+    pub fn from_image_infos(event_id: impl Into<String>, images: &[ImageInfo]) -> Self {
+        let mut tracks: HashSet<(&str, u32)> = HashSet::new();
+        let mut untracked_max: HashMap<&str, u32> = HashMap::new();
+
+        for image in images {
+            let mut untracked: HashMap<&str, u32> = HashMap::new();
+            for detection in image.detection.iter().flat_map(|d| d.detections.iter()) {
+                match detection.track_id {
+                    Some(track_id) => {
+                        tracks.insert((detection.class_name.as_str(), track_id));
+                    },
+                    None => {
+                        *untracked.entry(detection.class_name.as_str()).or_insert(0) += 1;
+                    }
+                }
+            }
+            for (class_name, count) in untracked {
+                let max = untracked_max.entry(class_name).or_insert(0);
+                *max = (*max).max(count);
+            }
+        }
+
+        let mut class_counts: BTreeMap<String, u32> = BTreeMap::new();
+        for (class_name, _track_id) in tracks {
+            *class_counts.entry(class_name.to_string()).or_insert(0) += 1;
+        }
+        for (class_name, count) in untracked_max {
+            *class_counts.entry(class_name.to_string()).or_insert(0) += count;
+        }
+
+        Self {
+            event_id: event_id.into(),
+            image_count: images.len() as u32,
+            first_capture_timestamp: images.iter().map(|i| i.capture_timestamp).min(),
+            last_capture_timestamp: images.iter().map(|i| i.capture_timestamp).max(),
+            class_counts,
+            interestingness: images.iter().filter_map(|i| i.interestingness).reduce(f32::max),
+        }
+    }
+}
+
+/// Formats class counts as e.g. "2 deer, 1 fox".
+pub fn describe_class_counts(class_counts: &BTreeMap<String, u32>) -> String {
+    class_counts
+        .iter()
+        .map(|(class_name, count)| format!("{} {}", count, class_name))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+impl fmt::Display for EventSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", describe_class_counts(&self.class_counts))
+    }
+}
@@ -0,0 +1,7 @@
+mod event_info;
+mod event_search_options;
+mod event_summary;
+
+pub use event_info::*;
+pub use event_search_options::*;
+pub use event_summary::*;
@@ -11,6 +11,10 @@ pub struct Detection {
     pub y: i32,
     pub width: i32,
     pub height: i32,
+
+    /// Identifies the same object across the frames of an event, when tracked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub track_id: Option<u32>,
 }
 
 impl Detection {
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use super::Detection;
+use super::{Detection, ModelProvenance};
 
 /// Result of object detection containing detections and optional per-image embeddings.
 ///
@@ -15,6 +15,26 @@ pub struct DetectionResult {
     /// Only present if the model supports embeddings output.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub embeddings: Option<Vec<f32>>,
+
+    /// Detections that were suppressed as static background (e.g. a rock that is
+    /// detected in every event). Kept for debugging only; they are not part of
+    /// `detections`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub suppressed: Vec<Detection>,
+
+    /// Model that produced the result. Missing on results stored before it was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<ModelProvenance>,
+
+    /// Time spent running the model, in milliseconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inference_duration_ms: Option<u64>,
+
+    /// Detections as the model reported them, before a class policy dropped or
+    /// relabeled any. Privacy redaction matches these, so a policy can't hide a
+    /// redacted class from it. Only kept in memory, never stored.
+    #[serde(skip)]
+    pub model_detections: Option<Vec<Detection>>,
 }
 
 impl DetectionResult {
@@ -23,6 +43,10 @@ impl DetectionResult {
         Self {
             detections,
             embeddings: None,
+            suppressed: Vec::new(),
+            provenance: None,
+            inference_duration_ms: None,
+            model_detections: None,
         }
     }
 
@@ -31,6 +55,10 @@ impl DetectionResult {
         Self {
             detections,
             embeddings: Some(embeddings),
+            suppressed: Vec::new(),
+            provenance: None,
+            inference_duration_ms: None,
+            model_detections: None,
         }
     }
 
@@ -38,4 +66,10 @@ impl DetectionResult {
     pub fn has_embeddings(&self) -> bool {
         self.embeddings.is_some()
     }
+
+    /// True when nothing was detected. Captures whose detections were all
+    /// suppressed as static are not negatives, so what was suppressed is stored.
+    pub fn is_negative(&self) -> bool {
+        self.detections.is_empty() && self.suppressed.is_empty()
+    }
 }
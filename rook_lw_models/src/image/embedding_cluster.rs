@@ -0,0 +1,40 @@
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+
+use super::ImageInfo;
+
+/// A group of images with similar embeddings, such as recurring visits of the
+/// same animal.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
+pub struct EmbeddingCluster {
+    pub cluster_id: u32,
+
+    /// Normalized mean of the member embeddings, used to assign new images.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub centroid: Vec<f32>,
+
+    /// Number of images assigned to the cluster.
+    pub member_count: u32,
+
+    /// Member closest to the centroid.
+    pub representative_image_id: Option<String>,
+
+    pub created_timestamp: DateTime<FixedOffset>,
+
+    pub updated_timestamp: DateTime<FixedOffset>,
+}
+
+/// A cluster as listed by the admin API, without its centroid.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct EmbeddingClusterSummary {
+    pub cluster_id: u32,
+
+    pub member_count: u32,
+
+    /// Representative image, for a thumbnail.
+    pub representative_image: Option<ImageInfo>,
+
+    pub created_timestamp: DateTime<FixedOffset>,
+
+    pub updated_timestamp: DateTime<FixedOffset>,
+}
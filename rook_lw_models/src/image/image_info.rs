@@ -3,6 +3,7 @@ use serde::{Deserialize, Serialize};
 
 use super::MotionDetectionScore;
 use super::DetectionResult;
+use super::Detection;
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
 pub struct ImageInfo {
@@ -14,4 +15,30 @@ pub struct ImageInfo {
     pub capture_timestamp: chrono::DateTime<chrono::FixedOffset>,
     pub detection: Option<DetectionResult>,
     pub image_path: String,
+
+    /// Detections checked, and corrected where needed, by a person. Empty when
+    /// the image was verified to contain nothing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verified_detections: Option<Vec<Detection>>,
+
+    /// Set when the image was kept as a near-duplicate of an earlier image.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duplicate_of: Option<String>,
+
+    /// Set when regions of the image were obscured for privacy before it was stored.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub redacted: bool,
+
+    /// Set when nothing was detected in the image, and it was kept as a sample
+    /// of the motion the detector found nothing in.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub negative: bool,
+
+    /// How interesting the image is, from 0 to 1, when scored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interestingness: Option<f32>,
+
+    /// Embedding cluster the image belongs to, when assigned.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cluster_id: Option<u32>,
 }
@@ -2,6 +2,18 @@
 use chrono::{DateTime, FixedOffset};
 use serde::{Deserialize, Serialize};
 
+/// Order of search results.
+#[derive(Debug, Deserialize, Serialize, Default, PartialEq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageInfoSort {
+    /// Newest first.
+    #[default]
+    CaptureTimestamp,
+
+    /// Most interesting first, images without a score last.
+    Interestingness,
+}
+
 #[derive(Debug, Deserialize, Serialize, Default, PartialEq, Clone)]
 pub struct ImageInfoSearchOptions {
     pub start_date: Option<DateTime<FixedOffset>>,
@@ -12,7 +24,19 @@ pub struct ImageInfoSearchOptions {
     pub detection_classes: Vec<String>,
 
     pub detection_class_confidence: Option<f32>,
+
+    /// Only results from this model, see [`UNKNOWN_MODEL_NAME`](super::UNKNOWN_MODEL_NAME).
+    pub model_name: Option<String>,
+
+    /// Only images assigned to this embedding cluster.
+    pub cluster_id: Option<u32>,
+
+    /// Only negatives when true, only images with detections when false.
+    pub negative: Option<bool>,
     
+    #[serde(default)]
+    pub sort: ImageInfoSort,
+
     pub limit: Option<u32>,
     
     pub offset: Option<u32>,
@@ -1,11 +1,21 @@
 mod detection;
 mod detection_result;
+mod embedding_cluster;
+mod model_provenance;
 mod motion_detection_score;
+mod motion_region;
 mod image_info;
 mod image_info_search_options;
+mod similar_image;
+mod similarity_search_options;
 
 pub use detection::*;
 pub use detection_result::*;
+pub use embedding_cluster::*;
+pub use model_provenance::*;
 pub use motion_detection_score::*;
+pub use motion_region::*;
 pub use image_info::*;
-pub use image_info_search_options::*;
\ No newline at end of file
+pub use image_info_search_options::*;
+pub use similar_image::*;
+pub use similarity_search_options::*;
\ No newline at end of file
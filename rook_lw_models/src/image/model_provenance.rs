@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+
+/// Model name matching results stored without provenance.
+pub const UNKNOWN_MODEL_NAME: &str = "unknown";
+
+/// Which model produced a detection result, and how it was run.
+///
+/// Every field has a default so results stored by older versions still load.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
+pub struct ModelProvenance {
+    /// Object detector implementation, e.g. "yolov8" or "opencv".
+    pub detector_type: String,
+
+    /// Name of the model, e.g. the model file name ("yolov8n.onnx").
+    pub model_name: String,
+
+    /// Optional free-form version of the model.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model_version: Option<String>,
+
+    /// SHA-256 of the model file, as lowercase hex.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model_hash: Option<String>,
+
+    /// Model input size in pixels.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_width: Option<u32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_height: Option<u32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confidence_threshold: Option<f32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nms_threshold: Option<f32>,
+}
+
+impl ModelProvenance {
+    /// True when both results come from the same model.
+    ///
+    /// Hashes are compared when both sides have one, so a retrained model with
+    /// the same file name counts as a different model.
+    pub fn same_model(&self, other: &ModelProvenance) -> bool {
+        let same_hash = match (&self.model_hash, &other.model_hash) {
+            (Some(hash), Some(other_hash)) => hash == other_hash,
+            _ => true,
+        };
+        self.model_name == other.model_name && self.model_version == other.model_version && same_hash
+    }
+}
+
@@ -3,11 +3,17 @@ use std::{collections::HashMap, fmt};
 
 use serde::{Serialize, Deserialize};
 
+use super::MotionRegion;
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
 pub struct MotionDetectionScore {
     pub score: f32,
     pub detected: bool,
     pub properties: HashMap<String, String>,
+
+    /// Areas of the frame where motion was found, when the detector can tell.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub regions: Vec<MotionRegion>,
 }
 
 impl fmt::Display for MotionDetectionScore {
@@ -0,0 +1,22 @@
+
+use serde::{Deserialize, Serialize};
+
+/// A rectangular area of the frame where motion was detected.
+///
+/// Coordinates are in full-frame pixels, using the same convention as `Detection`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct MotionRegion {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+
+    /// Strongest motion score of the area that makes up this region.
+    pub score: f32,
+}
+
+impl MotionRegion {
+    pub fn area(&self) -> i64 {
+        self.width.max(0) as i64 * self.height.max(0) as i64
+    }
+}
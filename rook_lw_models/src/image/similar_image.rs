@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+use super::ImageInfo;
+
+/// An image found by embedding similarity search.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct SimilarImage {
+    /// Cosine similarity to the query image, from -1 to 1.
+    pub similarity: f32,
+
+    pub image_info: ImageInfo,
+}
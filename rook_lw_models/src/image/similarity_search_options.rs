@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize, Default, PartialEq, Clone)]
+pub struct SimilaritySearchOptions {
+    /// Number of images to return.
+    pub limit: Option<u32>,
+
+    /// Select candidates with the 8-bit quantized embeddings, then rank them
+    /// exactly. Faster on large repositories, but can miss close matches.
+    #[serde(default)]
+    pub quantized: bool,
+}
@@ -1,3 +1,5 @@
 mod process_info;
+mod stage_status;
 
-pub use process_info::*;
\ No newline at end of file
+pub use process_info::*;
+pub use stage_status::*;
\ No newline at end of file
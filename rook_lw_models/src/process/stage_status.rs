@@ -0,0 +1,37 @@
+use serde::{Serialize, Deserialize};
+
+use chrono::{DateTime, FixedOffset};
+
+/// Health of a pipeline stage.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum StageHealth {
+    #[default]
+    Starting,
+
+    /// Processing items without recent errors.
+    Running,
+
+    /// Running, but the last item failed.
+    Degraded,
+
+    /// Waiting out a backoff before the stage is reset.
+    Restarting,
+
+    /// Exited normally.
+    Stopped,
+
+    /// Exited with an error.
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct StageStatus {
+    pub name: String,
+    pub health: StageHealth,
+    pub processed_count: u64,
+    pub error_count: u64,
+    pub restart_count: u32,
+    pub last_error: Option<String>,
+    pub updated: Option<DateTime<FixedOffset>>,
+}
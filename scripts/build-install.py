@@ -12,6 +12,7 @@ project_dirs = [
     "rook_lw_admin",
     "rook_lw_admin_fe",
     "rook_lw_models",
+    "rook_lw_control",
     "rook_lw_model_dev",
 ]
 
@@ -10,6 +10,7 @@ project_dirs = [
     "rook_lw_admin_fe",
     "rook_lw_models",
     "rook_lw_image_repo",
+    "rook_lw_control",
 ]
 
 def cmd(dir, *command):